@@ -49,7 +49,16 @@
 //!# }
 //! ```
 
-pub use sycamore_state_core::{RcCollectionSignal, RefCollectionSignal};
+pub use sycamore_state_core::{
+    batch, is_batching, BatchNotifier, BatchSignal, BuildError, CollectionOp, CollectionPath,
+    MapEntries, MapOp, MapPath, NestedPatch, RcBTreeMapSignal, RcBTreeSetSignal, RcCollectionItem,
+    RcCollectionSignal, RcHashMapItem, RcHashMapSignal, RcHashSetSignal, RcIndexMapSignal,
+    RcMapItem, RcMapSignal, RcRingSignal, RcSelection, RcSetSignal, RcSortedCollectionSignal,
+    RcSortedItem, RefCollectionItem, RefCollectionSignal, SelectionItem, SelectionMode, SetEntries,
+    StateField, StatePatch, StatePath, StateSnapshot,
+};
+#[cfg(feature = "json-patch")]
+pub use sycamore_state_core::{json_patch, JsonPatchError, JsonPatchOp, JsonPointer};
 
 #[doc(inline)]
 /// Derive macro for State Management
@@ -60,7 +69,7 @@ pub use sycamore_state_core::{RcCollectionSignal, RefCollectionSignal};
 /// # use sycamore_state_manager::State;
 ///
 /// #[derive(Debug, State, Clone)]
-/// #[state(clone, eq, debug)] // avaliable derive macros are: (clone, debug, eq, ord)
+/// #[state(clone, eq, debug)] // avaliable derive macros are: (clone, debug, eq, ord), others with derive(..)
 /// pub struct MyState<'a> {
 ///     pub field_1: String,
 ///     pub field_2: u32,
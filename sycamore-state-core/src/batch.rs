@@ -0,0 +1,289 @@
+use indexmap::IndexMap;
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+    ptr,
+    rc::{Rc, Weak},
+};
+use sycamore::reactive::{ReadSignal, Signal, SignalEmitter};
+
+/// Subscriber list of a [`SignalEmitter`], keyed by the address of each effect callback
+type Subscribers = RefCell<IndexMap<*const RefCell<dyn FnMut()>, Weak<RefCell<dyn FnMut()>>>>;
+
+/// Fields of [`ReadSignal`], which keeps its emitter private
+#[allow(dead_code)]
+struct ReadSignalFields<T> {
+    value: RefCell<Rc<T>>,
+    emitter: SignalEmitter,
+}
+
+const _: () = assert!(std::mem::size_of::<SignalEmitter>() == std::mem::size_of::<Rc<Subscribers>>());
+
+/// Emitter notifying the subscribers of `signal`
+fn emitter<T>(signal: &Signal<T>) -> &SignalEmitter {
+    let signal: &ReadSignal<T> = signal;
+    // SAFETY: `ReadSignalFields` declares the same fields as `ReadSignal`, in the same order
+    unsafe { &(*(signal as *const ReadSignal<T>).cast::<ReadSignalFields<T>>()).emitter }
+}
+
+/// Subscribers of `emitter`
+fn subscribers(emitter: &SignalEmitter) -> &Subscribers {
+    // SAFETY: `SignalEmitter` is a newtype over the `Rc` of its subscriber list
+    unsafe { &*(emitter as *const SignalEmitter).cast::<Rc<Subscribers>>() }
+}
+
+thread_local! {
+    /// Emitters written in the outermost batch, [`None`] outside of a batch
+    static PENDING: RefCell<Option<Vec<SignalEmitter>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with notifications deferred until it returns
+///
+/// The subscribers of every signal written inside the batch run once at the end, no
+/// matter how many times it was written or how many of the written signals they read,
+/// nested batches join the outermost one
+///
+/// Deferred writes are the ones made with [`BatchSignal`], by the methods of the Rc
+/// collection, map, set, ring and sorted signals, the generated `set_*` field setters and
+/// [`BatchNotifier::notify`], which backs the `on_change` and `on_any_change` callbacks of
+/// the `State` derive. [`Signal::set`] itself is not batch aware and notifies right away
+///
+/// ```rust
+/// # use std::cell::Cell;
+/// # use std::rc::Rc;
+/// # use sycamore::reactive::{create_effect, create_rc_signal, create_scope};
+/// # use sycamore_state_core::{batch, BatchSignal};
+/// # let _ = create_scope(|cx| {
+/// let (first, second) = (create_rc_signal(0), create_rc_signal(0));
+/// let runs = Rc::new(Cell::new(0));
+/// create_effect(cx, {
+///     let (first, second, runs) = (first.clone(), second.clone(), runs.clone());
+///     move || {
+///         first.track();
+///         second.track();
+///         runs.set(runs.get() + 1);
+///     }
+/// });
+/// batch(|| {
+///     first.set_batched(1);
+///     first.set_batched(2);
+///     second.set_batched(3);
+///     assert_eq!(*first.get(), 2);
+///     assert_eq!(runs.get(), 1);
+/// });
+/// assert_eq!(runs.get(), 2);
+/// # });
+///```
+pub fn batch<R>(f: impl FnOnce() -> R) -> R {
+    let outermost = PENDING.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        if pending.is_some() {
+            false
+        } else {
+            *pending = Some(Vec::new());
+            true
+        }
+    });
+    if !outermost {
+        return f();
+    }
+
+    // Leaves the batch even if `f` panics, the pending notifications are dropped then
+    struct Reset;
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            PENDING.with(|pending| pending.borrow_mut().take());
+        }
+    }
+    let reset = Reset;
+    let result = f();
+    // Writes made by the subscribers are deferred and deduplicated again,
+    // so callbacks depending on several signals run once after all of them
+    loop {
+        let pending = PENDING.with(|pending| pending.borrow_mut().replace(Vec::new()));
+        let pending = pending.unwrap_or_default();
+        if pending.is_empty() {
+            break;
+        }
+        // Every list is taken before any callback runs, a callback subscribing again
+        // to the signals it read is not run a second time for the later ones
+        let mut callbacks = IndexMap::new();
+        for emitter in &pending {
+            // Reversed as in `SignalEmitter::trigger_subscribers`, outer effects run first
+            for (key, callback) in subscribers(emitter).take().into_iter().rev() {
+                callbacks.entry(key).or_insert(callback);
+            }
+        }
+        for callback in callbacks.into_values() {
+            if let Some(callback) = callback.upgrade() {
+                callback.borrow_mut()();
+            }
+        }
+    }
+    drop(reset);
+    result
+}
+
+/// Returns `true` when called inside [`batch`]
+pub fn is_batching() -> bool {
+    PENDING.with(|pending| pending.borrow().is_some())
+}
+
+/// Trigger the subscribers of `emitter` now, or once at the end of the batch when called
+/// inside [`batch`]
+fn defer(emitter: &SignalEmitter) {
+    let now = PENDING.with(|pending| match pending.borrow_mut().as_mut() {
+        Some(pending) => {
+            if !pending.iter().any(|a| ptr::eq(subscribers(a), subscribers(emitter))) {
+                pending.push(emitter.clone());
+            }
+            false
+        }
+        None => true,
+    });
+    if now {
+        emitter.trigger_subscribers();
+    }
+}
+
+/// Trigger the subscribers of `signal`, once at the end of the batch when called inside [`batch`]
+pub(crate) fn trigger<T>(signal: &Signal<T>) {
+    defer(emitter(signal));
+}
+
+/// Write `value` to `signal` and [`trigger`] its subscribers
+pub(crate) fn set<T>(signal: &Signal<T>, value: T) {
+    signal.set_silent(value);
+    trigger(signal);
+}
+
+/// Mutable handle to the value of a signal, written back with [`set`] when dropped
+pub(crate) struct Modify<'s, T> {
+    value: Option<T>,
+    signal: &'s Signal<T>,
+}
+
+/// Batch aware version of [`Signal::modify`]
+pub(crate) fn modify<T: Clone>(signal: &Signal<T>) -> Modify<'_, T> {
+    Modify {
+        value: Some(signal.get_untracked().as_ref().clone()),
+        signal,
+    }
+}
+
+impl<T> Deref for Modify<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().expect("value taken on drop")
+    }
+}
+
+impl<T> DerefMut for Modify<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().expect("value taken on drop")
+    }
+}
+
+impl<T> Drop for Modify<'_, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            set(self.signal, value);
+        }
+    }
+}
+
+/// Writes of a [`Signal`] joining the running [`batch`]
+///
+/// The value is written right away, the subscribers run once when the batch ends,
+/// outside of a batch these behave as [`Signal::set`] and [`Signal::modify`]. Also
+/// available on [`RcSignal`](sycamore::reactive::RcSignal) through `Deref`
+pub trait BatchSignal<T> {
+    /// Set the value, notifying the subscribers at the end of the running batch
+    fn set_batched(&self, value: T);
+
+    /// Update the value in place, notifying the subscribers at the end of the running batch
+    fn modify_batched(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone;
+}
+
+impl<T> BatchSignal<T> for Signal<T> {
+    fn set_batched(&self, value: T) {
+        set(self, value);
+    }
+
+    fn modify_batched(&self, f: impl FnOnce(&mut T))
+    where
+        T: Clone,
+    {
+        f(&mut modify(self));
+    }
+}
+
+/// Signal free notification which runs its subscribers once per [`batch`]
+///
+/// Effects subscribe to it with [`track`](BatchNotifier::track), clones share their subscribers
+#[derive(Debug, Clone, Default)]
+pub struct BatchNotifier(Rc<SignalEmitter>);
+
+impl BatchNotifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe the current reactive scope to the notifier
+    pub fn track(&self) {
+        self.0.track();
+    }
+
+    /// Run the subscribers, at the end of the batch when called inside [`batch`]
+    pub fn notify(&self) {
+        defer(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use sycamore::reactive::{create_effect, create_ref, create_scope_immediate, create_signal};
+
+    use super::{batch, emitter, subscribers, BatchSignal};
+
+    #[test]
+    pub fn emitter_subscribers_test() {
+        create_scope_immediate(|cx| {
+            let signal = create_signal(cx, 0);
+            assert!(subscribers(emitter(signal)).borrow().is_empty());
+            create_effect(cx, || signal.track());
+            create_effect(cx, || signal.track());
+            assert_eq!(subscribers(emitter(signal)).borrow().len(), 2);
+        });
+    }
+
+    #[test]
+    pub fn shared_subscriber_test() {
+        create_scope_immediate(|cx| {
+            let (first, second) = (create_signal(cx, 0), create_signal(cx, 0));
+            let runs = create_ref(cx, Cell::new(0));
+            create_effect(cx, || {
+                first.track();
+                second.track();
+                runs.set(runs.get() + 1);
+            });
+            batch(|| {
+                first.set_batched(1);
+                second.modify_batched(|a| *a += 1);
+                first.set(2);
+            });
+            assert_eq!(runs.get(), 3);
+            batch(|| {
+                first.set_batched(3);
+                second.set_batched(3);
+            });
+            assert_eq!(runs.get(), 4);
+            assert_eq!((*first.get(), *second.get()), (3, 3));
+        });
+    }
+}
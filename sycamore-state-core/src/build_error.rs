@@ -0,0 +1,19 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Error of the `build` method of a generated builder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The field was never given a value and has no `#[state(default = ..)]`
+    MissingField(&'static str),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingField(field) => write!(f, "missing field `{}`", field),
+        }
+    }
+}
+
+impl Error for BuildError {}
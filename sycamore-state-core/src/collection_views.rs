@@ -0,0 +1,375 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    ops::{Add, Sub},
+    rc::Rc,
+};
+
+use indexmap::IndexMap;
+use sycamore::reactive::{create_effect, create_signal_unsafe, untrack, ReadSignal, Scope, Signal};
+
+use crate::{RcCollectionItem, RefCollectionItem};
+
+/// Item handle of a collection signal that views can be built on
+pub(crate) trait ViewItem: Clone {
+    type Value;
+
+    /// Tracked value of the item
+    fn value(&self) -> Rc<Self::Value>;
+
+    /// Identity of the item, stable while it is in its collection
+    fn id(&self) -> *const ();
+}
+
+impl<T> ViewItem for RcCollectionItem<T> {
+    type Value = T;
+
+    fn value(&self) -> Rc<T> {
+        self.get()
+    }
+
+    fn id(&self) -> *const () {
+        let signal: &Signal<T> = self;
+        signal as *const Signal<T> as *const ()
+    }
+}
+
+impl<'a, T> ViewItem for RefCollectionItem<'a, T> {
+    type Value = T;
+
+    fn value(&self) -> Rc<T> {
+        self.get()
+    }
+
+    fn id(&self) -> *const () {
+        let signal: &Signal<T> = self;
+        signal as *const Signal<T> as *const ()
+    }
+}
+
+/// Results computed from item values, recomputed only for items whose value changed
+///
+/// The cached [`Rc`](Rc) keeps the previous value alive so a changed item
+/// can never be mistaken for an unchanged one
+struct ItemCache<T, V> {
+    entries: HashMap<*const (), (Rc<T>, V)>,
+}
+
+impl<T, V: Clone> ItemCache<T, V> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Subscribes to every item and returns its cached result, entries of removed items are dropped
+    fn resolve<I: ViewItem<Value = T>>(
+        &mut self,
+        items: &[I],
+        mut f: impl FnMut(&T) -> V,
+    ) -> Vec<(I, V)> {
+        let mut entries = HashMap::with_capacity(items.len());
+        let resolved = items
+            .iter()
+            .map(|item| {
+                let value = item.value();
+                let result = match self.entries.remove(&item.id()) {
+                    Some((cached, result)) if Rc::ptr_eq(&cached, &value) => result,
+                    _ => f(&value),
+                };
+                entries.insert(item.id(), (value, result.clone()));
+                (item.clone(), result)
+            })
+            .collect();
+        self.entries = entries;
+        resolved
+    }
+
+    /// Subscribes to every item and calls `on_change` with the previous and the new result
+    /// of every item that was added, changed or removed
+    fn update<I: ViewItem<Value = T>>(
+        &mut self,
+        items: &[I],
+        mut f: impl FnMut(&T) -> V,
+        mut on_change: impl FnMut(Option<V>, Option<&V>),
+    ) {
+        let mut entries = HashMap::with_capacity(items.len());
+        for item in items {
+            let value = item.value();
+            let entry = match self.entries.remove(&item.id()) {
+                Some(entry) if Rc::ptr_eq(&entry.0, &value) => entry,
+                previous => {
+                    let result = f(&value);
+                    on_change(previous.map(|(_, result)| result), Some(&result));
+                    (value, result)
+                }
+            };
+            entries.insert(item.id(), entry);
+        }
+        for (_, (_, result)) in std::mem::replace(&mut self.entries, entries) {
+            on_change(Some(result), None);
+        }
+    }
+}
+
+/// Memo that may hold values borrowing from the scope
+fn create_view<'a, U: 'a>(cx: Scope<'a>, mut f: impl FnMut() -> U + 'a) -> &'a ReadSignal<U> {
+    let view = unsafe { create_signal_unsafe(cx, untrack(&mut f)) };
+    create_effect(cx, move || view.set(f()));
+    view
+}
+
+/// Memo that only notifies its subscribers when `eq` tells the value changed
+fn create_aggregate<'a, U: 'a>(
+    cx: Scope<'a>,
+    mut f: impl FnMut() -> U + 'a,
+    eq: impl Fn(&U, &U) -> bool + 'a,
+) -> &'a ReadSignal<U> {
+    let aggregate = unsafe { create_signal_unsafe(cx, untrack(&mut f)) };
+    create_effect(cx, move || {
+        let value = f();
+        if !eq(&aggregate.get_untracked(), &value) {
+            aggregate.set(value);
+        }
+    });
+    aggregate
+}
+
+pub(crate) fn filter_view<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    predicate: impl Fn(&I::Value) -> bool + 'a,
+) -> &'a ReadSignal<Vec<I>> {
+    let mut cache = ItemCache::new();
+    create_view(cx, move || {
+        cache
+            .resolve(&source(), &predicate)
+            .into_iter()
+            .filter_map(|(item, keep)| keep.then_some(item))
+            .collect()
+    })
+}
+
+pub(crate) fn map_view<'a, I: ViewItem + 'a, U: Clone + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    f: impl Fn(&I::Value) -> U + 'a,
+) -> &'a ReadSignal<Vec<U>> {
+    let mut cache = ItemCache::new();
+    create_view(cx, move || {
+        cache
+            .resolve(&source(), &f)
+            .into_iter()
+            .map(|(_, mapped)| mapped)
+            .collect()
+    })
+}
+
+pub(crate) fn sorted_view<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    compare: impl Fn(&I::Value, &I::Value) -> Ordering + 'a,
+) -> &'a ReadSignal<Vec<I>> {
+    let mut previous = Vec::<I>::new();
+    create_view(cx, move || {
+        let items = source();
+        let values = items
+            .iter()
+            .map(|item| (item.id(), item.value()))
+            .collect::<HashMap<_, _>>();
+
+        // Starting from the previous order keeps the sort close to linear
+        let known = previous.iter().map(|a| a.id()).collect::<HashSet<_>>();
+        let mut sorted = previous
+            .drain(..)
+            .filter(|a| values.contains_key(&a.id()))
+            .chain(items.iter().filter(|a| !known.contains(&a.id())).cloned())
+            .collect::<Vec<_>>();
+        sorted.sort_by(|a, b| compare(&values[&a.id()], &values[&b.id()]));
+        previous = sorted.clone();
+        sorted
+    })
+}
+
+pub(crate) fn group_by<'a, I: ViewItem + 'a, K: Hash + Eq + Clone + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    key: impl Fn(&I::Value) -> K + 'a,
+) -> &'a ReadSignal<IndexMap<K, &'a ReadSignal<Vec<I>>>> {
+    let groups: &'a Signal<IndexMap<K, &'a ReadSignal<Vec<I>>>> =
+        unsafe { create_signal_unsafe(cx, IndexMap::new()) };
+    // Every key keeps its signal, so a group that empties and comes back is the same view
+    let mut signals = HashMap::<K, &'a Signal<Vec<I>>>::new();
+    let mut cache = ItemCache::new();
+    create_effect(cx, move || {
+        let mut grouped = IndexMap::<K, Vec<I>>::new();
+        for (item, key) in cache.resolve(&source(), &key) {
+            grouped.entry(key).or_default().push(item);
+        }
+        untrack(|| {
+            for (key, signal) in signals.iter() {
+                if !grouped.contains_key(key) && !signal.get_untracked().is_empty() {
+                    signal.set(Vec::new());
+                }
+            }
+            let mut next = IndexMap::<K, &'a ReadSignal<Vec<I>>>::new();
+            for (key, items) in grouped {
+                let signal: &'a ReadSignal<Vec<I>> = match signals.get(&key) {
+                    Some(signal) => {
+                        let current = signal.get_untracked();
+                        let same = current.len() == items.len()
+                            && current.iter().zip(&items).all(|(a, b)| a.id() == b.id());
+                        if !same {
+                            signal.set(items);
+                        }
+                        signal
+                    }
+                    None => {
+                        let signal = unsafe { create_signal_unsafe(cx, items) };
+                        signals.insert(key.clone(), signal);
+                        signal
+                    }
+                };
+                next.insert(key, signal);
+            }
+            let current = groups.get_untracked();
+            let same = current.len() == next.len()
+                && current
+                    .iter()
+                    .zip(&next)
+                    .all(|((a, a_signal), (b, b_signal))| {
+                        a == b && std::ptr::eq(*a_signal, *b_signal)
+                    });
+            if !same {
+                groups.set(next);
+            }
+        });
+    });
+    groups
+}
+
+pub(crate) fn page<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    offset: usize,
+    len: usize,
+) -> &'a ReadSignal<Vec<I>> {
+    create_view(cx, move || {
+        source().iter().skip(offset).take(len).cloned().collect()
+    })
+}
+
+pub(crate) fn len<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+) -> &'a ReadSignal<usize> {
+    create_aggregate(cx, move || source().len(), PartialEq::eq)
+}
+
+pub(crate) fn count_where<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    predicate: impl Fn(&I::Value) -> bool + 'a,
+) -> &'a ReadSignal<usize> {
+    let mut cache = ItemCache::new();
+    let mut count = 0;
+    create_aggregate(
+        cx,
+        move || {
+            cache.update(&source(), &predicate, |previous, next| {
+                count -= usize::from(previous == Some(true));
+                count += usize::from(next == Some(&true));
+            });
+            count
+        },
+        PartialEq::eq,
+    )
+}
+
+pub(crate) fn sum_by<'a, I: ViewItem + 'a, S>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    f: impl Fn(&I::Value) -> S + 'a,
+) -> &'a ReadSignal<S>
+where
+    S: Add<Output = S> + Sub<Output = S> + Default + Clone + PartialEq + 'a,
+{
+    let mut cache = ItemCache::new();
+    let mut total = S::default();
+    create_aggregate(
+        cx,
+        move || {
+            cache.update(&source(), &f, |previous, next| {
+                if let Some(previous) = previous {
+                    total = std::mem::take(&mut total) - previous;
+                }
+                if let Some(next) = next {
+                    total = std::mem::take(&mut total) + next.clone();
+                }
+            });
+            total.clone()
+        },
+        PartialEq::eq,
+    )
+}
+
+/// Only compares the items whose value changed against the current extremum,
+/// the whole collection is scanned again when the extremum itself changed,
+/// was removed or when the items were reordered
+pub(crate) fn extremum_by<'a, I: ViewItem + 'a>(
+    cx: Scope<'a>,
+    source: impl Fn() -> Rc<Vec<I>> + 'a,
+    compare: impl Fn(&I::Value, &I::Value) -> Ordering + 'a,
+    wanted: Ordering,
+) -> &'a ReadSignal<Option<Rc<I::Value>>> {
+    let mut seen: HashMap<*const (), (Rc<I::Value>, usize)> = HashMap::new();
+    let mut best: Option<*const ()> = None;
+    create_aggregate(
+        cx,
+        move || {
+            let items = source();
+            let values = items.iter().map(ViewItem::value).collect::<Vec<_>>();
+            let (mut changed, mut current, mut reordered, mut last) = (vec![], None, false, None);
+            let mut next_seen = HashMap::with_capacity(items.len());
+            for (index, (item, value)) in items.iter().zip(&values).enumerate() {
+                match seen.get(&item.id()) {
+                    Some((cached, previous)) if Rc::ptr_eq(cached, value) => {
+                        reordered |= last.is_some_and(|last| *previous < last);
+                        last = Some(*previous);
+                        if best == Some(item.id()) {
+                            current = Some(index);
+                        }
+                    }
+                    _ => changed.push(index),
+                }
+                next_seen.insert(item.id(), (value.clone(), index));
+            }
+            seen = next_seen;
+
+            let better = |index: usize, best: usize| {
+                let ordering = compare(&values[index], &values[best]);
+                ordering == wanted || (ordering == Ordering::Equal && index < best)
+            };
+            let winner = match current {
+                Some(current) if !reordered => {
+                    Some(changed.into_iter().fold(current, |best, index| {
+                        if better(index, best) {
+                            index
+                        } else {
+                            best
+                        }
+                    }))
+                }
+                _ => (0..values.len())
+                    .reduce(|best, index| if better(index, best) { index } else { best }),
+            };
+            best = winner.map(|index| items[index].id());
+            winner.map(|index| values[index].clone())
+        },
+        |a, b| match (a, b) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        },
+    )
+}
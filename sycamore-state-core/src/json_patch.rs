@@ -0,0 +1,593 @@
+//! [RFC 6902](https://www.rfc-editor.org/rfc/rfc6902) JSON Patch operations applied to
+//! and emitted from states, enabled with the `json-patch` feature
+//!
+//! Paths are JSON Pointers over the Rust names of the fields, `#[state]` children,
+//! collection indices and map keys. The `State` derive implements [`JsonPointer`]
+//! and adds `apply_json_patch` and `json_diff` to the Rc and Ref types.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeOwned, IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use sycamore::reactive::Signal;
+
+use crate::batch::{batch, BatchSignal};
+
+#[doc(hidden)]
+pub use serde;
+#[doc(hidden)]
+pub use serde_json;
+
+/// Operation of a JSON Patch document, serialized as in RFC 6902
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum JsonPatchOp {
+    /// Insert `value` into a collection or map, or replace the value of a field
+    Add { path: String, value: Value },
+    /// Remove the item or entry at `path`
+    Remove { path: String },
+    /// Replace the value at `path`, which must exist
+    Replace { path: String, value: Value },
+    /// Remove the value at `from` and add it at `path`
+    Move { from: String, path: String },
+    /// Add the value at `from` at `path`
+    Copy { from: String, path: String },
+    /// Check the value at `path` is `value`
+    Test { path: String, value: Value },
+}
+
+impl JsonPatchOp {
+    /// Name of the operation, as found in its `op` member
+    pub fn name(&self) -> &'static str {
+        match self {
+            JsonPatchOp::Add { .. } => "add",
+            JsonPatchOp::Remove { .. } => "remove",
+            JsonPatchOp::Replace { .. } => "replace",
+            JsonPatchOp::Move { .. } => "move",
+            JsonPatchOp::Copy { .. } => "copy",
+            JsonPatchOp::Test { .. } => "test",
+        }
+    }
+}
+
+/// Error of `apply_json_patch`, naming the path of the operation which failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonPatchError {
+    /// The path is not a JSON Pointer
+    InvalidPointer(String),
+    /// The path names no field, item or entry of the state
+    NotFound(String),
+    /// The operation can't be applied at the path, as removing a field of a struct
+    Unsupported { op: &'static str, path: String },
+    /// The value doesn't match the type at the path
+    InvalidValue { path: String, message: String },
+    /// A `test` operation found another value at the path
+    TestFailed(String),
+}
+
+impl Display for JsonPatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPatchError::InvalidPointer(path) => write!(f, "invalid JSON pointer `{}`", path),
+            JsonPatchError::NotFound(path) => write!(f, "nothing found at `{}`", path),
+            JsonPatchError::Unsupported { op, path } => {
+                write!(f, "`{}` is not supported at `{}`", op, path)
+            }
+            JsonPatchError::InvalidValue { path, message } => {
+                write!(f, "invalid value at `{}`: {}", path, message)
+            }
+            JsonPatchError::TestFailed(path) => write!(f, "test failed at `{}`", path),
+        }
+    }
+}
+
+impl Error for JsonPatchError {}
+
+/// Change made at a path by [`JsonPointer::update_pointer`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PointerUpdate {
+    Add(Value),
+    Replace(Value),
+    Remove,
+}
+
+/// Error of a [`JsonPointer`], turned into a [`JsonPatchError`] naming the path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    NotFound,
+    Unsupported,
+    InvalidValue(String),
+}
+
+/// States resolving JSON Pointers, implemented by the `State` derive
+///
+/// Paths are given as unescaped segments, relative to the state
+pub trait JsonPointer {
+    /// Scope new items and nested states are created in, `()` for Rc states
+    type Scope: Copy;
+
+    /// JSON value at `path`, read untracked
+    fn get_pointer(&self, path: &[String]) -> Result<Value, PointerError>;
+
+    /// Apply `update` at `path`, the signals are set with [`BatchSignal`]
+    fn update_pointer(
+        &self,
+        cx: Self::Scope,
+        path: &[String],
+        update: PointerUpdate,
+    ) -> Result<(), PointerError>;
+}
+
+/// Apply the operations of `patch` to `state` in a single [`batch`]
+///
+/// Paths and `test` operations are checked against a copy of the current values
+/// first, a patch naming a missing path or failing a test leaves the state unchanged.
+/// A value of the wrong type is only found when written, the operations before it stay applied.
+pub fn apply<S: JsonPointer + ?Sized>(
+    state: &S,
+    cx: S::Scope,
+    patch: &[JsonPatchOp],
+) -> Result<(), JsonPatchError> {
+    let document = Document(
+        state
+            .get_pointer(&[])
+            .map_err(|error| patch_error(error, "get", ""))?
+            .into(),
+    );
+    for op in patch {
+        apply_op(&document, (), op)?;
+    }
+    batch(|| patch.iter().try_for_each(|op| apply_op(state, cx, op)))
+}
+
+/// Operations turning `old` into `new`, objects and arrays are compared member by member
+///
+/// Arrays are updated index by index, then grown or shrunk at their end.
+/// A single entry object whose key changes, as serde writes a switched enum variant, is replaced
+pub fn diff(old: &Value, new: &Value) -> Vec<JsonPatchOp> {
+    let mut ops = Vec::new();
+    diff_at(&mut Vec::new(), old, new, &mut ops);
+    ops
+}
+
+/// Operations turning the current values of `state` into the plain value `new`
+pub fn diff_state<S: JsonPointer + ?Sized, T: Serialize + ?Sized>(
+    state: &S,
+    new: &T,
+) -> Result<Vec<JsonPatchOp>, JsonPatchError> {
+    let old = state
+        .get_pointer(&[])
+        .map_err(|error| patch_error(error, "get", ""))?;
+    let new = to_json(new).map_err(|error| patch_error(error, "get", ""))?;
+    Ok(diff(&old, &new))
+}
+
+fn diff_at(path: &mut Vec<String>, old: &Value, new: &Value, ops: &mut Vec<JsonPatchOp>) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Value::Object(old), Value::Object(new))
+            if old.len() != 1 || new.len() != 1 || old.keys().eq(new.keys()) =>
+        {
+            for (key, old) in old {
+                path.push(key.clone());
+                match new.get(key) {
+                    Some(new) => diff_at(path, old, new, ops),
+                    None => ops.push(JsonPatchOp::Remove {
+                        path: format_pointer(path),
+                    }),
+                }
+                path.pop();
+            }
+            for (key, new) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                path.push(key.clone());
+                ops.push(JsonPatchOp::Add {
+                    path: format_pointer(path),
+                    value: new.clone(),
+                });
+                path.pop();
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for (index, (old, new)) in old.iter().zip(new).enumerate() {
+                path.push(index.to_string());
+                diff_at(path, old, new, ops);
+                path.pop();
+            }
+            for (index, new) in new.iter().enumerate().skip(old.len()) {
+                path.push(index.to_string());
+                ops.push(JsonPatchOp::Add {
+                    path: format_pointer(path),
+                    value: new.clone(),
+                });
+                path.pop();
+            }
+            for index in (new.len()..old.len()).rev() {
+                path.push(index.to_string());
+                ops.push(JsonPatchOp::Remove {
+                    path: format_pointer(path),
+                });
+                path.pop();
+            }
+        }
+        _ => ops.push(JsonPatchOp::Replace {
+            path: format_pointer(path),
+            value: new.clone(),
+        }),
+    }
+}
+
+fn apply_op<S: JsonPointer + ?Sized>(
+    state: &S,
+    cx: S::Scope,
+    op: &JsonPatchOp,
+) -> Result<(), JsonPatchError> {
+    let name = op.name();
+    let get = |path: &str| {
+        let segments = parse_pointer(path)?;
+        state
+            .get_pointer(&segments)
+            .map_err(|error| patch_error(error, name, path))
+    };
+    let update = |path: &str, update: PointerUpdate| {
+        let segments = parse_pointer(path)?;
+        state
+            .update_pointer(cx, &segments, update)
+            .map_err(|error| patch_error(error, name, path))
+    };
+    match op {
+        JsonPatchOp::Add { path, value } => update(path, PointerUpdate::Add(value.clone())),
+        JsonPatchOp::Remove { path } => update(path, PointerUpdate::Remove),
+        JsonPatchOp::Replace { path, value } => update(path, PointerUpdate::Replace(value.clone())),
+        JsonPatchOp::Move { from, path } => {
+            let (source, target) = (parse_pointer(from)?, parse_pointer(path)?);
+            if target.len() > source.len() && target.starts_with(&source) {
+                return Err(patch_error(PointerError::Unsupported, name, path));
+            }
+            let value = get(from)?;
+            if source != target {
+                update(from, PointerUpdate::Remove)?;
+                update(path, PointerUpdate::Add(value))?;
+            }
+            Ok(())
+        }
+        JsonPatchOp::Copy { from, path } => update(path, PointerUpdate::Add(get(from)?)),
+        JsonPatchOp::Test { path, value } => {
+            if get(path)? == *value {
+                Ok(())
+            } else {
+                Err(JsonPatchError::TestFailed(path.clone()))
+            }
+        }
+    }
+}
+
+fn patch_error(error: PointerError, op: &'static str, path: &str) -> JsonPatchError {
+    let path = path.to_owned();
+    match error {
+        PointerError::NotFound => JsonPatchError::NotFound(path),
+        PointerError::Unsupported => JsonPatchError::Unsupported { op, path },
+        PointerError::InvalidValue(message) => JsonPatchError::InvalidValue { path, message },
+    }
+}
+
+/// Plain JSON copy of a state, the patch is tried on it before the state is written
+struct Document(std::cell::RefCell<Value>);
+
+impl JsonPointer for Document {
+    type Scope = ();
+
+    fn get_pointer(&self, path: &[String]) -> Result<Value, PointerError> {
+        get_value(self.0.borrow().clone(), path)
+    }
+
+    fn update_pointer(
+        &self,
+        _: (),
+        path: &[String],
+        update: PointerUpdate,
+    ) -> Result<(), PointerError> {
+        update_value(&mut self.0.borrow_mut(), path, update)
+    }
+}
+
+/// Unescaped segments of a JSON Pointer, empty for the whole document
+pub fn parse_pointer(pointer: &str) -> Result<Vec<String>, JsonPatchError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let invalid = || JsonPatchError::InvalidPointer(pointer.to_owned());
+    let rest = pointer.strip_prefix('/').ok_or_else(invalid)?;
+    rest.split('/')
+        .map(|segment| {
+            let mut unescaped = String::with_capacity(segment.len());
+            let mut chars = segment.chars();
+            while let Some(c) = chars.next() {
+                unescaped.push(match (c, chars.clone().next()) {
+                    ('~', Some('0')) => '~',
+                    ('~', Some('1')) => '/',
+                    ('~', _) => return Err(invalid()),
+                    (c, _) => c,
+                });
+                if c == '~' {
+                    chars.next();
+                }
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// JSON Pointer of the segments, escaping `~` and `/`
+pub fn format_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Index named by `segment` in an array of `len` items
+///
+/// With `append` the index may be `len`, also named by `-`
+pub fn index(segment: &str, len: usize, append: bool) -> Result<usize, PointerError> {
+    let index = if segment == "-" {
+        len
+    } else if segment == "0" || !segment.starts_with('0') {
+        segment.parse().map_err(|_| PointerError::NotFound)?
+    } else {
+        return Err(PointerError::NotFound);
+    };
+    if index < len || (append && index == len) {
+        Ok(index)
+    } else {
+        Err(PointerError::NotFound)
+    }
+}
+
+/// JSON value of a plain value
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<Value, PointerError> {
+    serde_json::to_value(value).map_err(|error| PointerError::InvalidValue(error.to_string()))
+}
+
+/// Plain value of a JSON value
+pub fn from_json<T: DeserializeOwned>(value: Value) -> Result<T, PointerError> {
+    serde_json::from_value(value).map_err(|error| PointerError::InvalidValue(error.to_string()))
+}
+
+/// Value at `path` inside `value`
+pub fn get_value(mut value: Value, path: &[String]) -> Result<Value, PointerError> {
+    for segment in path {
+        value = match value {
+            Value::Object(mut map) => map.remove(segment).ok_or(PointerError::NotFound)?,
+            Value::Array(mut items) => {
+                let index = index(segment, items.len(), false)?;
+                items.swap_remove(index)
+            }
+            _ => return Err(PointerError::NotFound),
+        };
+    }
+    Ok(value)
+}
+
+/// Apply `update` at `path` inside `value`, as RFC 6902 does on a JSON document
+pub fn update_value(
+    value: &mut Value,
+    path: &[String],
+    update: PointerUpdate,
+) -> Result<(), PointerError> {
+    let Some((last, parent)) = path.split_last() else {
+        return match update {
+            PointerUpdate::Add(new) | PointerUpdate::Replace(new) => {
+                *value = new;
+                Ok(())
+            }
+            PointerUpdate::Remove => Err(PointerError::Unsupported),
+        };
+    };
+    let mut target = value;
+    for segment in parent {
+        target = match target {
+            Value::Object(map) => map.get_mut(segment),
+            Value::Array(items) => {
+                let index = index(segment, items.len(), false)?;
+                items.get_mut(index)
+            }
+            _ => None,
+        }
+        .ok_or(PointerError::NotFound)?;
+    }
+    match (target, update) {
+        (Value::Object(map), PointerUpdate::Add(new)) => {
+            map.insert(last.clone(), new);
+        }
+        (Value::Object(map), PointerUpdate::Replace(new)) => {
+            *map.get_mut(last).ok_or(PointerError::NotFound)? = new;
+        }
+        (Value::Object(map), PointerUpdate::Remove) => {
+            map.remove(last).ok_or(PointerError::NotFound)?;
+        }
+        (Value::Array(items), PointerUpdate::Add(new)) => {
+            let index = index(last, items.len(), true)?;
+            items.insert(index, new);
+        }
+        (Value::Array(items), PointerUpdate::Replace(new)) => {
+            let index = index(last, items.len(), false)?;
+            items[index] = new;
+        }
+        (Value::Array(items), PointerUpdate::Remove) => {
+            let index = index(last, items.len(), false)?;
+            items.remove(index);
+        }
+        _ => return Err(PointerError::NotFound),
+    }
+    Ok(())
+}
+
+/// Apply `update` at `path` inside a plain value written with `write`
+///
+/// `current` is only serialized when the path goes below the value,
+/// the whole value is then deserialized again and written back
+pub fn update_plain<T: DeserializeOwned>(
+    current: impl FnOnce() -> Result<Value, PointerError>,
+    path: &[String],
+    update: PointerUpdate,
+    write: impl FnOnce(T),
+) -> Result<(), PointerError> {
+    let value = match (path.is_empty(), update) {
+        (true, PointerUpdate::Add(value) | PointerUpdate::Replace(value)) => value,
+        (true, PointerUpdate::Remove) => return Err(PointerError::Unsupported),
+        (false, update) => {
+            let mut value = current()?;
+            update_value(&mut value, path, update)?;
+            value
+        }
+    };
+    write(from_json(value)?);
+    Ok(())
+}
+
+/// Value at `path` inside the value of `signal`
+pub fn get_signal<T: Serialize>(
+    signal: &Signal<T>,
+    path: &[String],
+) -> Result<Value, PointerError> {
+    get_value(to_json(&*signal.get_untracked())?, path)
+}
+
+/// Apply `update` at `path` inside the value of `signal`, set with [`BatchSignal`]
+pub fn update_signal<T: Serialize + DeserializeOwned>(
+    signal: &Signal<T>,
+    path: &[String],
+    update: PointerUpdate,
+) -> Result<(), PointerError> {
+    update_plain(
+        || to_json(&*signal.get_untracked()),
+        path,
+        update,
+        |value| signal.set_batched(value),
+    )
+}
+
+/// Set `signal` with [`BatchSignal`], reached through `Deref` from the Rc types
+pub fn set_signal<T>(signal: &Signal<T>, value: T) {
+    signal.set_batched(value);
+}
+
+/// Map key named by `segment`, read as a JSON object key so numeric keys work,
+/// a segment which isn't a key is not found
+pub fn key_from_segment<K: DeserializeOwned>(segment: &str) -> Result<K, PointerError> {
+    let mut map = Map::new();
+    map.insert(segment.to_owned(), Value::Null);
+    serde_json::from_value::<FirstKey<K>>(Value::Object(map))
+        .map(|key| key.0)
+        .map_err(|_| PointerError::NotFound)
+}
+
+/// Segment naming the map key, as written as a JSON object key
+pub fn key_to_segment<K: Serialize>(key: &K) -> Result<String, PointerError> {
+    match to_json(&SingleEntry(key))? {
+        Value::Object(map) => map.into_iter().next().map(|(key, _)| key),
+        _ => None,
+    }
+    .ok_or_else(|| PointerError::InvalidValue("map key is not a string".into()))
+}
+
+/// Map holding a single entry under the key
+struct SingleEntry<'a, K>(&'a K);
+
+impl<K: Serialize> Serialize for SingleEntry<'_, K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, &())?;
+        map.end()
+    }
+}
+
+/// First key of a map
+struct FirstKey<K>(K);
+
+impl<'de, K: Deserialize<'de>> Deserialize<'de> for FirstKey<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor<K>(PhantomData<K>);
+
+        impl<'de, K: Deserialize<'de>> Visitor<'de> for KeyVisitor<K> {
+            type Value = FirstKey<K>;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a map with a key")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let key = map
+                    .next_key()?
+                    .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                map.next_value::<IgnoredAny>()?;
+                Ok(FirstKey(key))
+            }
+        }
+
+        deserializer.deserialize_map(KeyVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn segments(pointer: &str) -> Vec<String> {
+        parse_pointer(pointer).expect("valid pointer")
+    }
+
+    #[test]
+    pub fn pointer_escape_test() {
+        assert_eq!(segments(""), Vec::<String>::new());
+        assert_eq!(segments("/a~1b/c~0d/"), vec!["a/b", "c~d", ""]);
+        assert_eq!(format_pointer(&segments("/a~1b/c~0d")), "/a~1b/c~0d");
+        assert!(parse_pointer("a").is_err());
+        assert!(parse_pointer("/a~2").is_err());
+        assert_eq!(index("-", 2, true), Ok(2));
+        assert_eq!(index("01", 2, false), Err(PointerError::NotFound));
+        assert_eq!(key_from_segment::<u32>("12"), Ok(12));
+        assert_eq!(key_to_segment(&12u32), Ok("12".into()));
+    }
+
+    #[test]
+    pub fn value_diff_test() {
+        let old = json!({ "a": 1, "b": [1, 2, 3], "c": { "d": true } });
+        let new = json!({ "a": 2, "b": [1, 5], "e": null });
+        let ops = diff(&old, &new);
+        let mut patched = old.clone();
+        for op in &ops {
+            let (path, update) = match op {
+                JsonPatchOp::Add { path, value } => (path, PointerUpdate::Add(value.clone())),
+                JsonPatchOp::Replace { path, value } => {
+                    (path, PointerUpdate::Replace(value.clone()))
+                }
+                JsonPatchOp::Remove { path } => (path, PointerUpdate::Remove),
+                op => panic!("unexpected {:?}", op),
+            };
+            update_value(&mut patched, &segments(path), update).expect("applied");
+        }
+        assert_eq!(patched, new);
+        assert_eq!(
+            serde_json::to_value(&ops[0]).expect("serialized"),
+            json!({ "op": "replace", "path": "/a", "value": 2 })
+        );
+        let ops = diff(
+            &json!({ "s": { "Idle": 1 } }),
+            &json!({ "s": { "Busy": 2 } }),
+        );
+        assert_eq!(
+            ops,
+            vec![JsonPatchOp::Replace {
+                path: "/s".into(),
+                value: json!({ "Busy": 2 })
+            }]
+        );
+    }
+}
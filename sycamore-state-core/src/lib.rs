@@ -1,9 +1,40 @@
+mod batch;
+mod build_error;
+mod collection_views;
+#[cfg(feature = "json-patch")]
+pub mod json_patch;
+mod rc_btreemap_signal;
 mod rc_collection_signal;
 mod rc_hashmap_signal;
+mod rc_indexmap_signal;
+mod rc_map_signal;
+mod rc_ring_signal;
+mod rc_selection;
+mod rc_set_signal;
+mod rc_sorted_collection_signal;
 mod ref_collection_signal;
+mod state_field;
+mod state_patch;
+mod state_path;
+mod state_snapshot;
 
-pub use rc_collection_signal::RcCollectionSignal;
+pub use batch::{batch, is_batching, BatchNotifier, BatchSignal};
+pub use build_error::BuildError;
+#[cfg(feature = "json-patch")]
+pub use json_patch::{JsonPatchError, JsonPatchOp, JsonPointer};
+pub use rc_btreemap_signal::RcBTreeMapSignal;
+pub use rc_collection_signal::{RcCollectionItem, RcCollectionSignal};
 pub use rc_hashmap_signal::{RcHashMapItem, RcHashMapSignal};
-pub use ref_collection_signal::RefCollectionSignal;
+pub use rc_indexmap_signal::RcIndexMapSignal;
+pub use rc_map_signal::{MapEntries, RcMapItem, RcMapSignal};
+pub use rc_ring_signal::RcRingSignal;
+pub use rc_selection::{RcSelection, SelectionItem, SelectionMode};
+pub use rc_set_signal::{RcBTreeSetSignal, RcHashSetSignal, RcSetSignal, SetEntries};
+pub use rc_sorted_collection_signal::{RcSortedCollectionSignal, RcSortedItem};
+pub use ref_collection_signal::{RefCollectionItem, RefCollectionSignal};
+pub use state_field::StateField;
+pub use state_patch::{CollectionOp, MapOp, NestedPatch, StatePatch};
+pub use state_path::{CollectionPath, MapPath, StatePath};
+pub use state_snapshot::StateSnapshot;
 
 pub trait State {}
@@ -0,0 +1,66 @@
+use std::{collections::BTreeMap, ops::RangeBounds};
+
+use crate::{RcMapItem, RcMapSignal};
+
+/// Wrapper type for [`RcSignal`](sycamore::reactive::RcSignal)<[`BTreeMap`](BTreeMap)<`K`, [`RcMapItem`](RcMapItem)<`V`>>>
+///
+/// Iterates in key order
+pub type RcBTreeMapSignal<'a, K, V> = RcMapSignal<'a, K, V, BTreeMap<K, RcMapItem<'a, V>>>;
+
+impl<'a, K: Ord + Clone + 'a, V: 'a> RcMapSignal<'a, K, V, BTreeMap<K, RcMapItem<'a, V>>> {
+    /// Get the entries with a key inside `range`, in key order
+    ///
+    /// Subscribes to the map when called inside a reactive scope
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcBTreeMapSignal;
+    /// let map = (0..10).map(|a| (a, a * 10)).collect::<RcBTreeMapSignal<_, _>>();
+    /// let values = map.range(2..5).into_iter().map(|(_, v)| *v.get()).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![20, 30, 40]);
+    ///```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Vec<(K, RcMapItem<'a, V>)> {
+        (**self)
+            .get()
+            .range(range)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Entry with the smallest key
+    ///
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn first(&self) -> Option<(K, RcMapItem<'a, V>)> {
+        (**self)
+            .get()
+            .first_key_value()
+            .map(|(k, v)| (k.clone(), v.clone()))
+    }
+
+    /// Entry with the largest key
+    ///
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn last(&self) -> Option<(K, RcMapItem<'a, V>)> {
+        (**self)
+            .get()
+            .last_key_value()
+            .map(|(k, v)| (k.clone(), v.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RcBTreeMapSignal;
+
+    #[test]
+    pub fn btreemap_order_test() {
+        let map = RcBTreeMapSignal::new([(3, "c"), (1, "a"), (2, "b")]);
+        map.insert(0, "z");
+        let keys = map.get_untracked().keys().copied().collect::<Vec<_>>();
+        assert_eq!(keys, vec![0, 1, 2, 3]);
+
+        map.get_item(&0).expect("inserted item").remove();
+        assert_eq!(map.first().map(|(k, _)| k), Some(1));
+        assert_eq!(map.last().map(|(k, _)| k), Some(3));
+        assert_eq!(map.range(2..).len(), 2);
+    }
+}
@@ -1,24 +1,204 @@
-use std::{ops::Deref, rc::Rc};
+use indexmap::IndexMap;
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    hash::Hash,
+    ops::{Add, Deref, Sub},
+    rc::{Rc, Weak},
+};
 use sycamore::prelude::*;
 
-/// Wrapper type for [`RcSignal`](RcSignal)<[`Vec`](Vec)<[`RcSignal`](RcSignal)<`T`>>>
+use crate::{batch, collection_views, RcSelection, SelectionMode};
+
+type RcItems<T> = RcSignal<Vec<RcCollectionItem<T>>>;
+
+/// Wrapper type for [`RcSignal`](RcSignal)<[`Vec`](Vec)<[`RcCollectionItem`](RcCollectionItem)<`T`>>>
 ///
 /// Base type for the [`#[collection]`](crate::State) attribute when using [`State`](crate::State) derive macro
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct RcCollectionSignal<T> {
-    inner: RcSignal<Vec<RcSignal<T>>>,
+    inner: Rc<RcItems<T>>,
+}
+
+/// Formats the values of the items, untracked
+impl<T: Debug> Debug for RcCollectionSignal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self.inner.get_untracked();
+        f.debug_list()
+            .entries(items.iter().map(|a| a.get_untracked()))
+            .finish()
+    }
+}
+
+impl<T> Clone for RcCollectionSignal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+/// Handle to an item of a [`RcCollectionSignal`](RcCollectionSignal)
+///
+/// Dereferences to the item's [`RcSignal`](RcSignal) and can remove or move itself
+/// inside its collection without knowing its index
+pub struct RcCollectionItem<T> {
+    inner: RcSignal<T>,
+    collection: Weak<RcItems<T>>,
+}
+
+impl<T> RcCollectionItem<T> {
+    fn new(value: T, collection: Weak<RcItems<T>>) -> Self {
+        Self {
+            inner: create_rc_signal(value),
+            collection,
+        }
+    }
+
+    /// Current position of the item in its collection, [`None`] once it has been removed
+    ///
+    /// Subscribes to the collection when called inside a reactive scope
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// let item = collection.get()[2].clone();
+    /// collection.remove(0);
+    /// assert_eq!(item.index(), Some(1));
+    ///```
+    pub fn index(&self) -> Option<usize> {
+        let collection = self.collection.upgrade()?;
+        let items = collection.get();
+        items.iter().position(|a| a.ptr_eq(self))
+    }
+
+    /// Remove the item from its collection
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// let item = collection.get()[2].clone();
+    /// item.remove();
+    /// # assert_eq!(collection.get().len(), 3);
+    /// # assert_eq!(item.index(), None);
+    ///```
+    pub fn remove(&self) {
+        if let Some((collection, index)) = self.locate() {
+            batch::modify(&collection).remove(index);
+        }
+    }
+
+    /// Swap the item with the previous one, does nothing if the item is first
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// let item = collection.get()[2].clone();
+    /// item.move_up();
+    /// # assert_eq!(item.index(), Some(1));
+    ///```
+    pub fn move_up(&self) {
+        if let Some((collection, index)) = self.locate() {
+            if index > 0 {
+                batch::modify(&collection).swap(index, index - 1);
+            }
+        }
+    }
+
+    /// Swap the item with the next one, does nothing if the item is last
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// let item = collection.get()[2].clone();
+    /// item.move_down();
+    /// # assert_eq!(item.index(), Some(3));
+    ///```
+    pub fn move_down(&self) {
+        if let Some((collection, index)) = self.locate() {
+            if index + 1 < collection.get_untracked().len() {
+                batch::modify(&collection).swap(index, index + 1);
+            }
+        }
+    }
+
+    /// Replace the value of the item, returning the previous one
+    ///
+    /// Subscribers of the item are notified, the item keeps its position
+    pub fn replace(&self, value: T) -> Rc<T> {
+        let old = self.inner.get_untracked();
+        self.inner.set(value);
+        old
+    }
+
+    /// Returns `true` if both handles point to the same item
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        std::ptr::eq::<Signal<T>>(&*self.inner, &*other.inner)
+    }
+
+    fn locate(&self) -> Option<(Rc<RcItems<T>>, usize)> {
+        let collection = self.collection.upgrade()?;
+        let index = collection
+            .get_untracked()
+            .iter()
+            .position(|a| a.ptr_eq(self))?;
+        Some((collection, index))
+    }
+}
+
+impl<T> Clone for RcCollectionItem<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            collection: self.collection.clone(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for RcCollectionItem<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for RcCollectionItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Eq> Eq for RcCollectionItem<T> {}
+
+impl<T: Hash> Hash for RcCollectionItem<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<T> Deref for RcCollectionItem<T> {
+    type Target = RcSignal<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
 }
 
 impl<T> RcCollectionSignal<T> {
     /// Create new RcCollection from an iterator
     pub fn new(inner: impl IntoIterator<Item = T>) -> RcCollectionSignal<T> {
+        let collection = RcCollectionSignal {
+            inner: Rc::new(create_rc_signal(Vec::new())),
+        };
         let collected = inner
             .into_iter()
-            .map(|a| create_rc_signal(a))
+            .map(|a| collection.make_item(a))
             .collect::<Vec<_>>();
-        RcCollectionSignal {
-            inner: create_rc_signal(collected),
-        }
+        collection.inner.set_silent(collected);
+        collection
+    }
+
+    fn make_item(&self, value: T) -> RcCollectionItem<T> {
+        RcCollectionItem::new(value, Rc::downgrade(&self.inner))
     }
 }
 
@@ -30,10 +210,37 @@ impl<T> RcCollectionSignal<T> {
     /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
     /// collection.push(8);
     /// # assert_eq!(collection.get().len(), 5);
-
     ///```
     pub fn push(&self, value: T) {
-        self.inner.modify().push(create_rc_signal(value));
+        let item = self.make_item(value);
+        batch::modify(&self.inner).push(item);
+    }
+
+    /// Insert new value at `index`, shifting the items after it
+    ///
+    /// Panics if `index` is greater than the length of the collection
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// collection.insert(1, 8);
+    /// # assert_eq!(*collection.get()[1].get(), 8);
+    ///```
+    pub fn insert(&self, index: usize, value: T) {
+        let item = self.make_item(value);
+        batch::modify(&self.inner).insert(index, item);
+    }
+
+    /// Remove every value from collection
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// let collection = RcCollectionSignal::new(vec![1,2,3,4]);
+    /// collection.clear();
+    /// # assert!(collection.get().is_empty());
+    ///```
+    pub fn clear(&self) {
+        batch::modify(&self.inner).clear();
     }
 
     /// Get position of value in collection
@@ -62,7 +269,8 @@ impl<T> RcCollectionSignal<T> {
         self.inner
             .get()
             .iter()
-            .find(|a| f(&a.get())).map(|a| a.get())
+            .find(|a| f(&a.get()))
+            .map(|a| a.get())
     }
 
     /// Remove value from collection by index
@@ -74,7 +282,7 @@ impl<T> RcCollectionSignal<T> {
     /// # assert_eq!(collection.get().len(), 3);
     ///```
     pub fn remove(&self, index: usize) -> Rc<T> {
-        self.inner.modify().remove(index).get()
+        batch::modify(&self.inner).remove(index).get()
     }
 
     /// Remove value from collection with predicate
@@ -91,10 +299,402 @@ impl<T> RcCollectionSignal<T> {
     }
 }
 
+impl<T> RcCollectionSignal<T> {
+    /// Live view of the items matching `predicate`, in collection order
+    ///
+    /// The predicate only runs again for items whose value changed
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::{RcCollectionSignal};
+    /// # create_scope_immediate(|cx| {
+    /// let collection = RcCollectionSignal::new(vec![1, 2, 3, 4]);
+    /// let even = collection.filter_view(cx, |a| a % 2 == 0);
+    /// collection.get()[0].set(10);
+    /// assert_eq!(even.get().len(), 3);
+    /// # });
+    ///```
+    pub fn filter_view<'a>(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<Vec<RcCollectionItem<T>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::filter_view(cx, move || collection.get(), predicate)
+    }
+
+    /// Live view of the items ordered by `compare`
+    ///
+    /// Each update starts from the previous order, so changing a few items does not resort everything
+    pub fn sorted_view<'a>(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Vec<RcCollectionItem<T>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::sorted_view(cx, move || collection.get(), compare)
+    }
+
+    /// Live view of the values mapped by `f`, in collection order
+    ///
+    /// `f` only runs again for items whose value changed
+    pub fn map_view<'a, U: Clone + 'a>(
+        &self,
+        cx: Scope<'a>,
+        f: impl Fn(&T) -> U + 'a,
+    ) -> &'a ReadSignal<Vec<U>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::map_view(cx, move || collection.get(), f)
+    }
+
+    /// Live view of the items grouped by `key`, groups are ordered by first appearance
+    ///
+    /// Every group is a signal of its own, only notified when its items change,
+    /// the map itself only changes when groups appear, disappear or move
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::{RcCollectionSignal};
+    /// # create_scope_immediate(|cx| {
+    /// let collection = RcCollectionSignal::new(vec!["apple", "avocado", "banana"]);
+    /// let groups = collection.group_by(cx, |a| a.chars().next());
+    /// assert_eq!(groups.get()[&Some('a')].get().len(), 2);
+    /// # });
+    ///```
+    pub fn group_by<'a, K: Hash + Eq + Clone + 'a>(
+        &self,
+        cx: Scope<'a>,
+        key: impl Fn(&T) -> K + 'a,
+    ) -> &'a ReadSignal<IndexMap<K, &'a ReadSignal<Vec<RcCollectionItem<T>>>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::group_by(cx, move || collection.get(), key)
+    }
+
+    /// Live view of at most `len` items starting at `offset`
+    ///
+    /// Only insertions and removals update the page, not changes of the items
+    pub fn page<'a>(
+        &self,
+        cx: Scope<'a>,
+        offset: usize,
+        len: usize,
+    ) -> &'a ReadSignal<Vec<RcCollectionItem<T>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::page(cx, move || collection.get(), offset, len)
+    }
+
+    /// Number of items, only notifies when it changes
+    pub fn len<'a>(&self, cx: Scope<'a>) -> &'a ReadSignal<usize>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::len(cx, move || collection.get())
+    }
+
+    /// Returns `true` if the collection holds no item, only notifies when it changes
+    pub fn is_empty<'a>(&self, cx: Scope<'a>) -> &'a ReadSignal<bool>
+    where
+        T: 'a,
+    {
+        let len = self.len(cx);
+        create_selector(cx, move || *len.get() == 0)
+    }
+
+    /// Number of items matching `predicate`, only notifies when it changes
+    ///
+    /// The predicate only runs again for items whose value changed, the count is updated by the difference
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// # create_scope_immediate(|cx| {
+    /// let todos = RcCollectionSignal::new(vec![("a", true), ("b", false)]);
+    /// let done = todos.count_where(cx, |(_, done)| *done);
+    /// todos.get()[1].set(("b", true));
+    /// assert_eq!(*done.get(), 2);
+    /// # });
+    ///```
+    pub fn count_where<'a>(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<usize>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::count_where(cx, move || collection.get(), predicate)
+    }
+
+    /// Sum of the values mapped by `f`, only notifies when it changes
+    ///
+    /// `f` only runs again for items whose value changed, the sum is updated by the difference
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::RcCollectionSignal;
+    /// # create_scope_immediate(|cx| {
+    /// let prices = RcCollectionSignal::new(vec![("a", 3), ("b", 4)]);
+    /// let total = prices.sum_by(cx, |(_, price)| *price);
+    /// prices.get()[0].set(("a", 5));
+    /// assert_eq!(*total.get(), 9);
+    /// # });
+    ///```
+    pub fn sum_by<'a, S>(&self, cx: Scope<'a>, f: impl Fn(&T) -> S + 'a) -> &'a ReadSignal<S>
+    where
+        T: 'a,
+        S: Add<Output = S> + Sub<Output = S> + Default + Clone + PartialEq + 'a,
+    {
+        let collection = self.clone();
+        collection_views::sum_by(cx, move || collection.get(), f)
+    }
+
+    /// Returns `true` if any item matches `predicate`, only notifies when it changes
+    pub fn any<'a>(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<bool>
+    where
+        T: 'a,
+    {
+        let count = self.count_where(cx, predicate);
+        create_selector(cx, move || *count.get() > 0)
+    }
+
+    /// Returns `true` if every item matches `predicate`, only notifies when it changes
+    pub fn all<'a>(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<bool>
+    where
+        T: 'a,
+    {
+        let (count, len) = (self.count_where(cx, predicate), self.len(cx));
+        create_selector(cx, move || *count.get() == *len.get())
+    }
+
+    /// Smallest value according to `compare`, the first one if several are equal
+    ///
+    /// Only notifies when another value becomes the minimum
+    pub fn min_by<'a>(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Option<Rc<T>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::extremum_by(cx, move || collection.get(), compare, Ordering::Less)
+    }
+
+    /// Greatest value according to `compare`, the first one if several are equal
+    ///
+    /// Only notifies when another value becomes the maximum
+    pub fn max_by<'a>(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Option<Rc<T>>>
+    where
+        T: 'a,
+    {
+        let collection = self.clone();
+        collection_views::extremum_by(cx, move || collection.get(), compare, Ordering::Greater)
+    }
+}
+
+impl<T: 'static> RcCollectionSignal<T> {
+    /// Create a selection over the items of the collection
+    pub fn selection(&self, mode: SelectionMode) -> RcSelection<RcCollectionItem<T>> {
+        let collection = self.clone();
+        RcSelection::new(mode, move || collection.get().as_ref().clone())
+    }
+}
+
 impl<T> Deref for RcCollectionSignal<T> {
-    type Target = Signal<Vec<RcSignal<T>>>;
+    type Target = Signal<Vec<RcCollectionItem<T>>>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use sycamore::reactive::{create_effect, create_ref, create_scope_immediate, create_signal};
+
+    use crate::RcCollectionSignal;
+
+    #[test]
+    pub fn item_handle_test() {
+        let collection = RcCollectionSignal::new(vec![1, 2, 3, 4]);
+        let items = collection.get();
+        let (first, third, last) = (items[0].clone(), items[2].clone(), items[3].clone());
+
+        first.remove();
+        assert_eq!(third.index(), Some(1));
+        assert_eq!(first.index(), None);
+
+        first.remove();
+        first.move_up();
+        assert_eq!(collection.get().len(), 3);
+
+        third.move_up();
+        third.move_up();
+        assert_eq!(third.index(), Some(0));
+        last.move_down();
+        assert_eq!(last.index(), Some(2));
+
+        assert_eq!(*third.replace(30), 3);
+        let values = collection
+            .get()
+            .iter()
+            .map(|a| *a.get())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![30, 2, 4]);
+    }
+
+    #[test]
+    pub fn reactive_index_test() {
+        create_scope_immediate(|cx| {
+            let collection = RcCollectionSignal::new(vec!["a", "b", "c"]);
+            let item = collection.get()[2].clone();
+            let index = create_signal(cx, None);
+            create_effect(cx, move || index.set(item.index()));
+
+            assert_eq!(*index.get(), Some(2));
+            collection.remove(0);
+            assert_eq!(*index.get(), Some(1));
+            collection.get()[1].remove();
+            assert_eq!(*index.get(), None);
+        });
+    }
+
+    #[test]
+    pub fn incremental_views_test() {
+        create_scope_immediate(|cx| {
+            let collection = RcCollectionSignal::new(vec![5, 1, 4, 2, 3]);
+            let calls = create_ref(cx, Cell::new(0));
+            let odd = collection.filter_view(cx, |a| {
+                calls.set(calls.get() + 1);
+                a % 2 == 1
+            });
+            let sorted = collection.sorted_view(cx, |a, b| a.cmp(b));
+            let doubled = collection.map_view(cx, |a| a * 2);
+            let parity = collection.group_by(cx, |a| a % 2);
+            let first_page = collection.page(cx, 0, 2);
+            let values = |items: &[crate::RcCollectionItem<i32>]| {
+                items.iter().map(|a| *a.get()).collect::<Vec<_>>()
+            };
+
+            let even = parity.get()[&0];
+            assert_eq!(values(&odd.get()), vec![5, 1, 3]);
+            let initial_calls = calls.get();
+
+            collection.get()[1].set(6);
+            assert_eq!(calls.get(), initial_calls + 1);
+            assert_eq!(values(&odd.get()), vec![5, 3]);
+            assert_eq!(values(&sorted.get()), vec![2, 3, 4, 5, 6]);
+            assert_eq!(*doubled.get(), vec![10, 12, 8, 4, 6]);
+            assert_eq!(values(&even.get()), vec![6, 4, 2]);
+            assert!(std::ptr::eq(even, parity.get()[&0]));
+
+            collection.remove(0);
+            assert_eq!(values(&first_page.get()), vec![6, 4]);
+            assert_eq!(values(&sorted.get()), vec![2, 3, 4, 6]);
+            assert_eq!(values(&parity.get()[&1].get()), vec![3]);
+            assert_eq!(calls.get(), initial_calls + 1);
+        });
+    }
+
+    #[test]
+    pub fn aggregates_test() {
+        create_scope_immediate(|cx| {
+            let todos = RcCollectionSignal::new(vec![(1, false), (2, true), (3, false)]);
+            let done = todos.count_where(cx, |(_, done)| *done);
+            let all_done = todos.all(cx, |(_, done)| *done);
+            let total = todos.sum_by(cx, |(id, _)| *id);
+            let highest = todos.max_by(cx, |a, b| a.0.cmp(&b.0));
+            let lowest = todos.min_by(cx, |a, b| a.0.cmp(&b.0));
+            let is_empty = todos.is_empty(cx);
+
+            let notified = create_signal(cx, 0);
+            create_effect(cx, move || {
+                done.track();
+                notified.set(*notified.get_untracked() + 1);
+            });
+
+            todos.get()[0].set((10, false));
+            assert_eq!(*notified.get(), 1);
+            assert_eq!(*total.get(), 15);
+            assert_eq!(highest.get().as_deref(), Some(&(10, false)));
+            assert_eq!(lowest.get().as_deref(), Some(&(2, true)));
+
+            for item in todos.get().iter() {
+                item.modify().1 = true;
+            }
+            assert_eq!(*notified.get(), 3);
+            assert!(*all_done.get());
+            assert!(*todos.any(cx, |(id, _)| *id == 3).get());
+
+            todos.get()[0].remove();
+            todos.get()[0].remove();
+            todos.get()[0].remove();
+            assert!(*is_empty.get());
+            assert_eq!(*highest.get(), None);
+        });
+    }
+
+    #[test]
+    fn incremental_aggregates_test() {
+        create_scope_immediate(|cx| {
+            let items = RcCollectionSignal::new(vec![("a", 1), ("b", 3), ("c", 2), ("d", 3)]);
+            let compared = create_ref(cx, Cell::new(0));
+            let highest = items.max_by(cx, |a, b| {
+                compared.set(compared.get() + 1);
+                a.1.cmp(&b.1)
+            });
+            let total = items.sum_by(cx, |(_, value)| *value);
+            assert_eq!(highest.get().as_deref().map(|item| item.0), Some("b"));
+
+            compared.set(0);
+            items.get()[0].set(("a", 2));
+            assert_eq!(compared.get(), 1);
+            assert_eq!(*total.get(), 10);
+            assert_eq!(highest.get().as_deref().map(|item| item.0), Some("b"));
+
+            items.get()[3].move_up();
+            items.get()[2].move_up();
+            assert_eq!(highest.get().as_deref().map(|item| item.0), Some("d"));
+
+            items.get()[1].remove();
+            assert_eq!(*total.get(), 7);
+            assert_eq!(highest.get().as_deref().map(|item| item.0), Some("b"));
+
+            items.push(("e", 4));
+            assert_eq!(*total.get(), 11);
+            assert_eq!(highest.get().as_deref().map(|item| item.0), Some("e"));
+        });
+    }
+}
@@ -1,86 +1,42 @@
-use std::{collections::HashMap, hash::Hash, ops::Deref, rc::Rc};
+use std::collections::HashMap;
 
-use sycamore::reactive::{create_rc_signal, RcSignal};
+use crate::{RcMapItem, RcMapSignal};
 
-pub struct RcHashMapSignal<'a, K, V> {
-    inner: RcSignal<HashMap<K, RcHashMapItem<'a, V>>>,
-}
-
-#[derive(Clone)]
-pub struct RcHashMapItem<'a, T> {
-    inner: RcSignal<T>,
-    remover: Rc<Box<dyn Fn() + 'a>>,
-}
-
-impl<'a, T> RcHashMapItem<'a, T> {
-    pub fn remove(&self) {
-        (self.remover)()
-    }
-}
+/// Wrapper type for [`RcSignal`](sycamore::reactive::RcSignal)<[`HashMap`](HashMap)<`K`, [`RcHashMapItem`](RcHashMapItem)<`V`>>>
+///
+/// Iterates in arbitrary order, see [`RcBTreeMapSignal`](crate::RcBTreeMapSignal)
+/// and [`RcIndexMapSignal`](crate::RcIndexMapSignal) for ordered maps
+pub type RcHashMapSignal<'a, K, V> = RcMapSignal<'a, K, V, HashMap<K, RcMapItem<'a, V>>>;
 
-impl<'a, T> Deref for RcHashMapItem<'a, T> {
-    type Target = RcSignal<T>;
+/// Handle to an entry of a [`RcHashMapSignal`](RcHashMapSignal)
+pub type RcHashMapItem<'a, T> = RcMapItem<'a, T>;
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
 
-impl<'a, T> RcHashMapItem<'a, T> {
-    fn new(inner: T, remover: impl Fn() + 'a) -> Self {
-        Self {
-            inner: create_rc_signal(inner),
-            remover: Rc::new(Box::new(remover)),
-        }
-    }
-}
+    use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
 
-impl<'a, K, V> Deref for RcHashMapSignal<'a, K, V> {
-    type Target = RcSignal<HashMap<K, RcHashMapItem<'a, V>>>;
+    use crate::RcHashMapSignal;
 
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}
+    #[derive(Clone)]
+    struct DropCounter(Arc<AtomicUsize>);
 
-impl<'a, K: Hash + Eq + Send + Sync + Clone + 'a, V: Send + Sync + Clone + 'a>
-    RcHashMapSignal<'a, K, V>
-{
-    pub fn new(map: HashMap<K, V>) -> Self {
-        let inner = HashMap::new();
-        let inner = create_rc_signal(inner);
-        for (k, v) in map {
-            let inner_clone = inner.clone();
-            let _k = k.clone();
-            let remover = move || {
-                inner_clone.modify().remove(&k);
-            };
-            let item = RcHashMapItem::new(v, remover);
-            inner.modify().insert(_k, item);
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
         }
-        Self { inner }
-    }
-
-    pub fn insert(&self, key: K, value: V) {
-        let clone = self.inner.clone();
-        self.inner.modify().insert(
-            key.clone(),
-            RcHashMapItem::new(value, move || {
-                clone.modify().remove(&key);
-            }),
-        );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
-
-    use crate::RcHashMapSignal;
 
     #[test]
     pub fn hashmap_test() {
-        let map = HashMap::default();
+        let map = HashMap::new();
         let map = RcHashMapSignal::new(map);
         map.insert("hello", 3);
         map.insert("hello2", 5);
@@ -93,4 +49,87 @@ mod tests {
         }
         assert_eq!(map.get().len(), 0);
     }
+
+    #[test]
+    pub fn hashmap_api_test() {
+        let map = (0..10)
+            .map(|a| (a, a * 10))
+            .collect::<RcHashMapSignal<_, _>>();
+        assert!(map.contains_key(&3));
+        assert_eq!(*map.remove(&3).expect("removed value"), 30);
+        assert_eq!(map.remove(&3), None);
+
+        map.retain(|k, _| k % 2 == 0);
+        assert_eq!(map.len(), 5);
+
+        map.extend([(0, 1), (11, 110)]);
+        assert_eq!(map.len(), 6);
+        assert_eq!(*map.get_item(&0).expect("existing item").get(), 1);
+
+        map.clear();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    pub fn hashmap_subscription_test() {
+        create_scope_immediate(|cx| {
+            let map = RcHashMapSignal::new(HashMap::from([("a", 1), ("b", 2)]));
+            let keys = map.keys(cx);
+            let map_runs = create_signal(cx, 0);
+            let inner = map.get_item(&"a").expect("existing item");
+            let entries = (*map).clone();
+            create_effect(cx, move || {
+                entries.track();
+                map_runs.set(*map_runs.get_untracked() + 1);
+            });
+
+            map.insert("a", 10);
+            map.update(&"b", |a| a * 10);
+            assert_eq!(*map_runs.get(), 1);
+            assert_eq!(*inner.get(), 10);
+
+            map.insert("c", 3);
+            assert_eq!(*map_runs.get(), 2);
+            assert_eq!(keys.get().len(), 3);
+        });
+    }
+
+    #[test]
+    pub fn hashmap_drop_test() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let map = RcHashMapSignal::new(HashMap::from([(1, DropCounter(drops.clone()))]));
+        map.insert(2, DropCounter(drops.clone()));
+        map.insert(3, DropCounter(drops.clone()));
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+
+        drop(map);
+        assert_eq!(drops.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    pub fn hashmap_item_outlives_map_test() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let map = RcHashMapSignal::new(HashMap::new());
+        map.insert("key", DropCounter(drops.clone()));
+        let item = map.get_item(&"key").expect("inserted item");
+
+        drop(map);
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        item.remove();
+        drop(item);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    pub fn hashmap_stale_item_test() {
+        let map = RcHashMapSignal::new(HashMap::from([("key", 1)]));
+        let stale = map.get_item(&"key").expect("existing item");
+        stale.remove();
+        map.insert("key", 2);
+
+        stale.remove();
+        assert_eq!(*map.get_item(&"key").expect("inserted item").get(), 2);
+        map.get_item(&"key").expect("inserted item").remove();
+        assert!(map.is_empty());
+    }
 }
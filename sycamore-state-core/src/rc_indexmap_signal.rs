@@ -0,0 +1,27 @@
+use indexmap::IndexMap;
+
+use crate::{RcMapItem, RcMapSignal};
+
+/// Wrapper type for [`RcSignal`](sycamore::reactive::RcSignal)<[`IndexMap`](IndexMap)<`K`, [`RcMapItem`](RcMapItem)<`V`>>>
+///
+/// Iterates in insertion order, removing an entry keeps the order of the others
+pub type RcIndexMapSignal<'a, K, V> = RcMapSignal<'a, K, V, IndexMap<K, RcMapItem<'a, V>>>;
+
+#[cfg(test)]
+mod tests {
+    use crate::RcIndexMapSignal;
+
+    #[test]
+    pub fn indexmap_order_test() {
+        let map = RcIndexMapSignal::new([("c", 3), ("a", 1)]);
+        map.insert("b", 2);
+        map.insert("c", 30);
+        map.remove(&"a");
+        let entries = map
+            .get_untracked()
+            .iter()
+            .map(|(k, v)| (*k, *v.get()))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, vec![("c", 30), ("b", 2)]);
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    ops::Deref,
+    ptr,
+    rc::{Rc, Weak},
+};
+
+use indexmap::IndexMap;
+use sycamore::reactive::{create_memo, create_rc_signal, RcSignal, ReadSignal, Scope, Signal};
+
+use crate::{batch, RcSelection, SelectionMode};
+
+/// Storage backing a [`RcMapSignal`](RcMapSignal)
+///
+/// Implemented for [`HashMap`](HashMap), [`BTreeMap`](BTreeMap) and [`IndexMap`](IndexMap),
+/// the iteration order of the storage is the iteration order of the signal
+pub trait MapEntries<K, V>: Clone + Default {
+    fn entry(&self, key: &K) -> Option<&V>;
+    fn insert_entry(&mut self, key: K, value: V);
+    fn remove_entry(&mut self, key: &K) -> Option<V>;
+    fn entries(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_>;
+    fn entries_len(&self) -> usize;
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> MapEntries<K, V> for HashMap<K, V> {
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn remove_entry(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
+        Box::new(self.iter())
+    }
+
+    fn entries_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> MapEntries<K, V> for BTreeMap<K, V> {
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn remove_entry(&mut self, key: &K) -> Option<V> {
+        self.remove(key)
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
+        Box::new(self.iter())
+    }
+
+    fn entries_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> MapEntries<K, V> for IndexMap<K, V> {
+    fn entry(&self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) {
+        self.insert(key, value);
+    }
+
+    fn remove_entry(&mut self, key: &K) -> Option<V> {
+        self.shift_remove(key)
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = (&K, &V)> + '_> {
+        Box::new(self.iter())
+    }
+
+    fn entries_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Wrapper type for [`RcSignal`](RcSignal)<`M`> where `M` maps `K` to [`RcMapItem`](RcMapItem)<`V`>
+///
+/// Use one of [`RcHashMapSignal`](crate::RcHashMapSignal), [`RcBTreeMapSignal`](crate::RcBTreeMapSignal)
+/// or [`RcIndexMapSignal`](crate::RcIndexMapSignal) instead of naming the storage directly
+///
+pub struct RcMapSignal<'a, K, V, M> {
+    inner: Rc<RcSignal<M>>,
+    _marker: PhantomData<(K, RcMapItem<'a, V>)>,
+}
+
+/// Handle to an entry of a map signal
+///
+/// Dereferences to the entry's [`RcSignal`](RcSignal) and can remove itself from its map
+pub struct RcMapItem<'a, T> {
+    inner: RcSignal<T>,
+    remover: Rc<Box<dyn Fn() + 'a>>,
+}
+
+impl<'a, T> RcMapItem<'a, T> {
+    fn new(inner: RcSignal<T>, remover: impl Fn() + 'a) -> Self {
+        Self {
+            inner,
+            remover: Rc::new(Box::new(remover)),
+        }
+    }
+
+    pub fn remove(&self) {
+        (self.remover)()
+    }
+}
+
+impl<'a, T> Clone for RcMapItem<'a, T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            remover: self.remover.clone(),
+        }
+    }
+}
+
+impl<'a, T: Debug> Debug for RcMapItem<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'a, T: PartialEq> PartialEq for RcMapItem<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<'a, T: Eq> Eq for RcMapItem<'a, T> {}
+
+impl<'a, T> Deref for RcMapItem<'a, T> {
+    type Target = RcSignal<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, K, V, M> Deref for RcMapSignal<'a, K, V, M> {
+    type Target = RcSignal<M>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<'a, K: Clone + 'a, V: 'a, M: MapEntries<K, RcMapItem<'a, V>> + 'a> RcMapSignal<'a, K, V, M> {
+    pub fn new(map: impl IntoIterator<Item = (K, V)>) -> Self {
+        let signal = Self {
+            inner: Rc::new(create_rc_signal(M::default())),
+            _marker: PhantomData,
+        };
+        let mut entries = M::default();
+        for (k, v) in map {
+            entries.insert_entry(k.clone(), signal.make_item(k, v));
+        }
+        signal.inner.set_silent(entries);
+        signal
+    }
+
+    /// Insert value into the map
+    ///
+    /// If the key is already present its item signal is updated instead,
+    /// so existing handles to the entry keep working
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcHashMapSignal;
+    /// let map = RcHashMapSignal::default();
+    /// map.insert("key", 1);
+    /// let item = map.get_item(&"key").expect("inserted item");
+    /// map.insert("key", 2);
+    /// assert_eq!(*item.get(), 2);
+    ///```
+    pub fn insert(&self, key: K, value: V) {
+        if let Some(item) = self.inner.get_untracked().entry(&key) {
+            item.set(value);
+            return;
+        }
+        let item = self.make_item(key.clone(), value);
+        batch::modify(&self.inner).insert_entry(key, item);
+    }
+
+    /// Remove value from the map by key
+    pub fn remove(&self, key: &K) -> Option<Rc<V>> {
+        self.inner.get_untracked().entry(key)?;
+        batch::modify(&self.inner)
+            .remove_entry(key)
+            .map(|item| item.get_untracked())
+    }
+
+    /// Get the item stored under `key`
+    ///
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn get_item(&self, key: &K) -> Option<RcMapItem<'a, V>> {
+        self.inner.get().entry(key).cloned()
+    }
+
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.inner.get().entry(key).is_some()
+    }
+
+    /// Get the item stored under `key`, inserting the result of `f` if it is missing
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcHashMapSignal;
+    /// let map = RcHashMapSignal::default();
+    /// let item = map.get_or_insert_with("key", || 1);
+    /// let same = map.get_or_insert_with("key", || 2);
+    /// assert_eq!(*same.get(), 1);
+    /// # assert_eq!(map.len(), 1);
+    /// # assert_eq!(*item.get(), 1);
+    ///```
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&self, key: K, f: F) -> RcMapItem<'a, V> {
+        if let Some(item) = self.inner.get_untracked().entry(&key) {
+            return item.clone();
+        }
+        let item = self.make_item(key.clone(), f());
+        batch::modify(&self.inner).insert_entry(key, item.clone());
+        item
+    }
+
+    /// Update the value stored under `key`, returns `false` if the key is missing
+    ///
+    /// Only subscribers of the item are notified
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcHashMapSignal;
+    /// let map = [("key", 1)].into_iter().collect::<RcHashMapSignal<_, _>>();
+    /// assert!(map.update(&"key", |a| a + 1));
+    /// assert!(!map.update(&"missing", |a| a + 1));
+    /// # assert_eq!(*map.get_item(&"key").unwrap().get(), 2);
+    ///```
+    pub fn update<F: FnOnce(&V) -> V>(&self, key: &K, f: F) -> bool {
+        match self.inner.get_untracked().entry(key) {
+            Some(item) => {
+                item.set(f(&item.get_untracked()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Retain only the entries for which `f` returns `true`
+    pub fn retain<F: FnMut(&K, &V) -> bool>(&self, mut f: F) {
+        let removed = self
+            .inner
+            .get_untracked()
+            .entries()
+            .filter(|(k, v)| !f(k, &v.get_untracked()))
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
+        if removed.is_empty() {
+            return;
+        }
+        let mut entries = batch::modify(&self.inner);
+        for key in removed {
+            entries.remove_entry(&key);
+        }
+    }
+
+    /// Insert all the values of an iterator, with the same semantics as [`insert`](RcMapSignal::insert)
+    pub fn extend<I: IntoIterator<Item = (K, V)>>(&self, iter: I) {
+        let current = self.inner.get_untracked();
+        let mut added = Vec::new();
+        for (key, value) in iter {
+            match current.entry(&key) {
+                Some(item) => item.set(value),
+                None => added.push((key.clone(), self.make_item(key, value))),
+            }
+        }
+        if added.is_empty() {
+            return;
+        }
+        let mut entries = batch::modify(&self.inner);
+        for (key, item) in added {
+            entries.insert_entry(key, item);
+        }
+    }
+
+    /// Remove all the entries
+    pub fn clear(&self) {
+        if self.inner.get_untracked().entries_len() != 0 {
+            batch::set(&self.inner, M::default());
+        }
+    }
+
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn len(&self) -> usize {
+        self.inner.get().entries_len()
+    }
+
+    /// Subscribes to the map when called inside a reactive scope
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Memo of the keys of the map, updated when entries are added or removed
+    ///
+    /// Memo values outlive their scope, so the keys have to be `'static`
+    pub fn keys<'cx>(&self, cx: Scope<'cx>) -> &'cx ReadSignal<Vec<K>>
+    where
+        K: 'static,
+        'a: 'cx,
+    {
+        let inner = self.inner.clone();
+        create_memo(cx, move || {
+            inner.get().entries().map(|(k, _)| k.clone()).collect()
+        })
+    }
+
+    /// Items only keep a weak reference to the map they belong to,
+    /// a strong one would form a cycle since the map owns its items
+    ///
+    /// The entry is only removed while it still holds this item, a stale handle
+    /// leaves alone a value inserted again under the same key
+    fn make_item(&self, key: K, value: V) -> RcMapItem<'a, V> {
+        let map: Weak<RcSignal<M>> = Rc::downgrade(&self.inner);
+        let inner = create_rc_signal(value);
+        let signal: *const Signal<V> = &*inner;
+        RcMapItem::new(inner, move || {
+            if let Some(map) = map.upgrade() {
+                let entries = map.get_untracked();
+                let current = entries
+                    .entry(&key)
+                    .is_some_and(|a| ptr::eq(&*a.inner, signal));
+                drop(entries);
+                if current {
+                    batch::modify(&map).remove_entry(&key);
+                }
+            }
+        })
+    }
+}
+
+impl<K: Clone + 'static, V: 'static, M: MapEntries<K, RcMapItem<'static, V>> + 'static>
+    RcMapSignal<'static, K, V, M>
+{
+    /// Create a selection over the items of the map, in iteration order
+    pub fn selection(&self, mode: SelectionMode) -> RcSelection<RcMapItem<'static, V>> {
+        let inner = self.inner.clone();
+        RcSelection::new(mode, move || {
+            inner.get().entries().map(|(_, v)| v.clone()).collect()
+        })
+    }
+
+    /// Memo of the items of the map, updated when entries are added or removed
+    ///
+    /// Memo values outlive their scope, so this needs a map of `'static` items,
+    /// use [`keys`](RcMapSignal::keys) and [`get_item`](RcMapSignal::get_item) for borrowing maps
+    pub fn values<'cx>(&self, cx: Scope<'cx>) -> &'cx ReadSignal<Vec<RcMapItem<'static, V>>> {
+        let inner = self.inner.clone();
+        create_memo(cx, move || {
+            inner.get().entries().map(|(_, v)| v.clone()).collect()
+        })
+    }
+}
+
+impl<'a, K, V, M> Clone for RcMapSignal<'a, K, V, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Formats the entries with the values of the items, untracked
+impl<'a, K: Debug, V: Debug, M: MapEntries<K, RcMapItem<'a, V>>> Debug
+    for RcMapSignal<'a, K, V, M>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.inner.get_untracked();
+        f.debug_map()
+            .entries(entries.entries().map(|(k, v)| (k, v.get_untracked())))
+            .finish()
+    }
+}
+
+impl<'a, K, V, M: PartialEq> PartialEq for RcMapSignal<'a, K, V, M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<'a, K, V, M: Eq> Eq for RcMapSignal<'a, K, V, M> {}
+
+impl<'a, K: Clone + 'a, V: 'a, M: MapEntries<K, RcMapItem<'a, V>> + 'a> Default
+    for RcMapSignal<'a, K, V, M>
+{
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl<'a, K: Clone + 'a, V: 'a, M: MapEntries<K, RcMapItem<'a, V>> + 'a> FromIterator<(K, V)>
+    for RcMapSignal<'a, K, V, M>
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
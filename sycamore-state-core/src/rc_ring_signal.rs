@@ -0,0 +1,201 @@
+use std::{cell::Cell, collections::VecDeque, fmt::Debug, ops::Deref, rc::Rc};
+
+use sycamore::reactive::{create_rc_signal, RcSignal, ReadSignal, Signal};
+
+use crate::batch;
+
+/// Bounded wrapper type for [`RcSignal`](RcSignal)<[`VecDeque`](VecDeque)<[`RcSignal`](RcSignal)<`T`>>>
+///
+/// Base type for the [`#[collection(capacity = ..)]`](crate::State) attribute when using [`State`](crate::State) derive macro
+///
+/// Once the ring is full pushing evicts the oldest item, that is the one
+/// at the opposite end of the push, evicted values are published on [`evicted`](RcRingSignal::evicted)
+pub struct RcRingSignal<T> {
+    inner: Rc<RcSignal<VecDeque<RcSignal<T>>>>,
+    capacity: Rc<Cell<usize>>,
+    evicted: Rc<RcSignal<Vec<Rc<T>>>>,
+}
+
+impl<T> RcRingSignal<T> {
+    /// Create new ring from an iterator, only the last `capacity` values are kept
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcRingSignal;
+    /// let ring = RcRingSignal::new(3, 0..5);
+    /// let values = ring.get().iter().map(|a| *a.get()).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![2, 3, 4]);
+    ///```
+    pub fn new(capacity: usize, inner: impl IntoIterator<Item = T>) -> RcRingSignal<T> {
+        let mut collected = inner
+            .into_iter()
+            .map(create_rc_signal)
+            .collect::<VecDeque<_>>();
+        Self::evict(&mut collected, capacity, VecDeque::pop_front);
+        RcRingSignal {
+            inner: Rc::new(create_rc_signal(collected)),
+            capacity: Rc::new(Cell::new(capacity)),
+            evicted: Rc::new(create_rc_signal(Vec::new())),
+        }
+    }
+
+    /// Maximum number of items held by the ring
+    pub fn capacity(&self) -> usize {
+        self.capacity.get()
+    }
+
+    /// Change the capacity, evicting the front items if the ring holds more than `capacity`
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.set(capacity);
+        if self.inner.get_untracked().len() > capacity {
+            let evicted = Self::evict(
+                &mut batch::modify(&self.inner),
+                capacity,
+                VecDeque::pop_front,
+            );
+            batch::set(&self.evicted, evicted);
+        }
+    }
+
+    /// Push new value at the back, evicting the front item if the ring is full
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcRingSignal;
+    /// let ring = RcRingSignal::new(2, vec![1, 2]);
+    /// ring.push_back(3);
+    /// assert_eq!(*ring.evicted().get()[0], 1);
+    /// # assert_eq!(ring.get().len(), 2);
+    ///```
+    pub fn push_back(&self, value: T) {
+        let mut items = batch::modify(&self.inner);
+        items.push_back(create_rc_signal(value));
+        let evicted = Self::evict(&mut items, self.capacity(), VecDeque::pop_front);
+        drop(items);
+        self.publish(evicted);
+    }
+
+    /// Push new value at the front, evicting the back item if the ring is full
+    pub fn push_front(&self, value: T) {
+        let mut items = batch::modify(&self.inner);
+        items.push_front(create_rc_signal(value));
+        let evicted = Self::evict(&mut items, self.capacity(), VecDeque::pop_back);
+        drop(items);
+        self.publish(evicted);
+    }
+
+    /// Remove the back value
+    pub fn pop_back(&self) -> Option<Rc<T>> {
+        if self.inner.get_untracked().is_empty() {
+            return None;
+        }
+        batch::modify(&self.inner)
+            .pop_back()
+            .map(|a| a.get_untracked())
+    }
+
+    /// Remove the front value
+    pub fn pop_front(&self) -> Option<Rc<T>> {
+        if self.inner.get_untracked().is_empty() {
+            return None;
+        }
+        batch::modify(&self.inner)
+            .pop_front()
+            .map(|a| a.get_untracked())
+    }
+
+    /// Values evicted by the last push or capacity change
+    ///
+    /// Subscribers are notified on every eviction, even if the same values are evicted twice
+    pub fn evicted(&self) -> &ReadSignal<Vec<Rc<T>>> {
+        &self.evicted
+    }
+
+    fn publish(&self, evicted: Vec<Rc<T>>) {
+        if !evicted.is_empty() {
+            batch::set(&self.evicted, evicted);
+        }
+    }
+
+    fn evict(
+        items: &mut VecDeque<RcSignal<T>>,
+        capacity: usize,
+        pop: fn(&mut VecDeque<RcSignal<T>>) -> Option<RcSignal<T>>,
+    ) -> Vec<Rc<T>> {
+        let mut evicted = Vec::new();
+        while items.len() > capacity {
+            match pop(items) {
+                Some(item) => evicted.push(item.get_untracked()),
+                None => break,
+            }
+        }
+        evicted
+    }
+}
+
+impl<T> Clone for RcRingSignal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            capacity: self.capacity.clone(),
+            evicted: self.evicted.clone(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for RcRingSignal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self.inner.get_untracked();
+        f.debug_list()
+            .entries(items.iter().map(|a| a.get_untracked()))
+            .finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for RcRingSignal<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Eq> Eq for RcRingSignal<T> {}
+
+impl<T> Deref for RcRingSignal<T> {
+    type Target = Signal<VecDeque<RcSignal<T>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
+
+    use crate::RcRingSignal;
+
+    #[test]
+    pub fn ring_eviction_test() {
+        create_scope_immediate(|cx| {
+            let ring = RcRingSignal::new(3, vec![1, 2]);
+            let evictions = create_signal(cx, Vec::new());
+            let tracked = ring.clone();
+            create_effect(cx, move || {
+                let evicted = tracked.evicted().get();
+                evictions.modify().extend(evicted.iter().map(|a| **a));
+            });
+
+            ring.push_back(3);
+            ring.push_back(4);
+            ring.push_front(0);
+            assert_eq!(*evictions.get(), vec![1, 4]);
+
+            let values = ring.get().iter().map(|a| *a.get()).collect::<Vec<_>>();
+            assert_eq!(values, vec![0, 2, 3]);
+
+            assert_eq!(ring.pop_back().as_deref(), Some(&3));
+            assert_eq!(ring.pop_front().as_deref(), Some(&0));
+            ring.set_capacity(0);
+            assert_eq!(*evictions.get(), vec![1, 4, 2]);
+            assert_eq!(ring.pop_front(), None);
+        });
+    }
+}
@@ -0,0 +1,399 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc};
+
+use sycamore::reactive::{
+    create_effect, create_rc_signal, create_scope, untrack, RcSignal, ReadSignal, ScopeDisposer,
+    Signal,
+};
+
+use crate::{RcCollectionItem, RcMapItem, RcSortedItem};
+
+/// Item handle that can be selected with a [`RcSelection`](RcSelection)
+pub trait SelectionItem: Clone {
+    /// Address identifying the item as long as a handle to it is alive
+    fn id(&self) -> *const ();
+}
+
+impl<T> SelectionItem for RcCollectionItem<T> {
+    fn id(&self) -> *const () {
+        let signal: &Signal<T> = self;
+        signal as *const Signal<T> as *const ()
+    }
+}
+
+impl<T> SelectionItem for RcSortedItem<T> {
+    fn id(&self) -> *const () {
+        let signal: &ReadSignal<T> = self;
+        signal as *const ReadSignal<T> as *const ()
+    }
+}
+
+impl<'a, T> SelectionItem for RcMapItem<'a, T> {
+    fn id(&self) -> *const () {
+        let signal: &Signal<T> = self;
+        signal as *const Signal<T> as *const ()
+    }
+}
+
+/// How many items a [`RcSelection`](RcSelection) can hold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Selecting an item deselects the previous one
+    Single,
+    /// Any number of items can be selected
+    Multiple,
+}
+
+struct SelectionInner<I> {
+    items: Box<dyn Fn() -> Vec<I>>,
+    mode: SelectionMode,
+    selected: RcSignal<Vec<I>>,
+    anchor: RefCell<Option<I>>,
+    flags: RefCell<HashMap<*const (), (I, RcSignal<bool>)>>,
+    /// Effect dropping removed items from the selection
+    watcher: RefCell<Option<ScopeDisposer<'static>>>,
+}
+
+impl<I> Drop for SelectionInner<I> {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            // SAFETY: the effect only runs while the selection is alive and never drops it
+            unsafe { watcher.dispose() };
+        }
+    }
+}
+
+/// Selection over the items of a collection signal
+///
+/// Created with the `selection` method of [`RcCollectionSignal`](crate::RcCollectionSignal),
+/// [`RcSortedCollectionSignal`](crate::RcSortedCollectionSignal) or [`RcMapSignal`](crate::RcMapSignal)
+///
+/// Items are tracked by identity rather than by index, so the selection follows
+/// them across inserts and reorders, removed items are dropped from the selection
+pub struct RcSelection<I> {
+    inner: Rc<SelectionInner<I>>,
+}
+
+impl<I: SelectionItem> RcSelection<I> {
+    pub(crate) fn new(mode: SelectionMode, items: impl Fn() -> Vec<I> + 'static) -> Self
+    where
+        I: 'static,
+    {
+        let selection = Self {
+            inner: Rc::new(SelectionInner {
+                items: Box::new(items),
+                mode,
+                selected: create_rc_signal(Vec::new()),
+                anchor: RefCell::new(None),
+                flags: RefCell::new(HashMap::new()),
+                watcher: RefCell::new(None),
+            }),
+        };
+        let inner = Rc::downgrade(&selection.inner);
+        let watcher = create_scope(move |cx| {
+            create_effect(cx, move || {
+                if let Some(inner) = inner.upgrade() {
+                    (inner.items)();
+                    let selection = Self { inner };
+                    untrack(|| {
+                        let selected = selection.inner.selected.get_untracked();
+                        selection.replace(selected.as_ref().clone());
+                    });
+                }
+            });
+        });
+        *selection.inner.watcher.borrow_mut() = Some(watcher);
+        selection
+    }
+
+    /// Selection mode given on creation
+    pub fn mode(&self) -> SelectionMode {
+        self.inner.mode
+    }
+
+    /// Select an item, replacing the selection in [`Single`](SelectionMode::Single) mode
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::{RcCollectionSignal, SelectionMode};
+    /// let collection = RcCollectionSignal::new(vec![1, 2, 3]);
+    /// let selection = collection.selection(SelectionMode::Single);
+    /// let items = collection.get();
+    /// selection.select(&items[0]);
+    /// selection.select(&items[2]);
+    /// assert!(!selection.is_selected(&items[0]));
+    /// assert!(selection.is_selected(&items[2]));
+    ///```
+    pub fn select(&self, item: &I) {
+        let mut selected = match self.inner.mode {
+            SelectionMode::Single => Vec::new(),
+            SelectionMode::Multiple => self.inner.selected.get_untracked().as_ref().clone(),
+        };
+        if !selected.iter().any(|a| a.id() == item.id()) {
+            selected.push(item.clone());
+        }
+        *self.inner.anchor.borrow_mut() = Some(item.clone());
+        self.replace(selected);
+    }
+
+    /// Deselect an item
+    pub fn deselect(&self, item: &I) {
+        let mut selected = self.inner.selected.get_untracked().as_ref().clone();
+        selected.retain(|a| a.id() != item.id());
+        self.replace(selected);
+    }
+
+    /// Select the item if it is not selected and deselect it otherwise,
+    /// returns `true` if the item is now selected
+    pub fn toggle(&self, item: &I) -> bool {
+        if self.contains(item) {
+            self.deselect(item);
+            false
+        } else {
+            self.select(item);
+            true
+        }
+    }
+
+    /// Select every item between the last item passed to [`select`](RcSelection::select)
+    /// and `item`, both included
+    ///
+    /// Selects only `item` if there is no such anchor or in [`Single`](SelectionMode::Single) mode
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::{RcCollectionSignal, SelectionMode};
+    /// let collection = RcCollectionSignal::new(vec![1, 2, 3, 4]);
+    /// let selection = collection.selection(SelectionMode::Multiple);
+    /// let items = collection.get();
+    /// selection.select(&items[3]);
+    /// selection.select_range(&items[1]);
+    /// assert_eq!(selection.selected().len(), 3);
+    ///```
+    pub fn select_range(&self, item: &I) {
+        let anchor = self.inner.anchor.borrow().clone();
+        let anchor = match anchor {
+            Some(anchor) if self.inner.mode == SelectionMode::Multiple => anchor,
+            _ => return self.select(item),
+        };
+        let items = untrack(|| (self.inner.items)());
+        let position = |target: &I| items.iter().position(|a| a.id() == target.id());
+        let (start, end) = match (position(&anchor), position(item)) {
+            (Some(a), Some(b)) => (a.min(b), a.max(b)),
+            _ => return self.select(item),
+        };
+
+        let mut selected = self.inner.selected.get_untracked().as_ref().clone();
+        for item in &items[start..=end] {
+            if !selected.iter().any(|a| a.id() == item.id()) {
+                selected.push(item.clone());
+            }
+        }
+        self.replace(selected);
+    }
+
+    /// Select every item, does nothing in [`Single`](SelectionMode::Single) mode
+    pub fn select_all(&self) {
+        if self.inner.mode == SelectionMode::Multiple {
+            self.replace(untrack(|| (self.inner.items)()));
+        }
+    }
+
+    /// Deselect every item
+    pub fn clear(&self) {
+        *self.inner.anchor.borrow_mut() = None;
+        self.replace(Vec::new());
+    }
+
+    /// Returns `true` if the item is selected and still in its collection
+    ///
+    /// Only subscribes to the selection state of this item, so subscribers
+    /// are not notified when other items are selected
+    pub fn is_selected(&self, item: &I) -> bool {
+        let flag = self
+            .inner
+            .flags
+            .borrow_mut()
+            .entry(item.id())
+            .or_insert_with(|| (item.clone(), create_rc_signal(self.contains(item))))
+            .1
+            .clone();
+        let selected = *flag.get();
+        selected
+            && untrack(|| (self.inner.items)())
+                .iter()
+                .any(|a| a.id() == item.id())
+    }
+
+    /// Selected items in collection order, removed items are left out
+    ///
+    /// Subscribes to the selection and to the collection
+    pub fn selected(&self) -> Vec<I> {
+        let selected = self.inner.selected.get();
+        (self.inner.items)()
+            .into_iter()
+            .filter(|a| selected.iter().any(|b| b.id() == a.id()))
+            .collect()
+    }
+
+    fn contains(&self, item: &I) -> bool {
+        self.inner
+            .selected
+            .get_untracked()
+            .iter()
+            .any(|a| a.id() == item.id())
+    }
+
+    /// Store the new selection without the removed items and notify the changed flags
+    fn replace(&self, mut selected: Vec<I>) {
+        let items = untrack(|| (self.inner.items)());
+        let present = |item: &I| items.iter().any(|a| a.id() == item.id());
+        selected.retain(present);
+
+        let previous = self.inner.selected.get_untracked();
+        let changed = previous.len() != selected.len()
+            || previous
+                .iter()
+                .zip(&selected)
+                .any(|(a, b)| a.id() != b.id());
+        if changed {
+            self.inner.selected.set(selected);
+
+            let flags = self
+                .inner
+                .flags
+                .borrow()
+                .values()
+                .map(|(item, flag)| (self.contains(item), flag.clone()))
+                .collect::<Vec<_>>();
+            for (is_selected, flag) in flags {
+                if *flag.get_untracked() != is_selected {
+                    flag.set(is_selected);
+                }
+            }
+        }
+        // flags of removed items are notified above before being dropped
+        self.inner
+            .flags
+            .borrow_mut()
+            .retain(|_, (item, _)| present(item));
+    }
+}
+
+impl<I> Clone for RcSelection<I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<I: Debug> Debug for RcSelection<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RcSelection")
+            .field("mode", &self.inner.mode)
+            .field("selected", &self.inner.selected)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
+
+    use crate::{RcCollectionSignal, RcHashMapSignal, SelectionMode};
+
+    #[test]
+    pub fn selection_follows_items_test() {
+        let collection = RcCollectionSignal::new(vec!["a", "b", "c", "d"]);
+        let selection = collection.selection(SelectionMode::Multiple);
+        let items = collection.get();
+
+        selection.select(&items[1]);
+        selection.select_range(&items[3]);
+        items[3].move_up();
+        collection.remove(0);
+        let selected = selection
+            .selected()
+            .iter()
+            .map(|a| *a.get())
+            .collect::<Vec<_>>();
+        assert_eq!(selected, vec!["b", "d", "c"]);
+
+        items[1].remove();
+        assert!(!selection.toggle(&items[2]));
+        assert_eq!(selection.selected().len(), 1);
+
+        selection.select_all();
+        assert_eq!(selection.selected().len(), 2);
+        selection.clear();
+        assert!(selection.selected().is_empty());
+    }
+
+    #[test]
+    pub fn selection_notifies_only_changed_items_test() {
+        create_scope_immediate(|cx| {
+            let collection = RcCollectionSignal::new(vec![1, 2, 3]);
+            let selection = collection.selection(SelectionMode::Single);
+            let items = collection.get();
+            let runs = create_signal(cx, 0);
+            let (tracked, first) = (selection.clone(), items[0].clone());
+            create_effect(cx, move || {
+                tracked.is_selected(&first);
+                runs.set(*runs.get_untracked() + 1);
+            });
+
+            selection.select(&items[1]);
+            selection.select(&items[2]);
+            assert_eq!(*runs.get(), 1);
+            selection.select(&items[0]);
+            selection.select(&items[0]);
+            assert_eq!(*runs.get(), 2);
+            selection.select(&items[1]);
+            assert_eq!(*runs.get(), 3);
+        });
+    }
+
+    #[test]
+    pub fn keyed_selection_test() {
+        let map = RcHashMapSignal::new([("a", 1), ("b", 2)]);
+        let selection = map.selection(SelectionMode::Multiple);
+        let a = map.get_item(&"a").expect("existing item");
+        selection.select(&a);
+        map.insert("a", 10);
+        assert!(selection.is_selected(&a));
+        map.remove(&"a");
+        assert!(selection.selected().is_empty());
+    }
+
+    #[test]
+    fn removal_deselects_test() {
+        create_scope_immediate(|cx| {
+            let collection = RcCollectionSignal::new(vec![1, 2, 3]);
+            let selection = collection.selection(SelectionMode::Multiple);
+            let items = collection.get();
+            selection.select(&items[0]);
+            selection.select(&items[1]);
+            let selected = create_signal(cx, true);
+            let (tracked, first) = (selection.clone(), items[0].clone());
+            create_effect(cx, move || selected.set(tracked.is_selected(&first)));
+
+            items[0].remove();
+            assert!(!*selected.get());
+            assert_eq!(selection.inner.selected.get_untracked().len(), 1);
+            collection.push(4);
+            selection.select_all();
+            let values = selection
+                .selected()
+                .iter()
+                .map(|a| *a.get())
+                .collect::<Vec<_>>();
+            assert_eq!(values, vec![2, 3, 4]);
+
+            collection.remove(0);
+            collection.remove(0);
+            let remaining = collection.get()[0].clone();
+            assert!(selection.is_selected(&remaining));
+            selection.deselect(&remaining);
+            collection.push(5);
+            assert!(selection.selected().is_empty());
+        });
+    }
+}
@@ -0,0 +1,444 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    ops::Deref,
+    rc::Rc,
+};
+
+use sycamore::reactive::{create_rc_signal, RcSignal, ReadSignal, Signal};
+
+use crate::{batch, BatchSignal, MapEntries};
+
+/// Storage backing a [`RcSetSignal`](RcSetSignal)
+///
+/// Implemented for [`HashSet`](HashSet) and [`BTreeSet`](BTreeSet),
+/// `Members` holds the per element membership signals
+pub trait SetEntries<T>: Clone + Default {
+    type Members: MapEntries<T, RcSignal<()>>;
+
+    fn has(&self, value: &T) -> bool;
+    fn insert_value(&mut self, value: T) -> bool;
+    fn remove_value(&mut self, value: &T) -> bool;
+    fn values(&self) -> Box<dyn Iterator<Item = &T> + '_>;
+    fn values_len(&self) -> usize;
+}
+
+impl<T: Hash + Eq + Clone> SetEntries<T> for HashSet<T> {
+    type Members = HashMap<T, RcSignal<()>>;
+
+    fn has(&self, value: &T) -> bool {
+        self.contains(value)
+    }
+
+    fn insert_value(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+
+    fn remove_value(&mut self, value: &T) -> bool {
+        self.remove(value)
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.iter())
+    }
+
+    fn values_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: Ord + Clone> SetEntries<T> for BTreeSet<T> {
+    type Members = BTreeMap<T, RcSignal<()>>;
+
+    fn has(&self, value: &T) -> bool {
+        self.contains(value)
+    }
+
+    fn insert_value(&mut self, value: T) -> bool {
+        self.insert(value)
+    }
+
+    fn remove_value(&mut self, value: &T) -> bool {
+        self.remove(value)
+    }
+
+    fn values(&self) -> Box<dyn Iterator<Item = &T> + '_> {
+        Box::new(self.iter())
+    }
+
+    fn values_len(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Wrapper type for [`RcSignal`](RcSignal)<`S`> where `S` is a set of `T`
+///
+/// Use [`RcHashSetSignal`](RcHashSetSignal) or [`RcBTreeSetSignal`](RcBTreeSetSignal)
+/// instead of naming the storage directly
+///
+/// Base type for the [`#[set]`](crate::State) attribute when using [`State`](crate::State) derive macro
+///
+/// Dereferences to a [`ReadSignal`](ReadSignal) of the whole set,
+/// changes go through the set methods so membership subscribers are notified
+pub struct RcSetSignal<T, S: SetEntries<T>> {
+    inner: Rc<SetInner<T, S>>,
+}
+
+struct SetInner<T, S: SetEntries<T>> {
+    values: RcSignal<S>,
+    members: RefCell<S::Members>,
+    _marker: PhantomData<T>,
+}
+
+impl<T, S: SetEntries<T>> SetInner<T, S> {
+    fn values(&self) -> &Signal<S> {
+        &self.values
+    }
+}
+
+/// Wrapper type for [`RcSignal`](RcSignal)<[`HashSet`](HashSet)<`T`>>
+pub type RcHashSetSignal<T> = RcSetSignal<T, HashSet<T>>;
+
+/// Wrapper type for [`RcSignal`](RcSignal)<[`BTreeSet`](BTreeSet)<`T`>>, iterates in order
+pub type RcBTreeSetSignal<T> = RcSetSignal<T, BTreeSet<T>>;
+
+impl<T: Clone, S: SetEntries<T>> RcSetSignal<T, S> {
+    /// Create new set signal from an iterator
+    pub fn new(values: impl IntoIterator<Item = T>) -> Self {
+        let mut set = S::default();
+        for value in values {
+            set.insert_value(value);
+        }
+        Self {
+            inner: Rc::new(SetInner {
+                values: create_rc_signal(set),
+                members: Default::default(),
+                _marker: PhantomData,
+            }),
+        }
+    }
+
+    /// Returns `true` if the set contains `value`
+    ///
+    /// Inside a reactive scope this only subscribes to the membership of `value`,
+    /// changes to other elements do not notify the caller
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcHashSetSignal;
+    /// # use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
+    /// # create_scope_immediate(|cx| {
+    /// let set = RcHashSetSignal::new(["a"]);
+    /// let runs = create_signal(cx, 0);
+    /// let tracked = set.clone();
+    /// create_effect(cx, move || {
+    ///     tracked.contains(&"a");
+    ///     runs.set(*runs.get_untracked() + 1);
+    /// });
+    /// set.insert("b");
+    /// assert_eq!(*runs.get(), 1);
+    /// set.remove(&"a");
+    /// assert_eq!(*runs.get(), 2);
+    /// # });
+    ///```
+    pub fn contains(&self, value: &T) -> bool {
+        self.member(value).track();
+        self.inner.values.get_untracked().has(value)
+    }
+
+    /// Insert `value`, returns `false` if it was already present
+    pub fn insert(&self, value: T) -> bool {
+        if self.inner.values.get_untracked().has(&value) {
+            return false;
+        }
+        batch::modify(self.inner.values()).insert_value(value.clone());
+        self.notify_member(&value);
+        true
+    }
+
+    /// Remove `value`, returns `false` if it was not present
+    pub fn remove(&self, value: &T) -> bool {
+        if !self.inner.values.get_untracked().has(value) {
+            return false;
+        }
+        batch::modify(self.inner.values()).remove_value(value);
+        self.notify_member(value);
+        true
+    }
+
+    /// Insert `value` if missing or remove it if present, returns whether it is now in the set
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcBTreeSetSignal;
+    /// let set = RcBTreeSetSignal::new([1, 2]);
+    /// assert!(!set.toggle(2));
+    /// assert!(set.toggle(3));
+    /// # assert_eq!(set.get().iter().copied().collect::<Vec<_>>(), vec![1, 3]);
+    ///```
+    pub fn toggle(&self, value: T) -> bool {
+        if self.remove(&value) {
+            false
+        } else {
+            self.insert(value)
+        }
+    }
+
+    /// Insert all the values of an iterator
+    pub fn extend<I: IntoIterator<Item = T>>(&self, values: I) {
+        let current = self.inner.values.get_untracked();
+        let added = values
+            .into_iter()
+            .filter(|value| !current.has(value))
+            .collect::<Vec<_>>();
+        self.apply(added, |set, value| {
+            set.insert_value(value);
+        });
+    }
+
+    /// Retain only the values for which `f` returns `true`
+    pub fn retain<F: FnMut(&T) -> bool>(&self, mut f: F) {
+        let removed = self
+            .inner
+            .values
+            .get_untracked()
+            .values()
+            .filter(|value| !f(value))
+            .cloned()
+            .collect::<Vec<_>>();
+        self.apply(removed, |set, value| {
+            set.remove_value(&value);
+        });
+    }
+
+    /// Remove all the values
+    pub fn clear(&self) {
+        self.retain(|_| false)
+    }
+
+    /// Subscribes to the whole set when called inside a reactive scope
+    pub fn len(&self) -> usize {
+        self.inner.values.get().values_len()
+    }
+
+    /// Subscribes to the whole set when called inside a reactive scope
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Values in either set
+    pub fn union(&self, other: &Self) -> S {
+        let (this, other) = (self.get(), other.get());
+        let mut result = (*this).clone();
+        for value in other.values() {
+            result.insert_value(value.clone());
+        }
+        result
+    }
+
+    /// Values in both sets
+    pub fn intersection(&self, other: &Self) -> S {
+        let (this, other) = (self.get(), other.get());
+        Self::collect(this.values().filter(|value| other.has(value)))
+    }
+
+    /// Values in `self` but not in `other`
+    pub fn difference(&self, other: &Self) -> S {
+        let (this, other) = (self.get(), other.get());
+        Self::collect(this.values().filter(|value| !other.has(value)))
+    }
+
+    /// Values in exactly one of the sets
+    pub fn symmetric_difference(&self, other: &Self) -> S {
+        let (this, other) = (self.get(), other.get());
+        let left = this.values().filter(|value| !other.has(value));
+        let right = other.values().filter(|value| !this.has(value));
+        Self::collect(left.chain(right))
+    }
+
+    /// Returns `true` if every value of `self` is in `other`
+    pub fn is_subset(&self, other: &Self) -> bool {
+        let (this, other) = (self.get(), other.get());
+        let subset = this.values().all(|value| other.has(value));
+        subset
+    }
+
+    /// Returns `true` if every value of `other` is in `self`
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Returns `true` if the sets have no value in common
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        let (this, other) = (self.get(), other.get());
+        let disjoint = this.values().all(|value| !other.has(value));
+        disjoint
+    }
+
+    fn collect<'v>(values: impl Iterator<Item = &'v T>) -> S
+    where
+        T: 'v,
+    {
+        let mut result = S::default();
+        for value in values {
+            result.insert_value(value.clone());
+        }
+        result
+    }
+
+    /// Apply `f` to the set for every value in a single update, then notify their members
+    fn apply(&self, values: Vec<T>, f: impl Fn(&mut S, T)) {
+        if values.is_empty() {
+            return;
+        }
+        let mut set = batch::modify(self.inner.values());
+        for value in values.iter().cloned() {
+            f(&mut set, value);
+        }
+        drop(set);
+        for value in values.iter() {
+            self.notify_member(value);
+        }
+    }
+
+    fn member(&self, value: &T) -> RcSignal<()> {
+        let mut members = self.inner.members.borrow_mut();
+        if let Some(member) = members.entry(value) {
+            return member.clone();
+        }
+        let member = create_rc_signal(());
+        members.insert_entry(value.clone(), member.clone());
+        member
+    }
+
+    /// Membership signals are dropped before notifying, the subscribers that still care
+    /// create a new one when they run again, so the members map only keeps the values
+    /// queried since their last change
+    fn notify_member(&self, value: &T) {
+        let member = self.inner.members.borrow_mut().remove_entry(value);
+        if let Some(member) = member {
+            member.set_batched(());
+        }
+    }
+}
+
+impl<T, S: SetEntries<T>> Deref for RcSetSignal<T, S> {
+    type Target = ReadSignal<S>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.values
+    }
+}
+
+impl<T, S: SetEntries<T>> Clone for RcSetSignal<T, S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T, S: SetEntries<T> + Debug> Debug for RcSetSignal<T, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.values.get_untracked().fmt(f)
+    }
+}
+
+impl<T, S: SetEntries<T> + PartialEq> PartialEq for RcSetSignal<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.values == other.inner.values
+    }
+}
+
+impl<T, S: SetEntries<T> + Eq> Eq for RcSetSignal<T, S> {}
+
+impl<T: Clone, S: SetEntries<T>> Default for RcSetSignal<T, S> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl<T: Clone, S: SetEntries<T>> FromIterator<T> for RcSetSignal<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
+
+    use crate::{MapEntries, RcBTreeSetSignal, RcHashSetSignal};
+
+    #[test]
+    pub fn set_membership_test() {
+        create_scope_immediate(|cx| {
+            let set = RcHashSetSignal::new([1, 2, 3]);
+            let runs = create_signal(cx, 0);
+            let member = create_signal(cx, false);
+            let tracked = set.clone();
+            create_effect(cx, move || {
+                member.set(tracked.contains(&4));
+                runs.set(*runs.get_untracked() + 1);
+            });
+
+            set.remove(&1);
+            set.extend([5, 6]);
+            assert_eq!(*runs.get(), 1);
+
+            assert!(set.toggle(4));
+            assert!(*member.get());
+            set.retain(|a| a % 2 == 1);
+            assert!(!*member.get());
+            assert_eq!(*runs.get(), 3);
+        });
+    }
+
+    #[test]
+    pub fn set_members_pruned_test() {
+        create_scope_immediate(|cx| {
+            let set = RcHashSetSignal::new([1, 2, 3]);
+            let runs = create_signal(cx, 0);
+            let tracked = set.clone();
+            create_effect(cx, move || {
+                tracked.contains(&1);
+                runs.set(*runs.get_untracked() + 1);
+            });
+            set.contains(&2);
+            set.contains(&3);
+            assert_eq!(set.inner.members.borrow().entries_len(), 3);
+
+            set.remove(&2);
+            set.retain(|a| *a != 3);
+            assert_eq!(set.inner.members.borrow().entries_len(), 1);
+
+            set.remove(&1);
+            set.insert(1);
+            assert_eq!(*runs.get(), 3);
+            assert_eq!(set.inner.members.borrow().entries_len(), 1);
+        });
+    }
+
+    #[test]
+    pub fn set_algebra_test() {
+        let left = RcBTreeSetSignal::new([1, 2, 3]);
+        let right = RcBTreeSetSignal::new([3, 4]);
+        assert_eq!(left.union(&right), BTreeSet::from([1, 2, 3, 4]));
+        assert_eq!(left.intersection(&right), BTreeSet::from([3]));
+        assert_eq!(left.difference(&right), BTreeSet::from([1, 2]));
+        assert_eq!(left.symmetric_difference(&right), BTreeSet::from([1, 2, 4]));
+        assert!(!left.is_disjoint(&right));
+
+        right.remove(&3);
+        assert!(left.is_disjoint(&right));
+        left.extend([4]);
+        assert!(left.is_superset(&right));
+        assert!(right.is_subset(&left));
+
+        left.clear();
+        assert!(left.is_empty());
+    }
+}
@@ -0,0 +1,546 @@
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    fmt::Debug,
+    ops::{Bound, Deref, RangeBounds},
+    rc::{Rc, Weak},
+};
+
+use sycamore::reactive::{
+    create_effect, create_rc_signal, create_scope, untrack, RcSignal, ReadSignal, Scope,
+    ScopeDisposer, Signal,
+};
+
+use crate::{batch, RcSelection, SelectionMode};
+
+type Comparator<T> = Box<dyn Fn(&T, &T) -> Ordering>;
+
+struct SortedInner<T> {
+    items: RcSignal<Vec<RcSortedItem<T>>>,
+    compare: Comparator<T>,
+    /// Root scope of the effect started by [`watched`](RcSortedCollectionSignal::watched)
+    watcher: RefCell<Option<ScopeDisposer<'static>>>,
+}
+
+impl<T> Drop for SortedInner<T> {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            // SAFETY: the effect only runs while the collection is alive and never drops it
+            unsafe { watcher.dispose() };
+        }
+    }
+}
+
+impl<T> SortedInner<T> {
+    fn items(&self) -> &Signal<Vec<RcSortedItem<T>>> {
+        &self.items
+    }
+
+    /// Index at which `value` would be inserted, after any equal item
+    fn insertion_point(&self, items: &[RcSortedItem<T>], value: &T) -> usize {
+        items.partition_point(|a| (self.compare)(&a.get_untracked(), value) != Ordering::Greater)
+    }
+
+    fn position(&self, item: &RcSortedItem<T>) -> Option<usize> {
+        self.items
+            .get_untracked()
+            .iter()
+            .position(|a| a.ptr_eq(item))
+    }
+
+    /// Move the item at `index` to its sorted position, returns `false` if it already was
+    fn reposition(self: &Rc<Self>, index: usize) -> bool {
+        let items = self.items.get_untracked();
+        let value = items[index].get_untracked();
+        let after_prev = index == 0
+            || (self.compare)(&items[index - 1].get_untracked(), &value) != Ordering::Greater;
+        let before_next = index + 1 == items.len()
+            || (self.compare)(&value, &items[index + 1].get_untracked()) != Ordering::Greater;
+        drop(items);
+        if after_prev && before_next {
+            return false;
+        }
+
+        let mut items = batch::modify(self.items());
+        let item = items.remove(index);
+        let point = self.insertion_point(&items, &value);
+        items.insert(point, item);
+        true
+    }
+
+    /// Sort the items again if they are out of order
+    fn resort(self: &Rc<Self>) {
+        let items = self.items.get_untracked();
+        let sorted = items.windows(2).all(|pair| {
+            (self.compare)(&pair[0].get_untracked(), &pair[1].get_untracked()) != Ordering::Greater
+        });
+        drop(items);
+        if !sorted {
+            batch::modify(self.items())
+                .sort_by(|a, b| (self.compare)(&a.get_untracked(), &b.get_untracked()));
+        }
+    }
+
+    /// Subscribe to the values the comparator reads on every item, then sort them again
+    fn track_and_resort(self: &Rc<Self>) {
+        let items = self.items.get();
+        for item in items.iter() {
+            let value = item.get();
+            // Comparing a value with itself reads exactly what ordering it depends on
+            (self.compare)(&value, &value);
+        }
+        untrack(|| self.resort());
+    }
+}
+
+/// Wrapper type for [`RcSignal`](RcSignal)<[`Vec`](Vec)<[`RcSortedItem`](RcSortedItem)<`T`>>>
+/// kept ordered by a comparator
+///
+/// Base type for the [`#[collection(sorted_by = ..)]`](crate::State) attribute when using [`State`](crate::State) derive macro
+///
+/// Values are inserted with a binary search and items changed through their
+/// [`RcSortedItem`](RcSortedItem) handle are moved back in place, items equal
+/// for the comparator keep their insertion order
+///
+/// When the comparator reads signals inside the items, like the fields of nested states,
+/// [`watch`](RcSortedCollectionSignal::watch) or [`watched`](RcSortedCollectionSignal::watched)
+/// keep the collection sorted as they change
+pub struct RcSortedCollectionSignal<T> {
+    inner: Rc<SortedInner<T>>,
+}
+
+/// Handle to an item of a [`RcSortedCollectionSignal`](RcSortedCollectionSignal)
+///
+/// Dereferences to the item's [`ReadSignal`](ReadSignal), writes go through
+/// [`set`](RcSortedItem::set) and [`update`](RcSortedItem::update) so the
+/// collection stays sorted
+pub struct RcSortedItem<T> {
+    inner: RcSignal<T>,
+    collection: Weak<SortedInner<T>>,
+}
+
+impl<T> RcSortedItem<T> {
+    /// Current rank of the item in its collection, [`None`] once it has been removed
+    ///
+    /// Subscribes to the collection when called inside a reactive scope
+    pub fn index(&self) -> Option<usize> {
+        let collection = self.collection.upgrade()?;
+        let items = collection.items.get();
+        items.iter().position(|a| a.ptr_eq(self))
+    }
+
+    /// Set the value of the item, moving it if its position changed
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// let collection = RcSortedCollectionSignal::new(|a: &i32, b: &i32| a.cmp(b), vec![1, 2, 3]);
+    /// let item = collection.get()[0].clone();
+    /// item.set(5);
+    /// assert_eq!(item.index(), Some(2));
+    ///```
+    pub fn set(&self, value: T) {
+        self.inner.set(value);
+        if let Some(collection) = self.collection.upgrade() {
+            if let Some(index) = collection.position(self) {
+                collection.reposition(index);
+            }
+        }
+    }
+
+    /// Update the value of the item from the previous one, moving it if its position changed
+    pub fn update(&self, f: impl FnOnce(&T) -> T) {
+        let value = f(&self.inner.get_untracked());
+        self.set(value);
+    }
+
+    /// Remove the item from its collection
+    pub fn remove(&self) {
+        if let Some(collection) = self.collection.upgrade() {
+            if let Some(index) = collection.position(self) {
+                batch::modify(collection.items()).remove(index);
+            }
+        }
+    }
+
+    /// Returns `true` if both handles point to the same item
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        std::ptr::eq::<Signal<T>>(&*self.inner, &*other.inner)
+    }
+}
+
+impl<T> Clone for RcSortedItem<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            collection: self.collection.clone(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for RcSortedItem<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for RcSortedItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Eq> Eq for RcSortedItem<T> {}
+
+impl<T> Deref for RcSortedItem<T> {
+    type Target = ReadSignal<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> RcSortedCollectionSignal<T> {
+    /// Create new sorted collection from an iterator, ordered by `compare`
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// let collection = RcSortedCollectionSignal::new(|a: &i32, b: &i32| b.cmp(a), vec![2, 3, 1]);
+    /// let values = collection.get().iter().map(|a| *a.get()).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![3, 2, 1]);
+    ///```
+    pub fn new(
+        compare: impl Fn(&T, &T) -> Ordering + 'static,
+        inner: impl IntoIterator<Item = T>,
+    ) -> RcSortedCollectionSignal<T> {
+        let collection = RcSortedCollectionSignal {
+            inner: Rc::new(SortedInner {
+                items: create_rc_signal(Vec::new()),
+                compare: Box::new(compare),
+                watcher: RefCell::new(None),
+            }),
+        };
+        let mut collected = inner
+            .into_iter()
+            .map(|a| collection.make_item(a))
+            .collect::<Vec<_>>();
+        collected
+            .sort_by(|a, b| (collection.inner.compare)(&a.get_untracked(), &b.get_untracked()));
+        collection.inner.items.set_silent(collected);
+        collection
+    }
+
+    fn make_item(&self, value: T) -> RcSortedItem<T> {
+        RcSortedItem {
+            inner: create_rc_signal(value),
+            collection: Rc::downgrade(&self.inner),
+        }
+    }
+
+    /// Insert new value at its sorted position, returning its handle
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// let collection = RcSortedCollectionSignal::new(|a: &i32, b: &i32| a.cmp(b), vec![1, 3]);
+    /// let item = collection.insert(2);
+    /// assert_eq!(item.index(), Some(1));
+    ///```
+    pub fn insert(&self, value: T) -> RcSortedItem<T> {
+        let item = self.make_item(value);
+        let mut items = batch::modify(self.inner.items());
+        let point = self.inner.insertion_point(&items, &item.get_untracked());
+        items.insert(point, item.clone());
+        item
+    }
+
+    /// Insert every value of an iterator
+    pub fn extend(&self, values: impl IntoIterator<Item = T>) {
+        let mut items = batch::modify(self.inner.items());
+        for value in values {
+            let item = self.make_item(value);
+            let point = self.inner.insertion_point(&items, &item.get_untracked());
+            items.insert(point, item);
+        }
+    }
+
+    /// Remove value from collection by rank
+    pub fn remove(&self, index: usize) -> Rc<T> {
+        batch::modify(self.inner.items())
+            .remove(index)
+            .get_untracked()
+    }
+
+    /// Remove all the values
+    pub fn clear(&self) {
+        if !self.inner.items.get_untracked().is_empty() {
+            batch::set(self.inner.items(), Vec::new());
+        }
+    }
+
+    /// Remove the first value matching a predicate
+    pub fn remove_where<F: Fn(&T) -> bool>(&self, f: F) -> Option<Rc<T>> {
+        let index = self
+            .inner
+            .items
+            .get_untracked()
+            .iter()
+            .position(|a| f(&a.get_untracked()))?;
+        Some(self.remove(index))
+    }
+
+    /// Number of values ordered strictly before `value`
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// let collection = RcSortedCollectionSignal::new(|a: &i32, b: &i32| a.cmp(b), vec![10, 20, 30]);
+    /// assert_eq!(collection.rank(&20), 1);
+    /// assert_eq!(collection.rank(&25), 2);
+    ///```
+    pub fn rank(&self, value: &T) -> usize {
+        let items = self.inner.items.get();
+        items.partition_point(|a| (self.inner.compare)(&a.get_untracked(), value) == Ordering::Less)
+    }
+
+    /// Items whose value falls within `range`
+    ///
+    /// ```rust
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// let collection = RcSortedCollectionSignal::new(|a: &i32, b: &i32| a.cmp(b), 0..10);
+    /// let values = collection.range(3..6).iter().map(|a| *a.get()).collect::<Vec<_>>();
+    /// assert_eq!(values, vec![3, 4, 5]);
+    ///```
+    pub fn range(&self, range: impl RangeBounds<T>) -> Vec<RcSortedItem<T>> {
+        let items = self.inner.items.get();
+        let compare = |a: &RcSortedItem<T>, b: &T| (self.inner.compare)(&a.get_untracked(), b);
+        let start = match range.start_bound() {
+            Bound::Included(start) => items.partition_point(|a| compare(a, start).is_lt()),
+            Bound::Excluded(start) => items.partition_point(|a| compare(a, start).is_le()),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => items.partition_point(|a| compare(a, end).is_le()),
+            Bound::Excluded(end) => items.partition_point(|a| compare(a, end).is_lt()),
+            Bound::Unbounded => items.len(),
+        };
+        items[start..end.max(start)].to_vec()
+    }
+
+    /// First value in sort order
+    pub fn first(&self) -> Option<RcSortedItem<T>> {
+        self.inner.items.get().first().cloned()
+    }
+
+    /// Last value in sort order
+    pub fn last(&self) -> Option<RcSortedItem<T>> {
+        self.inner.items.get().last().cloned()
+    }
+
+    /// Sort the collection again, needed after changing what the comparator reads
+    /// outside of a [`watch`](RcSortedCollectionSignal::watch)ed collection
+    ///
+    /// Subscribers are only notified if the order changed
+    pub fn resort(&self) {
+        self.inner.resort();
+    }
+
+    /// Keep the collection sorted while `cx` is alive, moving items when the signals
+    /// the comparator reads on them change
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::{create_rc_signal, create_scope_immediate, RcSignal};
+    /// # use sycamore_state_core::RcSortedCollectionSignal;
+    /// # create_scope_immediate(|cx| {
+    /// let by_score = |a: &RcSignal<u32>, b: &RcSignal<u32>| a.get().cmp(&b.get());
+    /// let scores = vec![create_rc_signal(1), create_rc_signal(2)];
+    /// let collection = RcSortedCollectionSignal::new(by_score, scores);
+    /// collection.watch(cx);
+    /// collection.get()[0].get().set(3);
+    /// assert_eq!(*collection.get()[0].get().get(), 2);
+    /// # });
+    ///```
+    pub fn watch<'a>(&self, cx: Scope<'a>)
+    where
+        T: 'a,
+    {
+        let inner = self.inner.clone();
+        create_effect(cx, move || inner.track_and_resort());
+    }
+
+    /// Number of values in the collection
+    pub fn len(&self) -> usize {
+        self.inner.items.get().len()
+    }
+
+    /// Returns `true` if the collection holds no value
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T: 'static> RcSortedCollectionSignal<T> {
+    /// Like [`watch`](RcSortedCollectionSignal::watch) for as long as the collection lives
+    pub fn watched(self) -> Self {
+        let inner = Rc::downgrade(&self.inner);
+        let watcher = create_scope(move |cx| {
+            create_effect(cx, move || {
+                if let Some(inner) = inner.upgrade() {
+                    inner.track_and_resort();
+                }
+            })
+        });
+        *self.inner.watcher.borrow_mut() = Some(watcher);
+        self
+    }
+
+    /// Create a selection over the items of the collection
+    pub fn selection(&self, mode: SelectionMode) -> RcSelection<RcSortedItem<T>> {
+        let items = self.inner.items.clone();
+        RcSelection::new(mode, move || items.get().as_ref().clone())
+    }
+}
+
+impl<T> Clone for RcSortedCollectionSignal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Debug> Debug for RcSortedCollectionSignal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self.inner.items.get_untracked();
+        f.debug_list()
+            .entries(items.iter().map(|a| a.get_untracked()))
+            .finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for RcSortedCollectionSignal<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.items == other.inner.items
+    }
+}
+
+impl<T: Eq> Eq for RcSortedCollectionSignal<T> {}
+
+impl<T> Deref for RcSortedCollectionSignal<T> {
+    type Target = ReadSignal<Vec<RcSortedItem<T>>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sycamore::reactive::{create_effect, create_scope_immediate, create_signal};
+
+    use crate::RcSortedCollectionSignal;
+
+    #[derive(Debug, PartialEq)]
+    struct Score {
+        name: &'static str,
+        points: u32,
+    }
+
+    fn by_points(a: &Score, b: &Score) -> std::cmp::Ordering {
+        b.points.cmp(&a.points)
+    }
+
+    fn names(collection: &RcSortedCollectionSignal<Score>) -> Vec<&'static str> {
+        collection.get().iter().map(|a| a.get().name).collect()
+    }
+
+    #[test]
+    pub fn sorted_insert_test() {
+        let board = RcSortedCollectionSignal::new(
+            by_points,
+            vec![
+                Score {
+                    name: "a",
+                    points: 10,
+                },
+                Score {
+                    name: "b",
+                    points: 30,
+                },
+            ],
+        );
+        board.insert(Score {
+            name: "c",
+            points: 20,
+        });
+        board.insert(Score {
+            name: "d",
+            points: 20,
+        });
+        assert_eq!(names(&board), vec!["b", "c", "d", "a"]);
+
+        assert_eq!(
+            board.rank(&Score {
+                name: "",
+                points: 20
+            }),
+            1
+        );
+        let middle = board.range(
+            Score {
+                name: "",
+                points: 25,
+            }..Score {
+                name: "",
+                points: 15,
+            },
+        );
+        assert_eq!(middle.len(), 2);
+    }
+
+    #[test]
+    pub fn sorted_item_reposition_test() {
+        create_scope_immediate(|cx| {
+            let board = RcSortedCollectionSignal::new(
+                by_points,
+                vec![
+                    Score {
+                        name: "a",
+                        points: 10,
+                    },
+                    Score {
+                        name: "b",
+                        points: 20,
+                    },
+                    Score {
+                        name: "c",
+                        points: 30,
+                    },
+                ],
+            );
+            let runs = create_signal(cx, 0);
+            let tracked = board.clone();
+            create_effect(cx, move || {
+                tracked.track();
+                runs.set(*runs.get_untracked() + 1);
+            });
+
+            let a = board.get()[2].clone();
+            a.update(|a| Score {
+                name: a.name,
+                points: a.points + 1,
+            });
+            assert_eq!(*runs.get(), 1);
+            a.set(Score {
+                name: "a",
+                points: 40,
+            });
+            assert_eq!(*runs.get(), 2);
+            assert_eq!(a.index(), Some(0));
+            assert_eq!(names(&board), vec!["a", "c", "b"]);
+
+            board.resort();
+            assert_eq!(*runs.get(), 2);
+            a.remove();
+            assert_eq!(a.index(), None);
+            assert_eq!(board.first().map(|a| a.get().name), Some("c"));
+        });
+    }
+}
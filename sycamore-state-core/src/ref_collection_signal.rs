@@ -1,50 +1,197 @@
-use std::{ops::Deref, rc::Rc};
+use indexmap::IndexMap;
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    hash::Hash,
+    ops::{Add, Deref, Sub},
+    rc::Rc,
+};
 use sycamore::prelude::*;
 
-/// Wrapper type for [`&Signal`](Signal)<[`Vec`](Vec)<[`&Signal`](Signal)<`T`>>>
+use crate::{batch, collection_views};
+
+type RefItems<'a, T> = Signal<Vec<RefCollectionItem<'a, T>>>;
+
+/// Wrapper type for [`&Signal`](Signal)<[`Vec`](Vec)<[`RefCollectionItem`](RefCollectionItem)<`T`>>>
 ///
 /// Base type for the [`#[collection]`](crate::State) attribute when using [`State`](crate::State) derive macro
-#[derive(Debug, PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct RefCollectionSignal<'a, T> {
-    inner: &'a Signal<Vec<&'a Signal<T>>>,
+    inner: &'a RefItems<'a, T>,
+}
+
+/// Formats the values of the items, untracked
+impl<'a, T: Debug> Debug for RefCollectionSignal<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items = self.inner.get_untracked();
+        f.debug_list()
+            .entries(items.iter().map(|a| a.get_untracked()))
+            .finish()
+    }
 }
 
 impl<'a, T> Clone for RefCollectionSignal<'a, T> {
     fn clone(&self) -> Self {
-        Self {
-            inner: <&Signal<Vec<&Signal<T>>>>::clone(&self.inner),
-        }
+        *self
     }
 }
 
 impl<'a, T> Copy for RefCollectionSignal<'a, T> {}
 
+/// Handle to an item of a [`RefCollectionSignal`](RefCollectionSignal)
+///
+/// Dereferences to the item's [`Signal`](Signal) and can remove or move itself
+/// inside its collection without knowing its index
+pub struct RefCollectionItem<'a, T> {
+    inner: &'a Signal<T>,
+    collection: &'a RefItems<'a, T>,
+}
+
+impl<'a, T> RefCollectionItem<'a, T> {
+    /// Current position of the item in its collection, [`None`] once it has been removed
+    ///
+    /// Subscribes to the collection when called inside a reactive scope
+    pub fn index(&self) -> Option<usize> {
+        self.collection.get().iter().position(|a| a.ptr_eq(self))
+    }
+
+    /// Remove the item from its collection
+    pub fn remove(&self) {
+        if let Some(index) = self.locate() {
+            batch::modify(self.collection).remove(index);
+        }
+    }
+
+    /// Swap the item with the previous one, does nothing if the item is first
+    pub fn move_up(&self) {
+        if let Some(index) = self.locate() {
+            if index > 0 {
+                batch::modify(self.collection).swap(index, index - 1);
+            }
+        }
+    }
+
+    /// Swap the item with the next one, does nothing if the item is last
+    pub fn move_down(&self) {
+        if let Some(index) = self.locate() {
+            if index + 1 < self.collection.get_untracked().len() {
+                batch::modify(self.collection).swap(index, index + 1);
+            }
+        }
+    }
+
+    /// Replace the value of the item, returning the previous one
+    ///
+    /// Subscribers of the item are notified, the item keeps its position
+    pub fn replace(&self, value: T) -> Rc<T> {
+        let old = self.inner.get_untracked();
+        self.inner.set(value);
+        old
+    }
+
+    /// Returns `true` if both handles point to the same item
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.inner, other.inner)
+    }
+
+    fn locate(&self) -> Option<usize> {
+        self.collection
+            .get_untracked()
+            .iter()
+            .position(|a| a.ptr_eq(self))
+    }
+}
+
+impl<'a, T> Clone for RefCollectionItem<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for RefCollectionItem<'a, T> {}
+
+impl<'a, T: Debug> Debug for RefCollectionItem<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<'a, T: PartialEq> PartialEq for RefCollectionItem<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<'a, T: Eq> Eq for RefCollectionItem<'a, T> {}
+
+impl<'a, T: Hash> Hash for RefCollectionItem<'a, T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state)
+    }
+}
+
+impl<'a, T> Deref for RefCollectionItem<'a, T> {
+    type Target = Signal<T>;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+    }
+}
+
 impl<T> RefCollectionSignal<'_, T> {
     /// Create new [`RefCollection`](RefCollectionSignal) from an iterator
-    pub fn new<'a>(cx: Scope<'a>, inner: impl IntoIterator<Item = T>) -> RefCollectionSignal<T>
+    pub fn new<'a>(cx: Scope<'a>, inner: impl IntoIterator<Item = T>) -> RefCollectionSignal<'a, T>
     where
         T: 'a,
     {
+        let collection = RefCollectionSignal::<'a, T> {
+            inner: unsafe { create_signal_unsafe(cx, Vec::new()) },
+        };
         let collected = inner
             .into_iter()
-            .map(|a| unsafe { create_signal_unsafe(cx, a) })
+            .map(|a| collection.make_item(cx, a))
             .collect::<Vec<_>>();
-        RefCollectionSignal::<'a, T> {
-            inner: unsafe { create_signal_unsafe(cx, collected) },
-        }
+        collection.inner.set_silent(collected);
+        collection
     }
 }
 
 impl<'a, T> RefCollectionSignal<'a, T> {
+    fn make_item(&self, cx: Scope<'a>, value: T) -> RefCollectionItem<'a, T>
+    where
+        T: 'a,
+    {
+        RefCollectionItem {
+            inner: unsafe { create_signal_unsafe(cx, value) },
+            collection: self.inner,
+        }
+    }
+
     /// Push new value with associated [`scope`](Scope)
     pub fn push_value<'b>(&self, cx: Scope<'a>, value: T)
     where
         T: 'b,
         'b: 'a,
     {
-        self.inner
-            .modify()
-            .push(unsafe { create_signal_unsafe(cx, value) });
+        let item = self.make_item(cx, value);
+        batch::modify(self.inner).push(item);
+    }
+
+    /// Insert new value at `index` with associated [`scope`](Scope), shifting the items after it
+    ///
+    /// Panics if `index` is greater than the length of the collection
+    pub fn insert_value<'b>(&self, cx: Scope<'a>, index: usize, value: T)
+    where
+        T: 'b,
+        'b: 'a,
+    {
+        let item = self.make_item(cx, value);
+        batch::modify(self.inner).insert(index, item);
+    }
+
+    /// Remove every value from collection
+    pub fn clear(&self) {
+        batch::modify(self.inner).clear();
     }
 
     /// Push new value with associated [`scope`](Scope) and a closure
@@ -52,9 +199,8 @@ impl<'a, T> RefCollectionSignal<'a, T> {
     where
         T: 'a,
     {
-        self.inner
-            .modify()
-            .push(unsafe { create_signal_unsafe(cx, value(cx)) });
+        let item = self.make_item(cx, value(cx));
+        batch::modify(self.inner).push(item);
     }
 
     /// Get position of value in collection
@@ -97,7 +243,7 @@ impl<'a, T> RefCollectionSignal<'a, T> {
     /// # assert_eq!(collection.get().len(), 3);
     ///```
     pub fn remove(&self, index: usize) -> Rc<T> {
-        self.inner.modify().remove(index).get()
+        batch::modify(self.inner).remove(index).get()
     }
 
     /// Remove value from collection with predicate
@@ -114,10 +260,221 @@ impl<'a, T> RefCollectionSignal<'a, T> {
     }
 }
 
+impl<'a, T: 'a> RefCollectionSignal<'a, T> {
+    /// Live view of the items matching `predicate`, in collection order
+    ///
+    /// The predicate only runs again for items whose value changed
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::{RefCollectionSignal};
+    /// # create_scope_immediate(|cx| {
+    /// let collection = RefCollectionSignal::new(cx, vec![1, 2, 3, 4]);
+    /// let even = collection.filter_view(cx, |a| a % 2 == 0);
+    /// collection.get()[0].set(10);
+    /// assert_eq!(even.get().len(), 3);
+    /// # });
+    ///```
+    pub fn filter_view(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<Vec<RefCollectionItem<'a, T>>> {
+        let collection = *self;
+        collection_views::filter_view(cx, move || collection.get(), predicate)
+    }
+
+    /// Live view of the items ordered by `compare`
+    ///
+    /// Each update starts from the previous order, so changing a few items does not resort everything
+    pub fn sorted_view(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Vec<RefCollectionItem<'a, T>>> {
+        let collection = *self;
+        collection_views::sorted_view(cx, move || collection.get(), compare)
+    }
+
+    /// Live view of the values mapped by `f`, in collection order
+    ///
+    /// `f` only runs again for items whose value changed
+    pub fn map_view<U: Clone + 'a>(
+        &self,
+        cx: Scope<'a>,
+        f: impl Fn(&T) -> U + 'a,
+    ) -> &'a ReadSignal<Vec<U>> {
+        let collection = *self;
+        collection_views::map_view(cx, move || collection.get(), f)
+    }
+
+    /// Live view of the items grouped by `key`, groups are ordered by first appearance
+    ///
+    /// Every group is a signal of its own, only notified when its items change,
+    /// the map itself only changes when groups appear, disappear or move
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::{RefCollectionSignal};
+    /// # create_scope_immediate(|cx| {
+    /// let collection = RefCollectionSignal::new(cx, vec!["apple", "avocado", "banana"]);
+    /// let groups = collection.group_by(cx, |a| a.chars().next());
+    /// assert_eq!(groups.get()[&Some('a')].get().len(), 2);
+    /// # });
+    ///```
+    pub fn group_by<K: Hash + Eq + Clone + 'a>(
+        &self,
+        cx: Scope<'a>,
+        key: impl Fn(&T) -> K + 'a,
+    ) -> &'a ReadSignal<IndexMap<K, &'a ReadSignal<Vec<RefCollectionItem<'a, T>>>>> {
+        let collection = *self;
+        collection_views::group_by(cx, move || collection.get(), key)
+    }
+
+    /// Live view of at most `len` items starting at `offset`
+    ///
+    /// Only insertions and removals update the page, not changes of the items
+    pub fn page(
+        &self,
+        cx: Scope<'a>,
+        offset: usize,
+        len: usize,
+    ) -> &'a ReadSignal<Vec<RefCollectionItem<'a, T>>> {
+        let collection = *self;
+        collection_views::page(cx, move || collection.get(), offset, len)
+    }
+
+    /// Number of items, only notifies when it changes
+    pub fn len(&self, cx: Scope<'a>) -> &'a ReadSignal<usize> {
+        let collection = *self;
+        collection_views::len(cx, move || collection.get())
+    }
+
+    /// Returns `true` if the collection holds no item, only notifies when it changes
+    pub fn is_empty(&self, cx: Scope<'a>) -> &'a ReadSignal<bool> {
+        let len = self.len(cx);
+        create_selector(cx, move || *len.get() == 0)
+    }
+
+    /// Number of items matching `predicate`, only notifies when it changes
+    ///
+    /// The predicate only runs again for items whose value changed, the count is updated by the difference
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::RefCollectionSignal;
+    /// # create_scope_immediate(|cx| {
+    /// let todos = RefCollectionSignal::new(cx, vec![("a", true), ("b", false)]);
+    /// let done = todos.count_where(cx, |(_, done)| *done);
+    /// todos.get()[1].set(("b", true));
+    /// assert_eq!(*done.get(), 2);
+    /// # });
+    ///```
+    pub fn count_where(
+        &self,
+        cx: Scope<'a>,
+        predicate: impl Fn(&T) -> bool + 'a,
+    ) -> &'a ReadSignal<usize> {
+        let collection = *self;
+        collection_views::count_where(cx, move || collection.get(), predicate)
+    }
+
+    /// Sum of the values mapped by `f`, only notifies when it changes
+    ///
+    /// `f` only runs again for items whose value changed, the sum is updated by the difference
+    ///
+    /// ```rust
+    /// # use sycamore::reactive::create_scope_immediate;
+    /// # use sycamore_state_core::RefCollectionSignal;
+    /// # create_scope_immediate(|cx| {
+    /// let prices = RefCollectionSignal::new(cx, vec![("a", 3), ("b", 4)]);
+    /// let total = prices.sum_by(cx, |(_, price)| *price);
+    /// prices.get()[0].set(("a", 5));
+    /// assert_eq!(*total.get(), 9);
+    /// # });
+    ///```
+    pub fn sum_by<S>(&self, cx: Scope<'a>, f: impl Fn(&T) -> S + 'a) -> &'a ReadSignal<S>
+    where
+        S: Add<Output = S> + Sub<Output = S> + Default + Clone + PartialEq + 'a,
+    {
+        let collection = *self;
+        collection_views::sum_by(cx, move || collection.get(), f)
+    }
+
+    /// Returns `true` if any item matches `predicate`, only notifies when it changes
+    pub fn any(&self, cx: Scope<'a>, predicate: impl Fn(&T) -> bool + 'a) -> &'a ReadSignal<bool> {
+        let count = self.count_where(cx, predicate);
+        create_selector(cx, move || *count.get() > 0)
+    }
+
+    /// Returns `true` if every item matches `predicate`, only notifies when it changes
+    pub fn all(&self, cx: Scope<'a>, predicate: impl Fn(&T) -> bool + 'a) -> &'a ReadSignal<bool> {
+        let (count, len) = (self.count_where(cx, predicate), self.len(cx));
+        create_selector(cx, move || *count.get() == *len.get())
+    }
+
+    /// Smallest value according to `compare`, the first one if several are equal
+    ///
+    /// Only notifies when another value becomes the minimum
+    pub fn min_by(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Option<Rc<T>>> {
+        let collection = *self;
+        collection_views::extremum_by(cx, move || collection.get(), compare, Ordering::Less)
+    }
+
+    /// Greatest value according to `compare`, the first one if several are equal
+    ///
+    /// Only notifies when another value becomes the maximum
+    pub fn max_by(
+        &self,
+        cx: Scope<'a>,
+        compare: impl Fn(&T, &T) -> Ordering + 'a,
+    ) -> &'a ReadSignal<Option<Rc<T>>> {
+        let collection = *self;
+        collection_views::extremum_by(cx, move || collection.get(), compare, Ordering::Greater)
+    }
+}
+
 impl<'a, T> Deref for RefCollectionSignal<'a, T> {
-    type Target = &'a Signal<Vec<&'a Signal<T>>>;
+    type Target = &'a RefItems<'a, T>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sycamore::reactive::create_scope_immediate;
+
+    use crate::RefCollectionSignal;
+
+    #[test]
+    pub fn item_handle_test() {
+        create_scope_immediate(|cx| {
+            let collection = RefCollectionSignal::new(cx, vec![1, 2, 3]);
+            let items = collection.get();
+            let (first, last) = (items[0], items[2]);
+
+            last.move_up();
+            assert_eq!(last.index(), Some(1));
+            first.move_down();
+            assert_eq!(first.index(), Some(1));
+
+            first.remove();
+            assert_eq!(first.index(), None);
+            assert_eq!(last.index(), Some(0));
+
+            assert_eq!(*last.replace(30), 3);
+            let values = collection
+                .get()
+                .iter()
+                .map(|a| *a.get())
+                .collect::<Vec<_>>();
+            assert_eq!(values, vec![30, 2]);
+        });
+    }
+}
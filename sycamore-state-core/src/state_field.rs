@@ -0,0 +1,80 @@
+use sycamore::reactive::Scope;
+
+/// Custom mapping of a plain field type `T` to its reactive types
+///
+/// Used with the `#[state(with = ..)]` attribute of the `State` derive, the field is
+/// declared as [`Rc`](StateField::Rc) in the Rc state and as [`Ref`](StateField::Ref)
+/// in the Ref state, which allows plugging reactive containers the derive does not know about
+///
+/// ```rust
+/// # use sycamore::reactive::{create_rc_signal, create_signal, RcSignal, Scope, Signal};
+/// # use sycamore_state_core::StateField;
+/// /// Stores text as a list of lines
+/// pub struct Lines;
+///
+/// impl StateField<String> for Lines {
+///     type Rc = RcSignal<Vec<String>>;
+///     type Ref<'a> = &'a Signal<Vec<String>>;
+///
+///     fn new_rc(value: String) -> Self::Rc {
+///         create_rc_signal(value.lines().map(Into::into).collect())
+///     }
+///
+///     fn new_ref<'a>(cx: Scope<'a>, value: String) -> Self::Ref<'a> {
+///         create_signal(cx, value.lines().map(Into::into).collect())
+///     }
+///
+///     fn snapshot_rc(field: &Self::Rc) -> String {
+///         field.get_untracked().join("\n")
+///     }
+///
+///     fn snapshot_ref(field: &Self::Ref<'_>) -> String {
+///         field.get_untracked().join("\n")
+///     }
+///
+///     fn set_rc(field: &Self::Rc, value: String) {
+///         field.set(value.lines().map(Into::into).collect());
+///     }
+///
+///     fn track_rc(field: &Self::Rc) {
+///         field.track();
+///     }
+///
+///     fn track_ref(field: &Self::Ref<'_>) {
+///         field.track();
+///     }
+/// }
+/// # let lines = Lines::new_rc("a\nb".into());
+/// # assert_eq!(Lines::snapshot_rc(&lines), "a\nb");
+/// ```
+pub trait StateField<T> {
+    /// Type of the field in the Rc state
+    type Rc;
+    /// Type of the field in the Ref state
+    type Ref<'a>
+    where
+        T: 'a;
+
+    /// Create the Rc field from its plain value
+    fn new_rc(value: T) -> Self::Rc;
+
+    /// Create the Ref field from its plain value
+    fn new_ref<'a>(cx: Scope<'a>, value: T) -> Self::Ref<'a>;
+
+    /// Plain value currently held by the Rc field
+    fn snapshot_rc(field: &Self::Rc) -> T;
+
+    /// Plain value currently held by the Ref field
+    fn snapshot_ref(field: &Self::Ref<'_>) -> T;
+
+    /// Write a plain value back into the Rc field, used by the generated `apply_patch`
+    fn set_rc(field: &Self::Rc, value: T);
+
+    /// Subscribe the current reactive scope to the Rc field, used by the generated `track`
+    /// and `on_change`
+    fn track_rc(field: &Self::Rc);
+
+    /// Subscribe the current reactive scope to the Ref field, used by the generated `track`
+    /// and `on_change`
+    fn track_ref(field: &Self::Ref<'_>);
+}
@@ -0,0 +1,59 @@
+/// Partial updates of a state, implemented by the `State` derive on the Rc types
+///
+/// The patch of `MyState` is the generated `MyStatePatch`, computed between two plain
+/// values with [`diff`](StatePatch::diff) and applied in place with
+/// [`apply_patch`](StatePatch::apply_patch)
+pub trait StatePatch {
+    /// Plain state the patches are computed from
+    type Value;
+    /// Partial update of the state
+    type Patch;
+
+    /// Apply the patch, only the signals it names are set
+    fn apply_patch(&self, patch: Self::Patch);
+
+    /// Patch turning `old` into `new`, [`None`] when the state can't be updated in place
+    /// as for an enum switching variants
+    fn diff(old: &Self::Value, new: &Self::Value) -> Option<Self::Patch>;
+}
+
+/// Patch of a `#[state]` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NestedPatch<T, P> {
+    /// Replace the nested state with a new value
+    Set(T),
+    /// Patch the nested state in place
+    Patch(P),
+}
+
+/// Operation of the patch of a `#[collection]` field
+///
+/// `P` is the patch type of the items, only used by collections of nested states,
+/// operations on an index past the end of the collection are ignored
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CollectionOp<T, P = ()> {
+    /// Push a new item at the end
+    Push(T),
+    /// Remove the item at the index
+    Remove(usize),
+    /// Replace the item at the index
+    Set(usize, T),
+    /// Patch the nested state at the index
+    Patch(usize, P),
+}
+
+/// Operation of the patch of a map field
+///
+/// `P` is the patch type of the values, only used by maps of nested states
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapOp<K, V, P = ()> {
+    /// Insert or replace the value of the entry
+    Insert(K, V),
+    /// Remove the entry
+    Remove(K),
+    /// Patch the nested state stored under the key
+    Patch(K, P),
+}
@@ -0,0 +1,34 @@
+/// Path type naming the fields of a state, reported by the generated `on_change`
+///
+/// Implemented by the `State` derive on the Rc and Ref types, the path of `MyState`
+/// is the generated `MyStateField` enum with a variant for each field
+pub trait StatePath {
+    /// Path to a changed field of the state
+    type Path: Clone + Eq;
+}
+
+/// Path to a change within a collection field
+///
+/// `P` is the path type of the items, only used by collections of nested states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollectionPath<P = ()> {
+    /// Items were added, removed, moved or the collection was replaced
+    Items,
+    /// The item at the index was set
+    Index(usize),
+    /// A field of the nested state at the index changed
+    Item(usize, P),
+}
+
+/// Path to a change within a map field
+///
+/// `P` is the path type of the values, only used by maps of nested states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapPath<K, P = ()> {
+    /// Entries were inserted, removed or the map was replaced
+    Entries,
+    /// The value of the entry was set
+    Key(K),
+    /// A field of the nested state stored under the key changed
+    Item(K, P),
+}
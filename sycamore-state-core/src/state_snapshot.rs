@@ -0,0 +1,12 @@
+/// Plain value of a derived state, implemented by the `State` derive
+///
+/// Only implemented when every value held by the state can be cloned,
+/// the generated `snapshot`, `to_ref` and `to_rc` methods and the `From`
+/// conversions to the original type are built on it
+pub trait StateSnapshot {
+    /// Type the state was derived from
+    type Value;
+
+    /// Plain copy of the current values, read untracked
+    fn snapshot(&self) -> Self::Value;
+}
@@ -0,0 +1,353 @@
+use crate::models::{CratePaths, Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::utils::{
+    collection_shape, format_rc_ident, format_ref_ident, is_collection, is_configured_collection,
+    is_custom, is_map, is_owner, is_set, rc_state_type, ref_state_type, unwrap_state_type,
+    StateWrapper,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::token::{Enum, Struct};
+use syn::{Ident, Type};
+
+/// Which of the derived types a snapshot reads from
+#[derive(Clone, Copy)]
+pub(crate) enum Repr {
+    Rc,
+    Ref,
+}
+
+/// Snapshot of a field with the bounds it needs
+///
+/// Bounds are higher ranked so that a state holding values which
+/// can't be cloned still derives, only without the snapshot
+pub(crate) struct FieldSnapshot {
+    pub value: TokenStream,
+    pub bounds: Vec<TokenStream>,
+}
+
+impl FieldWrapper {
+    /// Plain value currently held by the field at `access`, read untracked
+    ///
+    /// `owner` is the state being derived, a field holding the same state is
+    /// left out of the bounds which would otherwise never resolve
+    pub(crate) fn snapshot_value(
+        &self,
+        paths: &CratePaths,
+        owner: &Ident,
+        access: TokenStream,
+        repr: Repr,
+    ) -> FieldSnapshot {
+        let state = &paths.state;
+        let is_stateful = self.is_stateful();
+        let mut bounds = Vec::new();
+        // Plain value of an item `a` of type `ty`, given as the `Rc` returned by `get_untracked`
+        let mut item = |ty: &Type| {
+            if is_stateful {
+                let derived = match repr {
+                    Repr::Rc => rc_state_type(self, ty),
+                    Repr::Ref => ref_state_type(self, ty),
+                };
+                if !is_owner(ty, owner) {
+                    bounds.push(quote! { for<'s> #derived: #state::StateSnapshot<Value = #ty> });
+                }
+                quote! { #state::StateSnapshot::snapshot(&*a) }
+            } else {
+                bounds.push(quote! { for<'s> #ty: ::core::clone::Clone });
+                quote! { (*a).clone() }
+            }
+        };
+        let items = |collection: TokenStream, item: TokenStream| {
+            quote! {
+                #collection.get_untracked().iter().map(|a| { let a = a.get_untracked(); #item }).collect()
+            }
+        };
+        // Arrays can't be collected into, their fixed size signal is mapped instead
+        let level_items = |collection: TokenStream, item: TokenStream, array: bool| {
+            if array {
+                quote! {
+                    (*#collection.get_untracked()).each_ref().map(|a| { let a = a.get_untracked(); #item })
+                }
+            } else {
+                items(collection, item)
+            }
+        };
+
+        let value = if let Some(with) = is_custom(self) {
+            let ty = self.ref_ty();
+            let snapshot = match repr {
+                Repr::Rc => quote! { snapshot_rc },
+                Repr::Ref => quote! { snapshot_ref },
+            };
+            quote! { <#with as #state::StateField<#ty>>::#snapshot(&#access) }
+        } else if is_set(self).is_some() {
+            let ty = self.ref_ty();
+            bounds.push(quote! { for<'s> #ty: ::core::clone::Clone });
+            quote! { (*#access.get_untracked()).clone() }
+        } else if let Some(map) = is_map(self) {
+            let item = item(&map.value);
+            let key = &map.key;
+            bounds.push(quote! { for<'s> #key: ::core::clone::Clone });
+            quote! {
+                #access.get_untracked().iter().map(|(k, a)| (k.clone(), { let a = a.get_untracked(); #item })).collect()
+            }
+        } else if is_configured_collection(self).is_some() {
+            let element = is_collection(self).expect("Type must be a collection");
+            items(access, item(&element))
+        } else if let Some(shape) = collection_shape(self) {
+            // Each level collects the items `a` of the level above
+            let mut level = item(&shape.element);
+            for depth in (1..shape.depth).rev() {
+                level = level_items(quote! { a }, level, shape.arrays[depth].is_some());
+            }
+            if shape.optional {
+                let level = level_items(quote! { a }, level, shape.arrays[0].is_some());
+                quote! { ::core::option::Option::as_ref(&*#access.get_untracked()).map(|a| #level) }
+            } else {
+                level_items(access, level, shape.arrays[0].is_some())
+            }
+        } else if is_stateful {
+            let (wrappers, inner) = unwrap_state_type(self.ref_ty());
+            let value = wrappers
+                .iter()
+                .rev()
+                .fold(item(&inner), |value, wrapper| match wrapper {
+                    StateWrapper::Option => quote! { a.as_ref().map(|a| #value) },
+                    StateWrapper::Box => quote! { ::std::boxed::Box::new(#value) },
+                    StateWrapper::Rc => quote! { ::std::rc::Rc::new(#value) },
+                });
+            quote! { { let a = #access.get_untracked(); let a = &*a; #value } }
+        } else {
+            let ty = self.ref_ty();
+            bounds.push(quote! { for<'s> #ty: ::core::clone::Clone });
+            quote! { (*#access.get_untracked()).clone() }
+        };
+        FieldSnapshot { value, bounds }
+    }
+
+    /// Field holding a plain value in a signal, which a Ref type can borrow from a Rc type
+    fn is_shareable(&self) -> bool {
+        !self.is_stateful()
+            && !self.is_collection()
+            && is_custom(self).is_none()
+            && is_set(self).is_none()
+            && is_map(self).is_none()
+            && is_configured_collection(self).is_none()
+    }
+}
+
+impl<T> Deriver<T> {
+    fn derive_conversion_impls(
+        &self,
+        rc_snapshot: FieldSnapshot,
+        ref_snapshot: FieldSnapshot,
+        share: Option<TokenStream>,
+    ) -> TokenStream {
+        let source = self.source();
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let original_generics = &self.generics;
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let (ctor_generics, ctor_bounds) = self.ref_ctor_generics();
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let FieldSnapshot {
+            value: rc_value,
+            bounds: rc_bounds,
+        } = rc_snapshot;
+        let FieldSnapshot {
+            value: ref_value,
+            bounds: ref_bounds,
+        } = ref_snapshot;
+        let snapshot =
+            quote! { for<'s> Self: #state::StateSnapshot<Value = #source #original_generics> };
+
+        let share = share.map(|share| {
+            quote! {
+                /// Ref state borrowing the signals of this state, changes made
+                /// through either representation are seen by both
+                pub fn share<'stateful>(&'stateful self) -> #ref_ident #ref_generics {
+                    #share
+                }
+            }
+        });
+
+        quote! {
+            impl #rc_generics #state::StateSnapshot for #rc_ident #rc_generics
+            where
+                #(#rc_bounds,)*
+            {
+                type Value = #source #original_generics;
+
+                fn snapshot(&self) -> #source #original_generics {
+                    #rc_value
+                }
+            }
+
+            impl #ref_generics #state::StateSnapshot for #ref_ident #ref_generics
+            where
+                #(#ref_bounds,)*
+            {
+                type Value = #source #original_generics;
+
+                fn snapshot(&self) -> #source #original_generics {
+                    #ref_value
+                }
+            }
+
+            impl #rc_generics #rc_ident #rc_generics {
+                /// Plain copy of the current values, read untracked
+                pub fn snapshot(&self) -> #source #original_generics
+                where
+                    #snapshot
+                {
+                    #state::StateSnapshot::snapshot(self)
+                }
+
+                /// Ref state in `cx` holding a deep copy of the current values
+                pub fn to_ref<'ctor_ref>(&self, cx: #sycamore::prelude::Scope<'ctor_ref>) -> #ref_ident #ctor_generics
+                where
+                    #snapshot,
+                    #ctor_bounds
+                {
+                    #ref_ident::new(cx, self.snapshot())
+                }
+
+                /// Independent copy of this state, with fresh signals holding the current values
+                pub fn fork(&self) -> Self
+                where
+                    #snapshot
+                {
+                    Self::new(self.snapshot())
+                }
+
+                #share
+            }
+
+            impl #ref_generics #ref_ident #ref_generics {
+                /// Plain copy of the current values, read untracked
+                pub fn snapshot(&self) -> #source #original_generics
+                where
+                    #snapshot
+                {
+                    #state::StateSnapshot::snapshot(self)
+                }
+
+                /// Rc state holding a deep copy of the current values
+                pub fn to_rc(&self) -> #rc_ident #rc_generics
+                where
+                    #snapshot
+                {
+                    #rc_ident::new(self.snapshot())
+                }
+
+                /// Independent copy of this state in `cx`, with fresh signals holding the current values
+                pub fn fork<'ctor_ref>(&self, cx: #sycamore::prelude::Scope<'ctor_ref>) -> #ref_ident #ctor_generics
+                where
+                    #snapshot,
+                    #ctor_bounds
+                {
+                    #ref_ident::new(cx, self.snapshot())
+                }
+            }
+
+            impl #rc_generics ::core::convert::From<#source #original_generics> for #rc_ident #rc_generics {
+                fn from(data: #source #original_generics) -> Self {
+                    Self::new(data)
+                }
+            }
+
+            impl #rc_generics ::core::convert::From<&#rc_ident #rc_generics> for #source #original_generics
+            where
+                for<'s> #rc_ident #rc_generics: #state::StateSnapshot<Value = #source #original_generics>,
+            {
+                fn from(state: &#rc_ident #rc_generics) -> Self {
+                    #state::StateSnapshot::snapshot(state)
+                }
+            }
+
+            impl #ref_generics ::core::convert::From<&#ref_ident #ref_generics> for #source #original_generics
+            where
+                for<'s> #ref_ident #ref_generics: #state::StateSnapshot<Value = #source #original_generics>,
+            {
+                fn from(state: &#ref_ident #ref_generics) -> Self {
+                    #state::StateSnapshot::snapshot(state)
+                }
+            }
+
+            impl #ref_generics ::core::convert::From<&#ref_ident #ref_generics> for #rc_ident #rc_generics
+            where
+                for<'s> #ref_ident #ref_generics: #state::StateSnapshot<Value = #source #original_generics>,
+            {
+                fn from(state: &#ref_ident #ref_generics) -> Self {
+                    #rc_ident::new(#state::StateSnapshot::snapshot(state))
+                }
+            }
+        }
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_conversions(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let source = self.source();
+        let ref_ident = format_ref_ident(&self.ident);
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let snapshot = |repr: Repr| {
+            let mut bounds = Vec::new();
+            let values = self
+                .fields
+                .iter()
+                .map(|a| {
+                    let ident = a.ident();
+                    let snapshot =
+                        a.snapshot_value(&paths, &self.ident, quote! { self.#ident }, repr);
+                    bounds.extend(snapshot.bounds);
+                    snapshot.value
+                })
+                .collect::<Vec<_>>();
+            let value = quote! { #source { #(#idents: #values),* } };
+            FieldSnapshot { value, bounds }
+        };
+        let share = self.fields.iter().all(FieldWrapper::is_shareable).then(|| {
+            quote! { #ref_ident { #(#idents: &self.#idents),* } }
+        });
+        self.derive_conversion_impls(snapshot(Repr::Rc), snapshot(Repr::Ref), share)
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_conversions(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let source = self.source();
+        let ref_ident = format_ref_ident(&self.ident);
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let snapshot = |repr: Repr| {
+            let mut bounds = Vec::new();
+            let values = self
+                .fields
+                .iter()
+                .map(|a| {
+                    let snapshot = a.snapshot_value(&paths, &self.ident, quote! { (*data) }, repr);
+                    bounds.extend(snapshot.bounds);
+                    snapshot.value
+                })
+                .collect::<Vec<_>>();
+            let value = quote! {
+                match self {
+                    #(Self::#idents(data) => #source::#idents(#values)),*
+                }
+            };
+            FieldSnapshot { value, bounds }
+        };
+        let share = self.fields.iter().all(FieldWrapper::is_shareable).then(|| {
+            quote! {
+                match self {
+                    #(Self::#idents(data) => #ref_ident::#idents(data)),*
+                }
+            }
+        });
+        self.derive_conversion_impls(snapshot(Repr::Rc), snapshot(Repr::Ref), share)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::models::{Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::utils::{
+    collection_shape, extract_state_options, format_rc_ident, format_ref_ident,
+    is_configured_collection, is_custom, is_map, is_set, type_param_bounds,
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::token::{Enum, Struct};
+
+impl FieldWrapper {
+    /// Plain value of the field for the generated `Debug`, read untracked
+    ///
+    /// Collection types already format the values of their items, every
+    /// other field is a signal holding the value
+    fn debug_value(&self, access: TokenStream) -> TokenStream {
+        if extract_state_options(&self.attrs()).redact {
+            return quote! { &::core::format_args!("<redacted>") };
+        }
+        let is_signal = is_custom(self).is_none()
+            && is_set(self).is_none()
+            && is_map(self).is_none()
+            && is_configured_collection(self).is_none()
+            && collection_shape(self).is_none_or(|shape| shape.optional);
+        if is_signal {
+            quote! { &*#access.get_untracked() }
+        } else {
+            quote! { &#access }
+        }
+    }
+}
+
+impl<T> Deriver<T> {
+    /// Value based `Debug` impls of the Rc and Ref types when `#[state(debug)]` is set
+    fn derive_debug_impls(&self, body: TokenStream) -> TokenStream {
+        if !self.features.debug {
+            return quote! {};
+        }
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let bounds = type_param_bounds(&self.generics, quote! { ::core::fmt::Debug });
+        quote! {
+            impl #rc_generics ::core::fmt::Debug for #rc_ident #rc_generics
+            where
+                #bounds
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #body
+                }
+            }
+
+            impl #ref_generics ::core::fmt::Debug for #ref_ident #ref_generics
+            where
+                #bounds
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #body
+                }
+            }
+        }
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_debug(&self) -> TokenStream {
+        let name = self.ident.to_string();
+        let fields = self.fields.iter().map(|a| {
+            let ident = a.ident();
+            let name = ident.to_string();
+            let value = a.debug_value(quote! { self.#ident });
+            quote! { .field(#name, #value) }
+        });
+        self.derive_debug_impls(quote! {
+            f.debug_struct(#name) #(#fields)* .finish()
+        })
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_debug(&self) -> TokenStream {
+        let variants = self.fields.iter().map(|a| {
+            let ident = a.ident();
+            let name = ident.to_string();
+            let value = a.debug_value(quote! { data });
+            quote! {
+                Self::#ident(data) => f.debug_tuple(#name).field(#value).finish()
+            }
+        });
+        self.derive_debug_impls(quote! {
+            match self {
+                #(#variants),*
+            }
+        })
+    }
+}
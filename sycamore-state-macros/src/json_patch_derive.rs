@@ -0,0 +1,750 @@
+use crate::convert_derive::Repr;
+use crate::models::{CratePaths, Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::patch_derive::item_type;
+use crate::utils::{
+    collection_shape, format_rc_ident, format_ref_ident, is_collection, is_configured_collection,
+    is_custom, is_map, is_owner, is_set, rc_state_ctor, rc_state_type, ref_state_ctor,
+    ref_state_type, unwrap_state_type, wrap_state_ctor, CollectionShape, MapCollection,
+    StateWrapper,
+};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::token::{Enum, Struct};
+use syn::Type;
+
+/// How a field resolves the paths below it
+enum PointerKind {
+    /// Plain value in a signal, paths go into its JSON value
+    Value,
+    /// Nested state, paths go into the state
+    State {
+        wrappers: Vec<StateWrapper>,
+        inner: Box<Type>,
+    },
+    /// Collection or array, paths start with the index of an item
+    List(Box<CollectionShape>),
+    /// Map, paths start with a key
+    Map(Box<MapCollection>),
+    /// Any other field, read from its snapshot and written back whole
+    Whole,
+}
+
+/// Pointer resolution of a field, `get` and `update` resolve the path `rest`
+/// below the field, `update` applies the `PointerUpdate` named `update`
+struct FieldPointer {
+    get: TokenStream,
+    update: TokenStream,
+    bounds: Vec<TokenStream>,
+}
+
+impl FieldWrapper {
+    fn pointer_kind(&self) -> PointerKind {
+        if is_custom(self).is_some()
+            || is_set(self).is_some()
+            || is_configured_collection(self).is_some()
+        {
+            PointerKind::Whole
+        } else if let Some(map) = is_map(self) {
+            PointerKind::Map(Box::new(map))
+        } else if let Some(shape) = collection_shape(self) {
+            if shape.optional {
+                PointerKind::Whole
+            } else {
+                PointerKind::List(Box::new(shape))
+            }
+        } else if self.is_stateful() {
+            let (wrappers, inner) = unwrap_state_type(self.ref_ty());
+            PointerKind::State {
+                wrappers,
+                inner: Box::new(inner),
+            }
+        } else {
+            PointerKind::Value
+        }
+    }
+
+    /// Name of the field in the paths
+    fn pointer_name(&self) -> String {
+        self.ident().unraw().to_string()
+    }
+
+    /// Writes the plain value `a` of a field read and written whole, `None` when
+    /// the field can't be written as a custom mapping of a Ref state
+    fn whole_write(
+        &self,
+        paths: &CratePaths,
+        access: &TokenStream,
+        repr: Repr,
+    ) -> Option<TokenStream> {
+        let state = &paths.state;
+        let json = quote! { #state::json_patch };
+        let ty = self.ref_ty();
+        let is_stateful = self.is_stateful();
+        // Derived item built from a plain item `a`
+        let item = |ty: &Type| match ty {
+            Type::Path(inner) if is_stateful => match repr {
+                Repr::Rc => {
+                    let rc_ident = rc_state_ctor(self, inner);
+                    quote! { #rc_ident::new(a) }
+                }
+                Repr::Ref => {
+                    let ref_ident = ref_state_ctor(self, inner);
+                    quote! { #ref_ident::new(cx, a) }
+                }
+            },
+            _ => quote! { a },
+        };
+        if let Some(with) = is_custom(self) {
+            match repr {
+                Repr::Rc => {
+                    Some(quote! { <#with as #state::StateField<#ty>>::set_rc(&#access, a); })
+                }
+                Repr::Ref => None,
+            }
+        } else if is_set(self).is_some() {
+            Some(quote! { #access.clear(); #access.extend(a); })
+        } else if let Some(configured) = is_configured_collection(self) {
+            let element = is_collection(self).expect("Type must be a collection");
+            let item = item(&element);
+            if configured.signal == "RcSortedCollectionSignal" {
+                Some(quote! { #access.clear(); #access.extend(a.into_iter().map(|a| #item)); })
+            } else {
+                Some(quote! {
+                    while #access.pop_back().is_some() {}
+                    for a in a {
+                        #access.push_back(#item);
+                    }
+                })
+            }
+        } else {
+            let shape = collection_shape(self).expect("Type must be a collection");
+            let levels = match repr {
+                Repr::Rc => Self::rc_collection_levels(paths, self, &shape, 0, is_stateful),
+                Repr::Ref => Self::ref_collection_levels(paths, self, &shape, 0, is_stateful),
+            };
+            Some(quote! { #json::set_signal(&#access, a.map(|a| #levels)); })
+        }
+    }
+
+    /// Pointer resolution of the field at `access`
+    ///
+    /// `owner` is the state being derived, a field holding the same state is
+    /// left out of the bounds which would otherwise never resolve
+    fn field_pointer(
+        &self,
+        paths: &CratePaths,
+        owner: &Ident,
+        access: TokenStream,
+        repr: Repr,
+    ) -> FieldPointer {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let json = quote! { #state::json_patch };
+        let serde = quote! { #json::serde };
+        let error = quote! { #json::PointerError };
+        let update_op = quote! { #json::PointerUpdate };
+        let ty = self.ref_ty();
+        let is_stateful = self.is_stateful();
+        let mut bounds = Vec::new();
+        let serde_bound = |ty: &dyn quote::ToTokens| {
+            quote! { for<'s> #ty: #serde::Serialize + #serde::de::DeserializeOwned }
+        };
+        let scope = match repr {
+            Repr::Rc => quote! { () },
+            Repr::Ref => quote! { #sycamore::prelude::Scope<'stateful> },
+        };
+        // Derived type of a nested state and its constructor from a plain state `a`
+        let nested = |inner: &Type, bounds: &mut Vec<TokenStream>| {
+            let (derived, ctor) = match (repr, inner) {
+                (Repr::Rc, Type::Path(path)) => {
+                    let rc_ident = rc_state_ctor(self, path);
+                    (rc_state_type(self, inner), quote! { #rc_ident::new(a) })
+                }
+                (Repr::Ref, Type::Path(path)) => {
+                    let ref_ident = ref_state_ctor(self, path);
+                    (
+                        ref_state_type(self, inner),
+                        quote! { #ref_ident::new(cx, a) },
+                    )
+                }
+                (Repr::Rc, _) => (rc_state_type(self, inner), quote! { a }),
+                (Repr::Ref, _) => (ref_state_type(self, inner), quote! { a }),
+            };
+            if !is_owner(inner, owner) {
+                bounds.push(quote! { for<'s> #derived: #json::JsonPointer<Scope = #scope> });
+            }
+            (derived, ctor)
+        };
+        // Whole value of the field, from its snapshot
+        let whole = |bounds: &mut Vec<TokenStream>| {
+            let snapshot = self.snapshot_value(paths, owner, access.clone(), repr);
+            bounds.extend(snapshot.bounds);
+            bounds.push(serde_bound(ty));
+            let value = snapshot.value;
+            quote! { #json::to_json::<#ty>(&#value) }
+        };
+
+        let (get, update) = match self.pointer_kind() {
+            PointerKind::Value => {
+                bounds.push(serde_bound(ty));
+                (
+                    quote! { #json::get_signal(&#access, rest) },
+                    quote! { #json::update_signal(&#access, rest, update) },
+                )
+            }
+            PointerKind::State { wrappers, inner } => {
+                let (derived, ctor) = nested(&inner, &mut bounds);
+                bounds.push(quote! { for<'s> #ty: #serde::de::DeserializeOwned });
+                let ctor = wrap_state_ctor(&wrappers, ctor);
+                // Resolves the path in the nested state `a`, unwrapping the optional layers
+                let unwrap = |value: TokenStream, missing: TokenStream| {
+                    let value = wrappers
+                        .iter()
+                        .rev()
+                        .fold(value, |value, wrapper| match wrapper {
+                            StateWrapper::Option => quote! {
+                                match a.as_ref() {
+                                    ::core::option::Option::Some(a) => #value,
+                                    ::core::option::Option::None => #missing,
+                                }
+                            },
+                            StateWrapper::Box | StateWrapper::Rc => value,
+                        });
+                    quote! { { let a = #access.get_untracked(); let a = &*a; #value } }
+                };
+                let get = unwrap(
+                    quote! { <#derived as #json::JsonPointer>::get_pointer(a, rest) },
+                    quote! {
+                        if rest.is_empty() {
+                            ::core::result::Result::Ok(#json::serde_json::Value::Null)
+                        } else {
+                            ::core::result::Result::Err(#error::NotFound)
+                        }
+                    },
+                );
+                let nested_update = unwrap(
+                    quote! { <#derived as #json::JsonPointer>::update_pointer(a, cx, rest, update) },
+                    quote! { ::core::result::Result::Err(#error::NotFound) },
+                );
+                let update = quote! {
+                    match (rest.is_empty(), update) {
+                        (true, #update_op::Add(value) | #update_op::Replace(value)) => {
+                            let a: #ty = #json::from_json(value)?;
+                            #json::set_signal(&#access, #ctor);
+                            ::core::result::Result::Ok(())
+                        }
+                        (true, #update_op::Remove) => ::core::result::Result::Err(#error::Unsupported),
+                        (false, update) => #nested_update,
+                    }
+                };
+                (get, update)
+            }
+            PointerKind::List(shape) => {
+                let growable = shape.arrays[0].is_none();
+                let item_ty = if shape.depth > 1 {
+                    item_type(ty)
+                } else {
+                    shape.element.clone()
+                };
+                // Derived item built from a plain item `a`
+                let item_ctor = match repr {
+                    Repr::Rc => Self::rc_collection_levels(paths, self, &shape, 1, is_stateful),
+                    Repr::Ref => Self::ref_collection_levels(paths, self, &shape, 1, is_stateful),
+                };
+                let whole = whole(&mut bounds);
+                bounds.push(quote! { for<'s> #item_ty: #serde::de::DeserializeOwned });
+                // Value below the item handle `item` and update of it, nested
+                // collections are read whole and their items written back whole
+                let (item_get, item_update) = if shape.depth > 1 {
+                    (
+                        None,
+                        quote! {
+                            #json::update_plain(
+                                || #json::get_value(#whole?, ::core::slice::from_ref(segment)),
+                                rest,
+                                update,
+                                |a: #item_ty| #json::set_signal(item, #item_ctor),
+                            )
+                        },
+                    )
+                } else if is_stateful {
+                    let (derived, _) = nested(&shape.element, &mut bounds);
+                    (
+                        Some(quote! {
+                            <#derived as #json::JsonPointer>::get_pointer(&*item.get_untracked(), rest)
+                        }),
+                        quote! {
+                            <#derived as #json::JsonPointer>::update_pointer(&*item.get_untracked(), cx, rest, update)
+                        },
+                    )
+                } else {
+                    bounds.push(serde_bound(&item_ty));
+                    (
+                        Some(quote! { #json::get_signal(item, rest) }),
+                        quote! { #json::update_signal(item, rest, update) },
+                    )
+                };
+                let get = match item_get {
+                    Some(item_get) => quote! {
+                        {
+                            let items = #access.get_untracked();
+                            match rest.split_first() {
+                                ::core::option::Option::None => {
+                                    let rest: &[::std::string::String] = &[];
+                                    items
+                                        .iter()
+                                        .map(|item| #item_get)
+                                        .collect::<::core::result::Result<::std::vec::Vec<_>, _>>()
+                                        .map(#json::serde_json::Value::Array)
+                                }
+                                ::core::option::Option::Some((segment, rest)) => {
+                                    let item = &items[#json::index(segment, items.len(), false)?];
+                                    #item_get
+                                }
+                            }
+                        }
+                    },
+                    None => quote! { #json::get_value(#whole?, rest) },
+                };
+                let (push, insert) = match repr {
+                    Repr::Rc => (
+                        quote! { #access.push(#item_ctor) },
+                        quote! { #access.insert(index, #item_ctor) },
+                    ),
+                    Repr::Ref => (
+                        quote! { #access.push_value(cx, #item_ctor) },
+                        quote! { #access.insert_value(cx, index, #item_ctor) },
+                    ),
+                };
+                // Arrays keep their length, their items are only replaced
+                let (replace_all, add, remove) = if growable {
+                    (
+                        quote! {
+                            #access.clear();
+                            for a in values {
+                                #push;
+                            }
+                        },
+                        quote! {
+                            let index = #json::index(segment, #access.get_untracked().len(), true)?;
+                            let a: #item_ty = #json::from_json(value)?;
+                            #insert;
+                            ::core::result::Result::Ok(())
+                        },
+                        quote! {
+                            let index = #json::index(segment, #access.get_untracked().len(), false)?;
+                            #access.remove(index);
+                            ::core::result::Result::Ok(())
+                        },
+                    )
+                } else {
+                    (
+                        quote! {
+                            let items = #access.get_untracked();
+                            if values.len() != items.len() {
+                                return ::core::result::Result::Err(#error::InvalidValue(
+                                    ::std::format!("expected {} items", items.len()),
+                                ));
+                            }
+                            for (item, a) in items.iter().zip(values) {
+                                #json::set_signal(item, #item_ctor);
+                            }
+                        },
+                        quote! { ::core::result::Result::Err(#error::Unsupported) },
+                        quote! { ::core::result::Result::Err(#error::Unsupported) },
+                    )
+                };
+                let update = quote! {
+                    match rest.split_first() {
+                        ::core::option::Option::None => match update {
+                            #update_op::Add(value) | #update_op::Replace(value) => {
+                                let values: ::std::vec::Vec<#item_ty> = #json::from_json(value)?;
+                                #replace_all
+                                ::core::result::Result::Ok(())
+                            }
+                            #update_op::Remove => ::core::result::Result::Err(#error::Unsupported),
+                        },
+                        ::core::option::Option::Some((segment, rest)) if rest.is_empty() => match update {
+                            #update_op::Add(value) => {
+                                #add
+                            }
+                            #update_op::Replace(value) => {
+                                let items = #access.get_untracked();
+                                let item = &items[#json::index(segment, items.len(), false)?];
+                                let a: #item_ty = #json::from_json(value)?;
+                                #json::set_signal(item, #item_ctor);
+                                ::core::result::Result::Ok(())
+                            }
+                            #update_op::Remove => {
+                                #remove
+                            }
+                        },
+                        ::core::option::Option::Some((segment, rest)) => {
+                            let items = #access.get_untracked();
+                            let item = &items[#json::index(segment, items.len(), false)?];
+                            #item_update
+                        }
+                    }
+                };
+                (get, update)
+            }
+            PointerKind::Map(map) => {
+                let key = &map.key;
+                let value = &map.value;
+                bounds.push(serde_bound(key));
+                bounds.push(quote! { for<'s> #ty: #serde::de::DeserializeOwned });
+                let (entry_get, entry_update, value_ctor) = if is_stateful {
+                    let (derived, ctor) = nested(value, &mut bounds);
+                    bounds.push(quote! { for<'s> #value: #serde::de::DeserializeOwned });
+                    (
+                        quote! {
+                            <#derived as #json::JsonPointer>::get_pointer(&*entry.get_untracked(), rest)
+                        },
+                        quote! {
+                            <#derived as #json::JsonPointer>::update_pointer(&*entry.get_untracked(), cx, rest, update)
+                        },
+                        ctor,
+                    )
+                } else {
+                    bounds.push(serde_bound(value));
+                    (
+                        quote! { #json::get_signal(&entry, rest) },
+                        quote! { #json::update_signal(&entry, rest, update) },
+                        quote! { a },
+                    )
+                };
+                let get = quote! {
+                    match rest.split_first() {
+                        ::core::option::Option::None => {
+                            let rest: &[::std::string::String] = &[];
+                            let mut map = #json::serde_json::Map::new();
+                            for (key, entry) in #access.get_untracked().iter() {
+                                map.insert(#json::key_to_segment(key)?, #entry_get?);
+                            }
+                            ::core::result::Result::Ok(#json::serde_json::Value::Object(map))
+                        }
+                        ::core::option::Option::Some((segment, rest)) => {
+                            let key: #key = #json::key_from_segment(segment)?;
+                            let entry = #access.get_item(&key).ok_or(#error::NotFound)?;
+                            #entry_get
+                        }
+                    }
+                };
+                let update = quote! {
+                    match rest.split_first() {
+                        ::core::option::Option::None => match update {
+                            #update_op::Add(value) | #update_op::Replace(value) => {
+                                let entries: #ty = #json::from_json(value)?;
+                                #access.clear();
+                                #access.extend(entries.into_iter().map(|(key, a)| (key, #value_ctor)));
+                                ::core::result::Result::Ok(())
+                            }
+                            #update_op::Remove => ::core::result::Result::Err(#error::Unsupported),
+                        },
+                        ::core::option::Option::Some((segment, rest)) => {
+                            let key: #key = #json::key_from_segment(segment)?;
+                            match (#access.get_item(&key), rest.is_empty(), update) {
+                                (
+                                    ::core::option::Option::Some(entry),
+                                    true,
+                                    #update_op::Add(value) | #update_op::Replace(value),
+                                ) => {
+                                    let a: #value = #json::from_json(value)?;
+                                    #json::set_signal(&entry, #value_ctor);
+                                    ::core::result::Result::Ok(())
+                                }
+                                (::core::option::Option::None, true, #update_op::Add(value)) => {
+                                    let a: #value = #json::from_json(value)?;
+                                    #access.insert(key, #value_ctor);
+                                    ::core::result::Result::Ok(())
+                                }
+                                (::core::option::Option::Some(_), true, #update_op::Remove) => {
+                                    #access.remove(&key);
+                                    ::core::result::Result::Ok(())
+                                }
+                                (::core::option::Option::Some(entry), false, update) => #entry_update,
+                                _ => ::core::result::Result::Err(#error::NotFound),
+                            }
+                        }
+                    }
+                };
+                (get, update)
+            }
+            PointerKind::Whole => {
+                let whole = whole(&mut bounds);
+                let update = match self.whole_write(paths, &access, repr) {
+                    Some(write) => quote! {
+                        #json::update_plain(|| #whole, rest, update, |a: #ty| { #write })
+                    },
+                    None => quote! { ::core::result::Result::Err(#error::Unsupported) },
+                };
+                (quote! { #json::get_value(#whole?, rest) }, update)
+            }
+        };
+        FieldPointer {
+            get,
+            update,
+            bounds,
+        }
+    }
+}
+
+impl<T> Deriver<T> {
+    /// `JsonPointer` impls of the Rc and Ref types and the inherent methods using them
+    fn derive_json_patch_impls(
+        &self,
+        rc: (TokenStream, TokenStream, Vec<TokenStream>),
+        r#ref: (TokenStream, TokenStream, Vec<TokenStream>),
+    ) -> TokenStream {
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let source = self.source();
+        let original_generics = &self.generics;
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let json = quote! { #state::json_patch };
+        let (rc_get, rc_update, rc_bounds) = rc;
+        let (ref_get, ref_update, ref_bounds) = r#ref;
+        let result = |ty: TokenStream| quote! { ::core::result::Result<#ty, #json::PointerError> };
+        let value = result(quote! { #json::serde_json::Value });
+        let unit = result(quote! { () });
+        let patch_result = quote! { ::core::result::Result<(), #state::JsonPatchError> };
+        let diff_result = quote! {
+            ::core::result::Result<::std::vec::Vec<#state::JsonPatchOp>, #state::JsonPatchError>
+        };
+        let serialize = quote! { for<'s> #source #original_generics: #json::serde::Serialize };
+        let scope = quote! { #sycamore::prelude::Scope<'stateful> };
+
+        quote! {
+            impl #rc_generics #state::JsonPointer for #rc_ident #rc_generics
+            where
+                #(#rc_bounds,)*
+            {
+                type Scope = ();
+
+                fn get_pointer(&self, path: &[::std::string::String]) -> #value {
+                    #rc_get
+                }
+
+                fn update_pointer(
+                    &self,
+                    cx: (),
+                    path: &[::std::string::String],
+                    update: #json::PointerUpdate,
+                ) -> #unit {
+                    let _ = cx;
+                    #rc_update
+                }
+            }
+
+            impl #ref_generics #state::JsonPointer for #ref_ident #ref_generics
+            where
+                #(#ref_bounds,)*
+            {
+                type Scope = #scope;
+
+                fn get_pointer(&self, path: &[::std::string::String]) -> #value {
+                    #ref_get
+                }
+
+                fn update_pointer(
+                    &self,
+                    cx: #scope,
+                    path: &[::std::string::String],
+                    update: #json::PointerUpdate,
+                ) -> #unit {
+                    let _ = cx;
+                    #ref_update
+                }
+            }
+
+            impl #rc_generics #rc_ident #rc_generics {
+                /// Apply the RFC 6902 JSON Patch `patch` in a single batch, see [`json_patch::apply`](#json::apply)
+                pub fn apply_json_patch(&self, patch: &[#state::JsonPatchOp]) -> #patch_result
+                where
+                    for<'s> Self: #state::JsonPointer<Scope = ()>,
+                {
+                    #json::apply(self, (), patch)
+                }
+
+                /// JSON Patch turning the current values into `new`
+                pub fn json_diff(&self, new: &#source #original_generics) -> #diff_result
+                where
+                    for<'s> Self: #state::JsonPointer,
+                    #serialize
+                {
+                    #json::diff_state(self, new)
+                }
+            }
+
+            impl #ref_generics #ref_ident #ref_generics {
+                /// Apply the RFC 6902 JSON Patch `patch` in a single batch, new items
+                /// are created in `cx`, see [`json_patch::apply`](#json::apply)
+                pub fn apply_json_patch(&self, cx: #scope, patch: &[#state::JsonPatchOp]) -> #patch_result
+                where
+                    for<'s> Self: #state::JsonPointer<Scope = #scope>,
+                {
+                    #json::apply(self, cx, patch)
+                }
+
+                /// JSON Patch turning the current values into `new`
+                pub fn json_diff(&self, new: &#source #original_generics) -> #diff_result
+                where
+                    for<'s> Self: #state::JsonPointer,
+                    #serialize
+                {
+                    #json::diff_state(self, new)
+                }
+            }
+        }
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_json_patch(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let json = {
+            let state = &paths.state;
+            quote! { #state::json_patch }
+        };
+        let error = quote! { #json::PointerError };
+        let update_op = quote! { #json::PointerUpdate };
+        let names = self
+            .fields
+            .iter()
+            .map(FieldWrapper::pointer_name)
+            .collect::<Vec<_>>();
+        let resolve = |repr: Repr| {
+            let fields = self
+                .fields
+                .iter()
+                .map(|a| {
+                    let ident = a.ident();
+                    a.field_pointer(&paths, &self.ident, quote! { self.#ident }, repr)
+                })
+                .collect::<Vec<_>>();
+            let gets = fields.iter().map(|a| &a.get).collect::<Vec<_>>();
+            let updates = fields.iter().map(|a| &a.update).collect::<Vec<_>>();
+            let bounds = fields.iter().flat_map(|a| a.bounds.clone()).collect();
+            let get = quote! {
+                match path.split_first() {
+                    ::core::option::Option::None => {
+                        let rest: &[::std::string::String] = &[];
+                        let mut map = #json::serde_json::Map::new();
+                        #(map.insert(#names.into(), #gets?);)*
+                        ::core::result::Result::Ok(#json::serde_json::Value::Object(map))
+                    }
+                    ::core::option::Option::Some((segment, rest)) => match segment.as_str() {
+                        #(#names => #gets,)*
+                        _ => ::core::result::Result::Err(#error::NotFound),
+                    },
+                }
+            };
+            let update = quote! {
+                match path.split_first() {
+                    ::core::option::Option::None => match update {
+                        #update_op::Add(value) | #update_op::Replace(value) => {
+                            let #json::serde_json::Value::Object(mut map) = value else {
+                                return ::core::result::Result::Err(#error::InvalidValue(
+                                    "expected an object".into(),
+                                ));
+                            };
+                            #({
+                                let rest: &[::std::string::String] = &[];
+                                let value = map.remove(#names).ok_or_else(|| {
+                                    #error::InvalidValue(::std::format!("missing field `{}`", #names))
+                                })?;
+                                let update = #update_op::Replace(value);
+                                #updates?;
+                            })*
+                            ::core::result::Result::Ok(())
+                        }
+                        #update_op::Remove => ::core::result::Result::Err(#error::Unsupported),
+                    },
+                    ::core::option::Option::Some((segment, rest)) => match segment.as_str() {
+                        #(#names => #updates,)*
+                        _ => ::core::result::Result::Err(#error::NotFound),
+                    },
+                }
+            };
+            (get, update, bounds)
+        };
+        self.derive_json_patch_impls(resolve(Repr::Rc), resolve(Repr::Ref))
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_json_patch(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let json = {
+            let state = &paths.state;
+            quote! { #state::json_patch }
+        };
+        let error = quote! { #json::PointerError };
+        let update_op = quote! { #json::PointerUpdate };
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let names = self
+            .fields
+            .iter()
+            .map(FieldWrapper::pointer_name)
+            .collect::<Vec<_>>();
+        let resolve = |repr: Repr| {
+            let fields = self
+                .fields
+                .iter()
+                .map(|a| a.field_pointer(&paths, &self.ident, quote! { (*data) }, repr))
+                .collect::<Vec<_>>();
+            let gets = fields.iter().map(|a| &a.get).collect::<Vec<_>>();
+            let updates = fields.iter().map(|a| &a.update).collect::<Vec<_>>();
+            let bounds = fields.iter().flat_map(|a| a.bounds.clone()).collect();
+            // Paths start with the name of the variant, as serde writes enums
+            let get = quote! {
+                match self {
+                    #(Self::#idents(data) => match path.split_first() {
+                        ::core::option::Option::None => {
+                            let rest: &[::std::string::String] = &[];
+                            let mut map = #json::serde_json::Map::new();
+                            map.insert(#names.into(), #gets?);
+                            ::core::result::Result::Ok(#json::serde_json::Value::Object(map))
+                        }
+                        ::core::option::Option::Some((segment, rest)) if segment == #names => #gets,
+                        ::core::option::Option::Some(_) => ::core::result::Result::Err(#error::NotFound),
+                    },)*
+                }
+            };
+            // Switching variants replaces the state, which is done by the field holding it
+            let update = quote! {
+                match self {
+                    #(Self::#idents(data) => match path.split_first() {
+                        ::core::option::Option::None => match update {
+                            #update_op::Add(value) | #update_op::Replace(value) => {
+                                let #json::serde_json::Value::Object(map) = value else {
+                                    return ::core::result::Result::Err(#error::InvalidValue(
+                                        "expected an object naming the variant".into(),
+                                    ));
+                                };
+                                match ::core::iter::IntoIterator::into_iter(map).next() {
+                                    ::core::option::Option::Some((variant, value)) if variant == #names => {
+                                        let rest: &[::std::string::String] = &[];
+                                        let update = #update_op::Replace(value);
+                                        #updates
+                                    }
+                                    _ => ::core::result::Result::Err(#error::Unsupported),
+                                }
+                            }
+                            #update_op::Remove => ::core::result::Result::Err(#error::Unsupported),
+                        },
+                        ::core::option::Option::Some((segment, rest)) if segment == #names => #updates,
+                        ::core::option::Option::Some(_) => ::core::result::Result::Err(#error::NotFound),
+                    },)*
+                }
+            };
+            (get, update, bounds)
+        };
+        self.derive_json_patch_impls(resolve(Repr::Rc), resolve(Repr::Ref))
+    }
+}
@@ -1,6 +1,13 @@
+mod convert_derive;
+mod debug_derive;
+#[cfg(feature = "json-patch")]
+mod json_patch_derive;
 mod models;
+mod patch_derive;
+mod path_derive;
 mod rc_derive;
 mod ref_derive;
+mod track_derive;
 mod utils;
 
 use models::{Deriver, FeatureModel};
@@ -9,7 +16,7 @@ use quote::{format_ident, quote};
 use syn::DeriveInput;
 use utils::extract_features_from_attrs;
 
-#[proc_macro_derive(State, attributes(state, collection, derived))]
+#[proc_macro_derive(State, attributes(state, collection, set, derived))]
 pub fn entry_point(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     derive_stateful(input.into()).into()
 }
@@ -40,11 +47,30 @@ fn derive_enum(
     let derived_ref_decl = deriver.derive_ref_decl();
     let derived_rc_ctor = deriver.derive_rc_ctor();
     let derived_ref_ctor = deriver.derive_ref_ctor();
+    let derived_rc_default = deriver.derive_rc_default();
+    let derived_ref_default = deriver.derive_ref_default();
+    let derived_debug = deriver.derive_debug();
+    let derived_conversions = deriver.derive_conversions();
+    let derived_tracking = deriver.derive_tracking();
+    let derived_paths = deriver.derive_paths();
+    let derived_patches = deriver.derive_patches();
+    #[cfg(feature = "json-patch")]
+    let derived_json_patch = deriver.derive_json_patch();
+    #[cfg(not(feature = "json-patch"))]
+    let derived_json_patch = quote! {};
     quote! {
         #derived_rc_decl
         #derived_ref_decl
         #derived_rc_ctor
         #derived_ref_ctor
+        #derived_rc_default
+        #derived_ref_default
+        #derived_debug
+        #derived_conversions
+        #derived_tracking
+        #derived_paths
+        #derived_patches
+        #derived_json_patch
     }
 }
 
@@ -69,12 +95,33 @@ fn derive_struct(
     let derived_rc_ctor = deriver.derive_rc_ctor();
     let derived_ref_decl = deriver.derive_ref_decl();
     let derived_ref_ctor = deriver.derive_ref_ctor();
+    let derived_rc_default = deriver.derive_rc_default();
+    let derived_ref_default = deriver.derive_ref_default();
+    let derived_builders = deriver.derive_builders();
+    let derived_debug = deriver.derive_debug();
+    let derived_conversions = deriver.derive_conversions();
+    let derived_tracking = deriver.derive_tracking();
+    let derived_paths = deriver.derive_paths();
+    let derived_patches = deriver.derive_patches();
+    #[cfg(feature = "json-patch")]
+    let derived_json_patch = deriver.derive_json_patch();
+    #[cfg(not(feature = "json-patch"))]
+    let derived_json_patch = quote! {};
 
     quote! {
         #derived_ref_decl
         #derived_ref_ctor
         #derived_rc_decl
         #derived_rc_ctor
+        #derived_rc_default
+        #derived_ref_default
+        #derived_builders
+        #derived_debug
+        #derived_conversions
+        #derived_tracking
+        #derived_paths
+        #derived_patches
+        #derived_json_patch
     }
     .into()
 }
@@ -1,19 +1,20 @@
 use std::marker::PhantomData;
 
 use crate::utils::{
-    collection_ident, extract_lifetimes, format_rc_ident, format_ref_ident,
+    collection_ident, extract_lifetimes, extract_state_options, format_rc_ident, format_ref_ident,
     make_ref_derive_features, named_lifetime, s_lifetime, stateful_ident,
 };
-use crate::utils::{derived_ident, make_derive_features};
+use crate::utils::{derived_ident, is_set, make_derive_features};
 use proc_macro2::*;
 use quote::ToTokens;
 use quote::{format_ident, quote};
+use syn::ext::IdentExt;
 use syn::token::{Enum, Struct};
 use syn::{
     punctuated::Punctuated, token::Comma, Attribute, Field, GenericParam, Generics, LifetimeParam,
     Variant,
 };
-use syn::{AngleBracketedGenericArguments, Meta, Type};
+use syn::{AngleBracketedGenericArguments, Meta, Path, Type};
 use syn::{Expr, GenericArgument};
 
 pub(crate) trait GenericContainer {
@@ -45,6 +46,62 @@ pub struct FeatureModel {
     pub clone: bool,
     pub eq: bool,
     pub ord: bool,
+    /// Foreign type given by `#[state(remote = ..)]`, constructors take it instead of the derived type
+    pub remote: Option<Path>,
+    /// Path of the state manager crate given by `#[state(crate = "..")]`
+    pub krate: Option<Path>,
+    /// Path of the sycamore crate given by `#[state(sycamore = "..")]`
+    pub sycamore: Option<Path>,
+    /// Extra derives of the Rc type from `#[state(derive(..), rc_derive(..))]`
+    pub rc_derives: Vec<Path>,
+    /// Extra derives of the Ref type from `#[state(derive(..), ref_derive(..))]`
+    pub ref_derives: Vec<Path>,
+}
+
+impl FeatureModel {
+    /// Paths the generated code resolves the library types through
+    pub(crate) fn crate_paths(&self) -> CratePaths {
+        let state = match &self.krate {
+            Some(krate) => quote! { #krate },
+            None => quote! { ::sycamore_state_manager },
+        };
+        let sycamore = match &self.sycamore {
+            Some(sycamore) => quote! { #sycamore },
+            None => quote! { ::sycamore },
+        };
+        CratePaths { state, sycamore }
+    }
+
+    /// The Rc type derives `Clone`, from `#[state(clone)]` or a derive list
+    pub(crate) fn rc_clone(&self) -> bool {
+        self.clone || self.rc_derives.iter().any(|a| a.is_ident("Clone"))
+    }
+}
+
+/// Crate paths used by the generated code
+pub(crate) struct CratePaths {
+    pub state: TokenStream,
+    pub sycamore: TokenStream,
+}
+
+/// Options of a `#[collection(..)]` field attribute
+#[derive(Default, Clone)]
+pub struct CollectionOptions {
+    pub capacity: Option<Expr>,
+    pub sorted_by: Option<Expr>,
+    pub depth: Option<usize>,
+}
+
+/// Options of a `#[state(..)]` field attribute
+#[derive(Default, Clone)]
+pub struct StateOptions {
+    pub rc: Option<Path>,
+    pub r#ref: Option<Path>,
+    pub with: Option<Path>,
+    /// Value used by the generated builders when the field is not set
+    pub default: Option<Expr>,
+    /// Hidden in the generated `Debug` with `#[state(debug(redact))]`
+    pub redact: bool,
 }
 
 #[derive(Clone)]
@@ -70,6 +127,95 @@ pub struct Deriver<T> {
     _marker: PhantomData<T>,
 }
 
+impl<T> Deriver<T> {
+    /// Type the generated constructors take their data from
+    pub(crate) fn source(&self) -> TokenStream {
+        match &self.features.remote {
+            Some(remote) => quote! { #remote },
+            None => {
+                let ident = &self.ident;
+                quote! { #ident }
+            }
+        }
+    }
+}
+
+impl<T> Deriver<T> {
+    /// `Default` of the Rc type, built from the `Default` of the original type
+    pub fn derive_rc_default(&self) -> TokenStream {
+        if !self
+            .features
+            .rc_derives
+            .iter()
+            .any(|a| a.is_ident("Default"))
+        {
+            return quote! {};
+        }
+        let source = self.source();
+        let new_ident = format_rc_ident(&self.ident);
+        let generics = self.make_rc_generic();
+        quote! {
+            impl #generics Default for #new_ident #generics
+            where
+                #source #generics: Default,
+            {
+                fn default() -> Self {
+                    Self::new(Default::default())
+                }
+            }
+        }
+    }
+
+    /// `default_in` constructor of the Ref type, built from the `Default` of the original type
+    pub fn derive_ref_default(&self) -> TokenStream {
+        if !self
+            .features
+            .ref_derives
+            .iter()
+            .any(|a| a.is_ident("Default"))
+        {
+            return quote! {};
+        }
+        let source = self.source();
+        let new_ident = format_ref_ident(&self.ident);
+        let generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let sycamore = &paths.sycamore;
+        let original_generics = &self.generics;
+        let (ctor_generics, bounds) = self.ref_ctor_generics();
+        quote! {
+            impl #generics #new_ident #generics {
+                pub fn default_in<'ctor_ref>(cx: #sycamore::prelude::Scope<'ctor_ref>) -> #new_ident #ctor_generics
+                where
+                    #source #original_generics: Default,
+                    'ctor_ref: 'stateful,
+                    #bounds
+                {
+                    #new_ident::new(cx, Default::default())
+                }
+            }
+        }
+    }
+
+    /// Generics of the Ref type built by a constructor taking a `Scope<'ctor_ref>`,
+    /// with the bounds tying the lifetimes of the original type to the scope
+    pub(crate) fn ref_ctor_generics(&self) -> (Generics, Punctuated<Expr, Comma>) {
+        let mut added_generics = Punctuated::new();
+        added_generics.push(GenericParam::Lifetime(LifetimeParam::new(named_lifetime(
+            "ctor_ref",
+        ))));
+        let ctor_generics = self.make_generic(added_generics);
+        let bounds = extract_lifetimes(&self.generics)
+            .into_iter()
+            .map(|a| {
+                let ident = a.lifetime;
+                Expr::Verbatim(quote! {#ident: 'ctor_ref})
+            })
+            .collect::<Punctuated<_, Comma>>();
+        (ctor_generics, bounds)
+    }
+}
+
 impl Deriver<Enum> {
     pub fn new_enum(
         ident: Ident,
@@ -93,9 +239,10 @@ impl Deriver<Enum> {
     pub fn derive_rc_decl(&self) -> TokenStream {
         let new_ident = format_rc_ident(&self.ident);
         let generics = self.make_rc_generic();
+        let paths = self.features.crate_paths();
         let derives = make_derive_features(&self.features);
         let mut fields = Punctuated::<_, Comma>::new();
-        let mapped = self.fields.iter().map(|a| a.to_rc_decl());
+        let mapped = self.fields.iter().map(|a| a.to_rc_decl(&paths));
         fields.extend(mapped);
         quote! {
             #derives
@@ -108,9 +255,10 @@ impl Deriver<Enum> {
     pub fn derive_ref_decl(&self) -> TokenStream {
         let new_ident = format_ref_ident(&self.ident);
         let generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
         let derives = make_ref_derive_features(&self.features);
         let mut fields = Punctuated::<_, Comma>::new();
-        let mapped = self.fields.iter().map(|a| a.to_ref_decl());
+        let mapped = self.fields.iter().map(|a| a.to_ref_decl(&paths));
         fields.extend(mapped);
         quote! {
             #derives
@@ -122,14 +270,17 @@ impl Deriver<Enum> {
 
     pub fn derive_rc_ctor(&self) -> TokenStream {
         let ident = &self.ident;
+        let source = self.source();
         let new_ident = format_rc_ident(&ident);
         let generics = self.make_rc_generic();
+        let paths = self.features.crate_paths();
+        let watched = self.generics.params.is_empty();
         let mut fields = Punctuated::<_, Comma>::new();
         let mapped = self.fields.iter().map(|a| {
             let variant_ident = a.ident();
-            let arg = a.to_rc_ctor();
+            let arg = a.to_rc_ctor(&paths, watched);
             quote! {
-                #ident::#variant_ident(data) => Self::#variant_ident (#arg)
+                #source::#variant_ident(data) => Self::#variant_ident (#arg)
             }
         });
 
@@ -137,7 +288,7 @@ impl Deriver<Enum> {
 
         quote! {
             impl #generics #new_ident #generics {
-                pub fn new (data: #ident #generics) -> #new_ident # generics {
+                pub fn new (data: #source #generics) -> #new_ident # generics {
                     match data {
                         #fields
                     }
@@ -148,9 +299,12 @@ impl Deriver<Enum> {
 
     pub fn derive_ref_ctor(&self) -> TokenStream {
         let ident = &self.ident;
+        let source = self.source();
         let new_ident = format_ref_ident(&ident);
 
         let generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let sycamore = &paths.sycamore;
         let mut added_generics = Punctuated::new();
 
         added_generics.push(GenericParam::Lifetime(LifetimeParam::new(named_lifetime(
@@ -171,9 +325,9 @@ impl Deriver<Enum> {
         let mut fields = Punctuated::<_, Comma>::new();
         let mapped = self.fields.iter().map(|a| {
             let variant_ident = a.ident();
-            let arg = a.to_ref_ctor();
+            let arg = a.to_ref_ctor(&paths);
             quote! {
-                #ident::#variant_ident(data) => Self::#variant_ident (#arg)
+                #source::#variant_ident(data) => Self::#variant_ident (#arg)
             }
         });
 
@@ -181,7 +335,7 @@ impl Deriver<Enum> {
 
         quote! {
             impl #generics #new_ident #generics {
-                pub fn new<'ctor_ref>(cx: ::sycamore::prelude::Scope<'ctor_ref>, data: #ident #original_generics ) -> #new_ident #ctor_generics
+                pub fn new<'ctor_ref>(cx: #sycamore::prelude::Scope<'ctor_ref>, data: #source #original_generics ) -> #new_ident #ctor_generics
                 where
                     #bounds,
                     'ctor_ref: 'stateful,
@@ -218,9 +372,10 @@ impl Deriver<Struct> {
     pub fn derive_rc_decl(&self) -> TokenStream {
         let new_ident = format_rc_ident(&self.ident);
         let generics = self.make_rc_generic();
+        let paths = self.features.crate_paths();
         let derives = make_derive_features(&self.features);
         let mut fields = Punctuated::<_, Comma>::new();
-        let mapped = self.fields.iter().map(|a| a.to_rc_decl());
+        let mapped = self.fields.iter().map(|a| a.to_rc_decl(&paths));
         fields.extend(mapped);
         quote! {
             #derives
@@ -232,12 +387,15 @@ impl Deriver<Struct> {
 
     pub fn derive_rc_ctor(&self) -> TokenStream {
         let ident = &self.ident;
+        let source = self.source();
         let new_ident = format_rc_ident(&ident);
         let generics = self.make_rc_generic();
+        let paths = self.features.crate_paths();
+        let watched = self.generics.params.is_empty();
         let mut fields = Punctuated::<_, Comma>::new();
         let mapped = self.fields.iter().map(|a| {
             let ident = a.ident();
-            let arg = a.to_rc_ctor();
+            let arg = a.to_rc_ctor(&paths, watched);
             quote! {
                 #ident: #arg
             }
@@ -247,7 +405,7 @@ impl Deriver<Struct> {
 
         quote! {
             impl #generics #new_ident #generics {
-                pub fn new (data: #ident #generics) -> #new_ident # generics {
+                pub fn new (data: #source #generics) -> #new_ident # generics {
                     #new_ident {
                         #fields
                     }
@@ -259,9 +417,10 @@ impl Deriver<Struct> {
     pub fn derive_ref_decl(&self) -> TokenStream {
         let new_ident = format_ref_ident(&self.ident);
         let generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
         let derives = make_ref_derive_features(&self.features);
         let mut fields = Punctuated::<_, Comma>::new();
-        let mapped = self.fields.iter().map(|a| a.to_ref_decl());
+        let mapped = self.fields.iter().map(|a| a.to_ref_decl(&paths));
         fields.extend(mapped);
         quote! {
             #derives
@@ -273,9 +432,12 @@ impl Deriver<Struct> {
 
     pub fn derive_ref_ctor(&self) -> TokenStream {
         let ident = &self.ident;
+        let source = self.source();
         let new_ident = format_ref_ident(&ident);
 
         let generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let sycamore = &paths.sycamore;
         let mut added_generics = Punctuated::new();
 
         added_generics.push(GenericParam::Lifetime(LifetimeParam::new(named_lifetime(
@@ -296,7 +458,7 @@ impl Deriver<Struct> {
         let mut fields = Punctuated::<_, Comma>::new();
         let mapped = self.fields.iter().map(|a| {
             let ident = a.ident();
-            let arg = a.to_ref_ctor();
+            let arg = a.to_ref_ctor(&paths);
             quote! {
                 #ident: #arg
             }
@@ -306,7 +468,7 @@ impl Deriver<Struct> {
 
         quote! {
             impl #generics #new_ident #generics {
-                pub fn new<'ctor_ref>(cx: ::sycamore::prelude::Scope<'ctor_ref>, data: #ident #original_generics ) -> #new_ident #ctor_generics
+                pub fn new<'ctor_ref>(cx: #sycamore::prelude::Scope<'ctor_ref>, data: #source #original_generics ) -> #new_ident #ctor_generics
                 where
                     #bounds
                     {
@@ -319,6 +481,94 @@ impl Deriver<Struct> {
     }
 }
 
+impl Deriver<Struct> {
+    /// Builders of the Rc and Ref types, fields without a value fall back to their
+    /// `#[state(default = ..)]` and make `build` return `BuildError::MissingField` otherwise
+    pub fn derive_builders(&self) -> TokenStream {
+        let source = self.source();
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let rc_builder = format_ident!("{}Builder", rc_ident);
+        let ref_builder = format_ident!("{}Builder", ref_ident);
+        let original_generics = &self.generics;
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let (ctor_generics, bounds) = self.ref_ctor_generics();
+        let paths = self.features.crate_paths();
+        let sycamore = &paths.sycamore;
+        let state = &paths.state;
+
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let types = self.fields.iter().map(|a| a.ref_ty()).collect::<Vec<_>>();
+        let values = self.fields.iter().map(|a| {
+            let ident = a.ident();
+            match extract_state_options(&a.attrs()).default {
+                Some(default) => quote! { self.#ident.unwrap_or_else(|| #default) },
+                None => {
+                    let name = ident.unraw().to_string();
+                    quote! { self.#ident.ok_or(#state::BuildError::MissingField(#name))? }
+                }
+            }
+        });
+        let data = quote! { #source { #(#idents: #values),* } };
+
+        let builder = |builder: &Ident| {
+            quote! {
+                pub struct #builder #original_generics {
+                    #(#idents: Option<#types>),*
+                }
+
+                impl #original_generics #builder #original_generics {
+                    #(
+                        pub fn #idents(mut self, value: #types) -> Self {
+                            self.#idents = Some(value);
+                            self
+                        }
+                    )*
+                }
+            }
+        };
+        let rc_builder_decl = builder(&rc_builder);
+        let ref_builder_decl = builder(&ref_builder);
+
+        quote! {
+            #rc_builder_decl
+
+            impl #original_generics #rc_builder #original_generics {
+                pub fn build(self) -> ::core::result::Result<#rc_ident #rc_generics, #state::BuildError> {
+                    ::core::result::Result::Ok(#rc_ident::new(#data))
+                }
+            }
+
+            impl #rc_generics #rc_ident #rc_generics {
+                pub fn builder() -> #rc_builder #original_generics {
+                    #rc_builder { #(#idents: None),* }
+                }
+            }
+
+            #ref_builder_decl
+
+            impl #original_generics #ref_builder #original_generics {
+                pub fn build<'ctor_ref>(
+                    self,
+                    cx: #sycamore::prelude::Scope<'ctor_ref>,
+                ) -> ::core::result::Result<#ref_ident #ctor_generics, #state::BuildError>
+                where
+                    #bounds
+                {
+                    ::core::result::Result::Ok(#ref_ident::new(cx, #data))
+                }
+            }
+
+            impl #ref_generics #ref_ident #ref_generics {
+                pub fn builder() -> #ref_builder #original_generics {
+                    #ref_builder { #(#idents: None),* }
+                }
+            }
+        }
+    }
+}
+
 impl<T> GenericContainer for Deriver<T> {
     fn generics(&self) -> Generics {
         self.generics.clone()
@@ -455,8 +705,20 @@ impl FieldWrapper {
         }
     }
 
+    /// `#[state]` or an explicit `#[state(rc = .., ref = ..)]` mapping, other
+    /// `#[state(..)]` options like `default` leave the field bare
     pub(crate) fn is_stateful(&self) -> bool {
-        self.has_attribute(stateful_ident())
+        let options = extract_state_options(&self.attrs());
+        let marked = self.attrs().iter().any(|a| match &a.meta {
+            Meta::Path(path) => path.is_ident(&stateful_ident()),
+            _ => false,
+        });
+        marked || options.rc.is_some() || options.r#ref.is_some() || options.with.is_some()
+    }
+
+    /// Plain value held in a single signal, neither a state, a collection nor a set
+    pub(crate) fn is_bare(&self) -> bool {
+        !self.is_stateful() && !self.is_collection() && is_set(self).is_none()
     }
 
     pub(crate) fn has_attribute(&self, ident: Ident) -> bool {
@@ -0,0 +1,766 @@
+use crate::models::{CratePaths, Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::utils::{
+    collection_shape, extract_state_options, format_patch_ident, format_rc_ident, is_collection,
+    is_configured_collection, is_custom, is_map, is_owner, is_set, rc_state_ctor, rc_state_type,
+    unwrap_state_type, CollectionShape, MapCollection, StateWrapper,
+};
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::token::{Enum, Struct};
+use syn::{GenericArgument, GenericParam, PathArguments, Type};
+
+/// How a field is patched
+enum PatchKind {
+    /// Custom mapping written back with `StateField::set_rc`
+    Custom(TokenStream),
+    /// Signal set to the new value
+    Value,
+    /// Set cleared and extended with the new values
+    Set,
+    /// Signal set to the value of a new signal built by the constructor of the field
+    Fresh(TokenStream),
+    /// Sorted collection cleared and extended with the new values
+    Sorted(TokenStream),
+    /// Nested state, replaced or patched in place
+    State {
+        wrappers: Vec<StateWrapper>,
+        inner: Type,
+    },
+    /// Collection updated with a list of operations on its items
+    Collection(Box<CollectionShape>),
+    /// Map updated with a list of operations on its entries
+    Map(Box<MapCollection>),
+}
+
+/// Patch of a field, the type of the patch and the code computing and applying it
+struct FieldPatch {
+    payload: TokenStream,
+    apply: TokenStream,
+    diff: TokenStream,
+    bounds: Vec<TokenStream>,
+    nested: Vec<Type>,
+    redact: bool,
+    /// Type the traits of the patch are bounded on, the plain field type when the
+    /// payload holds the patch of the owner, as bounds on it would never resolve
+    bounded: TokenStream,
+}
+
+/// Type of the items of a collection, one level down from `ty`
+pub(crate) fn item_type(ty: &Type) -> Type {
+    let item = match ty {
+        Type::Path(path) => path.path.segments.last().and_then(|a| match &a.arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().find_map(|a| match a {
+                GenericArgument::Type(ty) => Some(ty.clone()),
+                _ => None,
+            }),
+            _ => None,
+        }),
+        _ => None,
+    };
+    item.expect("Type must be a collection")
+}
+
+impl FieldPatch {
+    /// Payload `value` for the `Debug` impl of the patch
+    fn debug_value(&self, value: TokenStream) -> TokenStream {
+        if self.redact {
+            quote! { &#value.as_ref().map(|_| ::core::format_args!("<redacted>")) }
+        } else {
+            quote! { #value }
+        }
+    }
+}
+
+impl FieldWrapper {
+    fn patch_kind(&self, paths: &CratePaths) -> PatchKind {
+        let is_stateful = self.is_stateful();
+        if let Some(with) = is_custom(self) {
+            PatchKind::Custom(quote! { #with })
+        } else if is_set(self).is_some() {
+            PatchKind::Set
+        } else if let Some(map) = is_map(self) {
+            PatchKind::Map(Box::new(map))
+        } else if let Some(configured) = is_configured_collection(self) {
+            if configured.signal == "RcSortedCollectionSignal" {
+                let element = is_collection(self).expect("Type must be a collection");
+                let values = match &element {
+                    Type::Path(inner) if is_stateful => {
+                        let rc_ident = rc_state_ctor(self, inner);
+                        quote! { v.into_iter().map(|a| #rc_ident::new(a)) }
+                    }
+                    _ => quote! { v },
+                };
+                PatchKind::Sorted(values)
+            } else {
+                let ctor = Self::rc_configured_ctor(
+                    paths,
+                    self,
+                    configured,
+                    is_stateful,
+                    false,
+                    quote! { v },
+                );
+                PatchKind::Fresh(quote! { #ctor })
+            }
+        } else if let Some(shape) = collection_shape(self) {
+            if shape.optional {
+                let ctor = Self::rc_collection_ctor(paths, self, is_stateful, quote! { v });
+                PatchKind::Fresh(quote! { #ctor })
+            } else {
+                PatchKind::Collection(Box::new(shape))
+            }
+        } else if is_stateful {
+            let (wrappers, inner) = unwrap_state_type(self.ref_ty());
+            PatchKind::State { wrappers, inner }
+        } else {
+            PatchKind::Value
+        }
+    }
+
+    /// Patch of the field, `access` is the field in the Rc state, `old` and `new`
+    /// are references to the field in the plain states
+    fn field_patch(
+        &self,
+        paths: &CratePaths,
+        owner: &Ident,
+        owner_patch: &TokenStream,
+        access: TokenStream,
+    ) -> FieldPatch {
+        let state = &paths.state;
+        let ty = self.ref_ty();
+        let mut bounds =
+            vec![quote! { for<'s> #ty: ::core::cmp::PartialEq + ::core::clone::Clone }];
+        let mut nested = Vec::new();
+        let mut owned = false;
+        // Patch type of a nested state, its bounds and how a patch `p` is unboxed
+        let mut state_patch = |inner: &Type, bounds: &mut Vec<TokenStream>| {
+            let rc = rc_state_type(self, inner);
+            if is_owner(inner, owner) {
+                owned = true;
+                let patch = quote! { ::std::boxed::Box<#owner_patch> };
+                (
+                    rc,
+                    patch,
+                    quote! { *p },
+                    quote! { ::std::boxed::Box::new(p) },
+                )
+            } else {
+                bounds.push(quote! { for<'s> #rc: #state::StatePatch<Value = #inner> });
+                nested.push(rc.clone());
+                let patch = quote! { <#rc as #state::StatePatch>::Patch };
+                (rc, patch, quote! { p }, quote! { p })
+            }
+        };
+        let replace = quote! { (old != new).then(|| ::core::clone::Clone::clone(new)) };
+
+        let (payload, apply, diff) = match self.patch_kind(paths) {
+            PatchKind::Custom(with) => (
+                quote! { #ty },
+                quote! { <#with as #state::StateField<#ty>>::set_rc(&#access, v); },
+                replace,
+            ),
+            PatchKind::Value => (quote! { #ty }, quote! { #access.set(v); }, replace),
+            PatchKind::Set => (
+                quote! { #ty },
+                quote! { #access.clear(); #access.extend(v); },
+                replace,
+            ),
+            PatchKind::Fresh(ctor) => (
+                quote! { #ty },
+                quote! { #access.set_rc((#ctor).get_untracked()); },
+                replace,
+            ),
+            PatchKind::Sorted(values) => (
+                quote! { #ty },
+                quote! { #access.clear(); #access.extend(#values); },
+                replace,
+            ),
+            PatchKind::State { wrappers, inner } => {
+                let (rc, patch, unbox, boxed) = state_patch(&inner, &mut bounds);
+                let ctor = Self::rc_stateful_ctor(paths, self, quote! { v });
+                let apply = wrappers.iter().rev().fold(
+                    quote! { <#rc as #state::StatePatch>::apply_patch(a, #unbox); },
+                    |value, wrapper| match wrapper {
+                        StateWrapper::Option => quote! {
+                            if let ::core::option::Option::Some(a) = a.as_ref() { #value }
+                        },
+                        StateWrapper::Box | StateWrapper::Rc => value,
+                    },
+                );
+                let diff = wrappers.iter().rev().fold(
+                    quote! { <#rc as #state::StatePatch>::diff(old, new) },
+                    |value, wrapper| match wrapper {
+                        StateWrapper::Option => quote! {
+                            match (old.as_ref(), new.as_ref()) {
+                                (::core::option::Option::Some(old), ::core::option::Option::Some(new)) => #value,
+                                _ => ::core::option::Option::None,
+                            }
+                        },
+                        StateWrapper::Box | StateWrapper::Rc => value,
+                    },
+                );
+                (
+                    quote! { #state::NestedPatch<#ty, #patch> },
+                    quote! {
+                        match v {
+                            #state::NestedPatch::Set(v) => #access.set_rc((#ctor).get_untracked()),
+                            #state::NestedPatch::Patch(p) => {
+                                let a = #access.get_untracked();
+                                let a = &*a;
+                                #apply
+                            }
+                        }
+                    },
+                    quote! {
+                        if old == new {
+                            ::core::option::Option::None
+                        } else {
+                            ::core::option::Option::Some(match #diff {
+                                ::core::option::Option::Some(p) => #state::NestedPatch::Patch(#boxed),
+                                ::core::option::Option::None => #state::NestedPatch::Set(::core::clone::Clone::clone(new)),
+                            })
+                        }
+                    },
+                )
+            }
+            PatchKind::Collection(shape) => {
+                let depth = shape.depth;
+                let element = &shape.element;
+                let item = if depth > 1 {
+                    item_type(ty)
+                } else {
+                    element.clone()
+                };
+                bounds
+                    .push(quote! { for<'s> #item: ::core::cmp::PartialEq + ::core::clone::Clone });
+                let is_stateful = self.is_stateful();
+                // Rc item built from the plain item `v`
+                let item_ctor = Self::rc_collection_levels(paths, self, &shape, 1, is_stateful);
+                let item_ctor = quote! { { let a = v; #item_ctor } };
+                let (patch, patch_apply, item_op) = if is_stateful && depth == 1 {
+                    let (rc, patch, unbox, boxed) = state_patch(element, &mut bounds);
+                    (
+                        patch,
+                        quote! {
+                            if let ::core::option::Option::Some(a) = #access.get_untracked().get(index) {
+                                <#rc as #state::StatePatch>::apply_patch(&*a.get_untracked(), #unbox);
+                            }
+                        },
+                        quote! {
+                            match <#rc as #state::StatePatch>::diff(old, new) {
+                                ::core::option::Option::Some(p) => #state::CollectionOp::Patch(index, #boxed),
+                                ::core::option::Option::None => #state::CollectionOp::Set(index, ::core::clone::Clone::clone(new)),
+                            }
+                        },
+                    )
+                } else {
+                    (
+                        quote! { () },
+                        quote! { let _ = (index, p); },
+                        quote! { #state::CollectionOp::Set(index, ::core::clone::Clone::clone(new)) },
+                    )
+                };
+                // Arrays keep their length, their diff never pushes or removes items
+                let resize_ops = if shape.arrays[0].is_some() {
+                    quote! {
+                        #state::CollectionOp::Push(_) | #state::CollectionOp::Remove(_) => {}
+                        #state::CollectionOp::Set(index, v) => {
+                            if let ::core::option::Option::Some(a) = #access.get_untracked().get(index) {
+                                a.set(#item_ctor);
+                            }
+                        }
+                    }
+                } else {
+                    quote! {
+                        #state::CollectionOp::Push(v) => #access.push(#item_ctor),
+                        #state::CollectionOp::Remove(index) => {
+                            if let ::core::option::Option::Some(a) = #access.get_untracked().get(index) {
+                                a.remove();
+                            }
+                        }
+                        #state::CollectionOp::Set(index, v) => {
+                            if let ::core::option::Option::Some(a) = #access.get_untracked().get(index) {
+                                a.replace(#item_ctor);
+                            }
+                        }
+                    }
+                };
+                (
+                    quote! { ::std::vec::Vec<#state::CollectionOp<#item, #patch>> },
+                    quote! {
+                        for op in v {
+                            match op {
+                                #resize_ops
+                                #state::CollectionOp::Patch(index, p) => {
+                                    #patch_apply
+                                }
+                            }
+                        }
+                    },
+                    quote! {
+                        if old == new {
+                            ::core::option::Option::None
+                        } else {
+                            let mut ops = ::std::vec::Vec::new();
+                            for (index, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                                if old != new {
+                                    ops.push(#item_op);
+                                }
+                            }
+                            for new in new.iter().skip(old.len()) {
+                                ops.push(#state::CollectionOp::Push(::core::clone::Clone::clone(new)));
+                            }
+                            for index in (new.len()..old.len()).rev() {
+                                ops.push(#state::CollectionOp::Remove(index));
+                            }
+                            ::core::option::Option::Some(ops)
+                        }
+                    },
+                )
+            }
+            PatchKind::Map(map) => {
+                let key = &map.key;
+                let value = &map.value;
+                bounds.push(quote! { for<'s> #key: ::core::clone::Clone });
+                bounds
+                    .push(quote! { for<'s> #value: ::core::cmp::PartialEq + ::core::clone::Clone });
+                let is_stateful = self.is_stateful();
+                let value_ctor = match value {
+                    Type::Path(inner) if is_stateful => {
+                        let rc_ident = rc_state_ctor(self, inner);
+                        quote! { #rc_ident::new(v) }
+                    }
+                    _ => quote! { v },
+                };
+                let (patch, patch_apply, changed) = if is_stateful {
+                    let (rc, patch, unbox, boxed) = state_patch(value, &mut bounds);
+                    (
+                        patch,
+                        quote! {
+                            if let ::core::option::Option::Some(a) = #state::MapEntries::entry(&*#access.get_untracked(), &key) {
+                                <#rc as #state::StatePatch>::apply_patch(&*a.get_untracked(), #unbox);
+                            }
+                        },
+                        quote! {
+                            ::core::option::Option::Some(old) => ops.push(match <#rc as #state::StatePatch>::diff(old, new) {
+                                ::core::option::Option::Some(p) => #state::MapOp::Patch(::core::clone::Clone::clone(key), #boxed),
+                                ::core::option::Option::None => #state::MapOp::Insert(::core::clone::Clone::clone(key), ::core::clone::Clone::clone(new)),
+                            }),
+                        },
+                    )
+                } else {
+                    (quote! { () }, quote! { let _ = (key, p); }, quote! {})
+                };
+                (
+                    quote! { ::std::vec::Vec<#state::MapOp<#key, #value, #patch>> },
+                    quote! {
+                        for op in v {
+                            match op {
+                                #state::MapOp::Insert(key, v) => #access.insert(key, #value_ctor),
+                                #state::MapOp::Remove(key) => {
+                                    #access.remove(&key);
+                                }
+                                #state::MapOp::Patch(key, p) => {
+                                    #patch_apply
+                                }
+                            }
+                        }
+                    },
+                    quote! {
+                        if old == new {
+                            ::core::option::Option::None
+                        } else {
+                            let mut ops = ::std::vec::Vec::new();
+                            for (key, new) in new.iter() {
+                                match old.get(key) {
+                                    ::core::option::Option::Some(old) if old == new => {}
+                                    #changed
+                                    _ => ops.push(#state::MapOp::Insert(::core::clone::Clone::clone(key), ::core::clone::Clone::clone(new))),
+                                }
+                            }
+                            for key in old.keys() {
+                                if !new.contains_key(key) {
+                                    ops.push(#state::MapOp::Remove(::core::clone::Clone::clone(key)));
+                                }
+                            }
+                            ::core::option::Option::Some(ops)
+                        }
+                    },
+                )
+            }
+        };
+        let payload = quote! { ::core::option::Option<#payload> };
+        FieldPatch {
+            bounded: if owned {
+                quote! { #ty }
+            } else {
+                payload.clone()
+            },
+            payload,
+            apply,
+            diff,
+            bounds,
+            nested,
+            redact: extract_state_options(&self.attrs()).redact,
+        }
+    }
+}
+
+impl<T> Deriver<T> {
+    /// Clone, PartialEq, Eq and Debug impls of the patch type, each only
+    /// holding when the payloads of the patch implement the trait
+    fn derive_patch_traits(
+        &self,
+        fields: &[&FieldPatch],
+        clone: TokenStream,
+        eq: TokenStream,
+        debug: TokenStream,
+    ) -> TokenStream {
+        let patch_ident = format_patch_ident(&self.ident);
+        let original_generics = &self.generics;
+        let nested = self.patch_declaration_bounds(fields);
+        let payloads = fields.iter().map(|a| &a.bounded).collect::<Vec<_>>();
+        let shown = fields
+            .iter()
+            .filter(|a| !a.redact)
+            .map(|a| &a.bounded)
+            .collect::<Vec<_>>();
+        quote! {
+            impl #original_generics ::core::clone::Clone for #patch_ident #original_generics
+            where
+                #nested
+                #(for<'s> #payloads: ::core::clone::Clone,)*
+            {
+                fn clone(&self) -> Self {
+                    #clone
+                }
+            }
+
+            impl #original_generics ::core::cmp::PartialEq for #patch_ident #original_generics
+            where
+                #nested
+                #(for<'s> #payloads: ::core::cmp::PartialEq,)*
+            {
+                fn eq(&self, other: &Self) -> bool {
+                    #eq
+                }
+            }
+
+            impl #original_generics ::core::cmp::Eq for #patch_ident #original_generics
+            where
+                #nested
+                #(for<'s> #payloads: ::core::cmp::Eq,)*
+            {
+            }
+
+            impl #original_generics ::core::fmt::Debug for #patch_ident #original_generics
+            where
+                #nested
+                #(for<'s> #shown: ::core::fmt::Debug,)*
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #debug
+                }
+            }
+        }
+    }
+
+    /// Type keeping the generics of the state in use
+    fn patch_marker(&self) -> Option<TokenStream> {
+        if self.generics.params.is_empty() {
+            return None;
+        }
+        let params = self.generics.params.iter().filter_map(|a| match a {
+            GenericParam::Lifetime(param) => {
+                let lifetime = &param.lifetime;
+                Some(quote! { &#lifetime () })
+            }
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                Some(quote! { #ident })
+            }
+            GenericParam::Const(_) => None,
+        });
+        Some(quote! { ::core::marker::PhantomData<(#(#params,)*)> })
+    }
+
+    fn derive_patch_impls(
+        &self,
+        patch: TokenStream,
+        fields: &[&FieldPatch],
+        apply: TokenStream,
+        diff: TokenStream,
+        inherent_diff: TokenStream,
+        traits: TokenStream,
+    ) -> TokenStream {
+        let patch_ident = format_patch_ident(&self.ident);
+        let rc_ident = format_rc_ident(&self.ident);
+        let source = self.source();
+        let original_generics = &self.generics;
+        let rc_generics = self.make_rc_generic();
+        let state = &self.features.crate_paths().state;
+        let bounds = fields.iter().flat_map(|a| &a.bounds);
+        let nested = self.patch_declaration_bounds(fields);
+        quote! {
+            #patch
+            #traits
+
+            impl #rc_generics #state::StatePatch for #rc_ident #rc_generics
+            where
+                #nested
+                #(#bounds,)*
+            {
+                type Value = #source #original_generics;
+                type Patch = #patch_ident #original_generics;
+
+                fn apply_patch(&self, patch: #patch_ident #original_generics) {
+                    #apply
+                }
+
+                fn diff(
+                    old: &#source #original_generics,
+                    new: &#source #original_generics,
+                ) -> ::core::option::Option<#patch_ident #original_generics> {
+                    #diff
+                }
+            }
+
+            impl #rc_generics #rc_ident #rc_generics {
+                /// Apply `patch`, only the signals it names are set
+                pub fn apply_patch(&self, patch: #patch_ident #original_generics)
+                where
+                    for<'s> Self: #state::StatePatch<Patch = #patch_ident #original_generics>,
+                {
+                    #state::StatePatch::apply_patch(self, patch)
+                }
+            }
+
+            impl #original_generics #patch_ident #original_generics
+            where
+                #nested
+            {
+                #inherent_diff
+            }
+        }
+    }
+
+    fn patch_declaration_bounds(&self, fields: &[&FieldPatch]) -> TokenStream {
+        let state = &self.features.crate_paths().state;
+        let nested = fields
+            .iter()
+            .flat_map(|a| &a.nested)
+            .filter(|ty| self.is_generic(ty))
+            .collect::<Vec<_>>();
+        quote! { #(#nested: #state::StatePatch,)* }
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_patches(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let patch_ident = format_patch_ident(&self.ident);
+        let rc_ident = format_rc_ident(&self.ident);
+        let source = self.source();
+        let original_generics = &self.generics;
+        let owner_patch = quote! { #patch_ident #original_generics };
+        let (idents, fields): (Vec<_>, Vec<_>) = self
+            .fields
+            .iter()
+            .map(|a| {
+                let ident = a.ident();
+                let patch =
+                    a.field_patch(&paths, &self.ident, &owner_patch, quote! { self.#ident });
+                (ident.clone(), patch)
+            })
+            .unzip();
+        let debug_values = idents
+            .iter()
+            .zip(&fields)
+            .map(|(ident, a)| a.debug_value(quote! { &self.#ident }));
+        let debug = quote! {
+            f.debug_struct(::core::stringify!(#patch_ident))
+                #(.field(::core::stringify!(#idents), #debug_values))*
+                .finish()
+        };
+        let fields = fields.iter().collect::<Vec<_>>();
+        let payloads = fields.iter().map(|a| &a.payload);
+        let applies = fields.iter().map(|a| &a.apply);
+        let diffs = fields.iter().map(|a| &a.diff);
+        let declaration_bounds = self.patch_declaration_bounds(&fields);
+        let marker = self.patch_marker();
+        let (marker_field, marker_value) = match &marker {
+            Some(marker) => (
+                Some(quote! { #[doc(hidden)] pub __marker: #marker, }),
+                Some(quote! { __marker: ::core::marker::PhantomData, }),
+            ),
+            None => (None, None),
+        };
+
+        let patch = quote! {
+            /// Partial update of the state, fields left to `None` are unchanged
+            #[derive(::core::default::Default)]
+            pub struct #patch_ident #original_generics
+            where
+                #declaration_bounds
+            {
+                #(pub #idents: #payloads,)*
+                #marker_field
+            }
+        };
+        let apply = quote! {
+            #(
+                if let ::core::option::Option::Some(v) = patch.#idents {
+                    #applies
+                }
+            )*
+        };
+        let diff = quote! {
+            ::core::option::Option::Some(#patch_ident {
+                #(#idents: {
+                    let old = &old.#idents;
+                    let new = &new.#idents;
+                    #diffs
+                },)*
+                #marker_value
+            })
+        };
+        let inherent_diff = quote! {
+            /// Patch turning `old` into `new`, only the fields which differ are set
+            pub fn diff(old: &#source #original_generics, new: &#source #original_generics) -> Self
+            where
+                for<'s> #rc_ident #original_generics: #state::StatePatch<Value = #source #original_generics, Patch = Self>,
+            {
+                <#rc_ident #original_generics as #state::StatePatch>::diff(old, new)
+                    .expect("struct states are always patched in place")
+            }
+        };
+        let clone = quote! {
+            Self {
+                #(#idents: ::core::clone::Clone::clone(&self.#idents),)*
+                #marker_value
+            }
+        };
+        let eq = quote! { true #(&& self.#idents == other.#idents)* };
+        let traits = self.derive_patch_traits(&fields, clone, eq, debug);
+        self.derive_patch_impls(patch, &fields, apply, diff, inherent_diff, traits)
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_patches(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let patch_ident = format_patch_ident(&self.ident);
+        let rc_ident = format_rc_ident(&self.ident);
+        let source = self.source();
+        let original_generics = &self.generics;
+        let owner_patch = quote! { #patch_ident #original_generics };
+        let patches = self
+            .fields
+            .iter()
+            .map(|a| {
+                let patch = a.field_patch(&paths, &self.ident, &owner_patch, quote! { (*data) });
+                (a.ident().clone(), patch)
+            })
+            .collect::<Vec<_>>();
+        let fields = patches.iter().map(|(_, a)| a).collect::<Vec<_>>();
+        let variants = patches.iter().map(|(ident, patch)| {
+            let payload = &patch.payload;
+            quote! { #ident(#payload) }
+        });
+        let applies = patches.iter().map(|(ident, patch)| {
+            let apply = &patch.apply;
+            quote! {
+                (Self::#ident(data), #patch_ident::#ident(::core::option::Option::Some(v))) => {
+                    #apply
+                }
+            }
+        });
+        let diffs = patches.iter().map(|(ident, patch)| {
+            let diff = &patch.diff;
+            quote! {
+                (#source::#ident(old), #source::#ident(new)) => {
+                    ::core::option::Option::Some(#patch_ident::#ident(#diff))
+                }
+            }
+        });
+        let clones = patches.iter().map(|(ident, _)| {
+            quote! { Self::#ident(a) => Self::#ident(::core::clone::Clone::clone(a)), }
+        });
+        let eqs = patches
+            .iter()
+            .map(|(ident, _)| quote! { (Self::#ident(a), Self::#ident(b)) => a == b, });
+        let debugs = patches.iter().map(|(ident, patch)| {
+            let value = patch.debug_value(quote! { a });
+            quote! {
+                Self::#ident(a) => f.debug_tuple(::core::stringify!(#ident)).field(#value).finish(),
+            }
+        });
+        let never = (!self.generics.params.is_empty())
+            .then(|| quote! { Self::__Marker(never, _) => match *never {}, });
+        let clone = quote! {
+            match self {
+                #(#clones)*
+                #never
+            }
+        };
+        let eq = quote! {
+            #[allow(unreachable_patterns)]
+            match (self, other) {
+                #(#eqs)*
+                _ => false,
+            }
+        };
+        let debug = quote! {
+            match self {
+                #(#debugs)*
+                #never
+            }
+        };
+        let switched =
+            (self.fields.len() > 1).then(|| quote! { _ => ::core::option::Option::None, });
+        let declaration_bounds = self.patch_declaration_bounds(&fields);
+        let marker = self.patch_marker().map(|marker| {
+            quote! {
+                #[doc(hidden)]
+                __Marker(::core::convert::Infallible, #marker),
+            }
+        });
+
+        let patch = quote! {
+            /// Partial update of the variant of the state, fields left to `None` are unchanged
+            pub enum #patch_ident #original_generics
+            where
+                #declaration_bounds
+            {
+                #(#variants,)*
+                #marker
+            }
+        };
+        let apply = quote! {
+            #[allow(unreachable_patterns)]
+            match (self, patch) {
+                #(#applies)*
+                _ => {}
+            }
+        };
+        let diff = quote! {
+            match (old, new) {
+                #(#diffs)*
+                #switched
+            }
+        };
+        let inherent_diff = quote! {
+            /// Patch turning `old` into `new`, [`None`] when the variant changed
+            pub fn diff(old: &#source #original_generics, new: &#source #original_generics) -> ::core::option::Option<Self>
+            where
+                for<'s> #rc_ident #original_generics: #state::StatePatch<Value = #source #original_generics, Patch = Self>,
+            {
+                <#rc_ident #original_generics as #state::StatePatch>::diff(old, new)
+            }
+        };
+        let traits = self.derive_patch_traits(&fields, clone, eq, debug);
+        self.derive_patch_impls(patch, &fields, apply, diff, inherent_diff, traits)
+    }
+}
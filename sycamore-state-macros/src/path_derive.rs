@@ -0,0 +1,588 @@
+use crate::convert_derive::Repr;
+use crate::models::{CratePaths, Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::utils::{
+    collection_shape, format_path_ident, format_path_variant, format_rc_ident, format_ref_ident,
+    is_collection, is_configured_collection, is_custom, is_map, is_owner, is_set, rc_state_type,
+    unwrap_state_type, StateWrapper,
+};
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::token::{Enum, Struct};
+use syn::{GenericParam, Type};
+
+/// Items of a collection or values of a map
+enum Items {
+    /// Plain values, reported by index or key
+    Plain,
+    /// Nested states, `boxed` when the state holds itself
+    State { boxed: bool },
+}
+
+/// Code shared by the watchers of every field
+struct Watch<'a> {
+    paths: &'a CratePaths,
+}
+
+impl Watch<'_> {
+    /// Reports `path` each time the signal `handle` changes
+    fn leaf(&self, handle: TokenStream, track: TokenStream, path: TokenStream) -> TokenStream {
+        let sycamore = &self.paths.sycamore;
+        quote! {
+            {
+                let h = ::core::clone::Clone::clone(#handle);
+                let report = ::std::rc::Rc::clone(&report);
+                let mut first = true;
+                #sycamore::reactive::create_effect(cx, move || {
+                    #track
+                    if !::core::mem::take(&mut first) {
+                        report(#path);
+                    }
+                });
+            }
+        }
+    }
+
+    /// Reports `replaced` when the signal `handle` holding a nested state is set, and
+    /// `changed` for every change of the nested state, given as `path`
+    ///
+    /// `captures` are cloned into the nested subscription along with the reporter
+    fn state(
+        &self,
+        handle: TokenStream,
+        wrappers: &[StateWrapper],
+        replaced: TokenStream,
+        changed: TokenStream,
+        captures: TokenStream,
+    ) -> TokenStream {
+        let sycamore = &self.paths.sycamore;
+        let subscribe = quote! {
+            #captures
+            let report = ::std::rc::Rc::clone(&report);
+            a.on_change(cx, move |path| report(#changed));
+        };
+        let subscribe = wrappers
+            .iter()
+            .rev()
+            .fold(subscribe, |value, wrapper| match wrapper {
+                StateWrapper::Option => quote! {
+                    if let ::core::option::Option::Some(a) = a.as_ref() { #value }
+                },
+                StateWrapper::Box | StateWrapper::Rc => value,
+            });
+        quote! {
+            {
+                let h = ::core::clone::Clone::clone(#handle);
+                let report = ::std::rc::Rc::clone(&report);
+                let mut first = true;
+                #sycamore::reactive::create_effect_scoped(cx, move |cx| {
+                    let a = h.get();
+                    if !::core::mem::take(&mut first) {
+                        report(#replaced);
+                    }
+                    let a = &*a;
+                    #subscribe
+                });
+            }
+        }
+    }
+
+    /// Watches the collection `handle` with `depth` levels of nesting, `wrap` maps
+    /// the path within the collection to the path of the field
+    fn collection(
+        &self,
+        handle: TokenStream,
+        wrap: &dyn Fn(TokenStream) -> TokenStream,
+        depth: usize,
+        level: usize,
+        items: &Items,
+    ) -> TokenStream {
+        let state = &self.paths.state;
+        let sycamore = &self.paths.sycamore;
+        let index = format_ident!("index_{}", level);
+        let replaced = wrap(quote! { #state::CollectionPath::Index(#index) });
+        let item = if depth > 1 {
+            let inner = self.collection(
+                quote! { a },
+                &|path| wrap(quote! { #state::CollectionPath::Item(#index, #path) }),
+                depth - 1,
+                level + 1,
+                items,
+            );
+            quote! {
+                {
+                    let h = ::core::clone::Clone::clone(a);
+                    let report = ::std::rc::Rc::clone(&report);
+                    let mut first = true;
+                    #sycamore::reactive::create_effect_scoped(cx, move |cx| {
+                        let a = h.get();
+                        if !::core::mem::take(&mut first) {
+                            report(#replaced);
+                        }
+                        let a = &*a;
+                        #inner
+                    });
+                }
+            }
+        } else {
+            match items {
+                Items::Plain => self.leaf(quote! { a }, quote! { h.track(); }, replaced),
+                Items::State { boxed } => {
+                    let path = boxed_path(*boxed);
+                    let changed = wrap(quote! { #state::CollectionPath::Item(#index, #path) });
+                    self.state(quote! { a }, &[], replaced, changed, quote! {})
+                }
+            }
+        };
+        let moved = wrap(quote! { #state::CollectionPath::Items });
+        quote! {
+            {
+                let h = ::core::clone::Clone::clone(#handle);
+                let report = ::std::rc::Rc::clone(&report);
+                let mut first = true;
+                #sycamore::reactive::create_effect_scoped(cx, move |cx| {
+                    let items = h.get();
+                    if !::core::mem::take(&mut first) {
+                        report(#moved);
+                    }
+                    for (#index, a) in items.iter().enumerate() {
+                        #item
+                    }
+                });
+            }
+        }
+    }
+
+    /// Watches the map `handle`, `wrap` maps the path within the map to the path of the field
+    fn map(
+        &self,
+        handle: TokenStream,
+        wrap: &dyn Fn(TokenStream) -> TokenStream,
+        items: &Items,
+    ) -> TokenStream {
+        let state = &self.paths.state;
+        let sycamore = &self.paths.sycamore;
+        let replaced = wrap(quote! { #state::MapPath::Key(::core::clone::Clone::clone(&key)) });
+        let item = match items {
+            Items::Plain => self.leaf(quote! { a }, quote! { h.track(); }, replaced),
+            Items::State { boxed } => {
+                let path = boxed_path(*boxed);
+                let changed = wrap(
+                    quote! { #state::MapPath::Item(::core::clone::Clone::clone(&key), #path) },
+                );
+                let captures = quote! { let key = ::core::clone::Clone::clone(&key); };
+                self.state(quote! { a }, &[], replaced, changed, captures)
+            }
+        };
+        let moved = wrap(quote! { #state::MapPath::Entries });
+        quote! {
+            {
+                let h = ::core::clone::Clone::clone(#handle);
+                let report = ::std::rc::Rc::clone(&report);
+                let mut first = true;
+                #sycamore::reactive::create_effect_scoped(cx, move |cx| {
+                    let entries = ::core::ops::Deref::deref(&*h).get();
+                    if !::core::mem::take(&mut first) {
+                        report(#moved);
+                    }
+                    for (key, a) in entries.iter() {
+                        let key = ::core::clone::Clone::clone(key);
+                        #item
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Path reported by a nested state, boxed when the state holds itself
+fn boxed_path(boxed: bool) -> TokenStream {
+    if boxed {
+        quote! { ::std::boxed::Box::new(path) }
+    } else {
+        quote! { path }
+    }
+}
+
+/// Path type of a field and the Rc types of the nested states it names
+struct FieldPath {
+    payload: Option<TokenStream>,
+    nested: Vec<Type>,
+}
+
+impl FieldWrapper {
+    /// Variant of the field in the path type
+    fn path_variant(&self) -> Ident {
+        match self {
+            FieldWrapper::Struct(_) => format_path_variant(self.ident()),
+            FieldWrapper::Enum(_) => self.ident().clone(),
+        }
+    }
+
+    /// Type the variant of the field holds, the path within the field
+    fn path_payload(
+        &self,
+        paths: &CratePaths,
+        owner: &Ident,
+        owner_path: &TokenStream,
+    ) -> FieldPath {
+        let state = &paths.state;
+        let mut nested = Vec::new();
+        let mut state_path = |ty: &Type| {
+            if is_owner(ty, owner) {
+                quote! { ::std::boxed::Box<#owner_path> }
+            } else {
+                let rc = rc_state_type(self, ty);
+                let path = quote! { <#rc as #state::StatePath>::Path };
+                nested.push(rc);
+                path
+            }
+        };
+        let is_stateful = self.is_stateful();
+        let mut item_path = |ty: &Type| {
+            if is_stateful {
+                state_path(ty)
+            } else {
+                quote! { () }
+            }
+        };
+
+        let payload = if is_custom(self).is_some() || is_set(self).is_some() {
+            None
+        } else if let Some(map) = is_map(self) {
+            let key = &map.key;
+            let item = item_path(&map.value);
+            Some(quote! { #state::MapPath<#key, #item> })
+        } else if is_configured_collection(self).is_some() {
+            let element = is_collection(self).expect("Type must be a collection");
+            let item = item_path(&element);
+            Some(quote! { #state::CollectionPath<#item> })
+        } else if let Some(shape) = collection_shape(self) {
+            let item = item_path(&shape.element);
+            Some((0..shape.depth).fold(item, |item, _| {
+                quote! { #state::CollectionPath<#item> }
+            }))
+        } else if is_stateful {
+            let (_, inner) = unwrap_state_type(self.ref_ty());
+            let path = state_path(&inner);
+            Some(quote! { ::core::option::Option<#path> })
+        } else {
+            None
+        };
+        FieldPath { payload, nested }
+    }
+
+    /// Statements reporting the changes of the field at `access`, a reference to the field
+    fn watch_changes(
+        &self,
+        paths: &CratePaths,
+        owner: &Ident,
+        path_ident: &Ident,
+        access: TokenStream,
+        repr: Repr,
+    ) -> TokenStream {
+        let state = &paths.state;
+        let watch = Watch { paths };
+        let variant = self.path_variant();
+        let wrap = |path: TokenStream| quote! { #path_ident::#variant(#path) };
+        let is_stateful = self.is_stateful();
+        let items = |ty: &Type| match is_stateful {
+            true => Items::State {
+                boxed: is_owner(ty, owner),
+            },
+            false => Items::Plain,
+        };
+
+        if let Some(with) = is_custom(self) {
+            let ty = self.ref_ty();
+            let track = match repr {
+                Repr::Rc => quote! { track_rc },
+                Repr::Ref => quote! { track_ref },
+            };
+            let track = quote! { <#with as #state::StateField<#ty>>::#track(&h); };
+            watch.leaf(access, track, quote! { #path_ident::#variant })
+        } else if is_set(self).is_some() {
+            watch.leaf(
+                access,
+                quote! { h.track(); },
+                quote! { #path_ident::#variant },
+            )
+        } else if let Some(map) = is_map(self) {
+            watch.map(access, &wrap, &items(&map.value))
+        } else if is_configured_collection(self).is_some() {
+            let element = is_collection(self).expect("Type must be a collection");
+            watch.collection(access, &wrap, 1, 0, &items(&element))
+        } else if let Some(shape) = collection_shape(self) {
+            let items = items(&shape.element);
+            if shape.optional {
+                let sycamore = &paths.sycamore;
+                let inner = watch.collection(quote! { a }, &wrap, shape.depth, 0, &items);
+                let replaced = wrap(quote! { #state::CollectionPath::Items });
+                quote! {
+                    {
+                        let h = ::core::clone::Clone::clone(#access);
+                        let report = ::std::rc::Rc::clone(&report);
+                        let mut first = true;
+                        #sycamore::reactive::create_effect_scoped(cx, move |cx| {
+                            let a = h.get();
+                            if !::core::mem::take(&mut first) {
+                                report(#replaced);
+                            }
+                            if let ::core::option::Option::Some(a) = ::core::option::Option::as_ref(&*a) {
+                                #inner
+                            }
+                        });
+                    }
+                }
+            } else {
+                watch.collection(access, &wrap, shape.depth, 0, &items)
+            }
+        } else if is_stateful {
+            let (wrappers, inner) = unwrap_state_type(self.ref_ty());
+            let path = boxed_path(is_owner(&inner, owner));
+            let replaced = wrap(quote! { ::core::option::Option::None });
+            let changed = wrap(quote! { ::core::option::Option::Some(#path) });
+            watch.state(access, &wrappers, replaced, changed, quote! {})
+        } else {
+            watch.leaf(
+                access,
+                quote! { h.track(); },
+                quote! { #path_ident::#variant },
+            )
+        }
+    }
+
+    /// Bounds letting the watchers clone the handle of a custom field
+    fn watch_bounds(&self, paths: &CratePaths, repr: Repr) -> Option<TokenStream> {
+        let state = &paths.state;
+        let with = is_custom(self)?;
+        let ty = self.ref_ty();
+        Some(match repr {
+            Repr::Rc => quote! {
+                for<'s> <#with as #state::StateField<#ty>>::Rc: ::core::clone::Clone
+            },
+            Repr::Ref => quote! {
+                for<'s> <#with as #state::StateField<#ty>>::Ref<'stateful>: ::core::clone::Clone
+            },
+        })
+    }
+}
+
+impl<T> Deriver<T> {
+    fn derive_path_impls(
+        &self,
+        variants: Vec<TokenStream>,
+        nested: Vec<Type>,
+        rc_body: TokenStream,
+        ref_body: TokenStream,
+    ) -> TokenStream {
+        let path_ident = format_path_ident(&self.ident);
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let original_generics = &self.generics;
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let path = quote! { #path_ident #original_generics };
+        let rc_bounds = self
+            .fields
+            .iter()
+            .filter_map(|a| a.watch_bounds(&paths, Repr::Rc));
+        let ref_bounds = self
+            .fields
+            .iter()
+            .filter_map(|a| a.watch_bounds(&paths, Repr::Ref));
+        let nested = quote! { #(#nested: #state::StatePath,)* };
+
+        let debug = self.features.debug.then(|| quote! { ::core::fmt::Debug, });
+        // Keeps the generics of the state in use, without being constructible
+        let marker = (!self.generics.params.is_empty()).then(|| {
+            let params = self.generics.params.iter().filter_map(|a| match a {
+                GenericParam::Lifetime(param) => {
+                    let lifetime = &param.lifetime;
+                    Some(quote! { &#lifetime () })
+                }
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    Some(quote! { #ident })
+                }
+                GenericParam::Const(_) => None,
+            });
+            quote! {
+                #[doc(hidden)]
+                __Marker(::core::convert::Infallible, ::core::marker::PhantomData<(#(#params,)*)>),
+            }
+        });
+
+        let on_change = |body: TokenStream, bounds: TokenStream| {
+            quote! {
+                /// Call `f` in `cx` with the path of every field that changes,
+                /// `f` itself runs untracked
+                ///
+                /// Inside a `batch` the paths are reported once each when it ends
+                pub fn on_change<'track>(
+                    &self,
+                    cx: #sycamore::reactive::Scope<'track>,
+                    f: impl Fn(#path) + 'track,
+                ) where
+                    Self: 'track,
+                    for<'s> #path: ::core::cmp::PartialEq,
+                    #nested
+                    #bounds
+                {
+                    let pending = ::std::rc::Rc::new(::core::cell::RefCell::new(::std::vec::Vec::<#path>::new()));
+                    let notifier = #state::BatchNotifier::new();
+                    let mut first = true;
+                    #sycamore::reactive::create_effect(cx, {
+                        let pending = ::std::rc::Rc::clone(&pending);
+                        let notifier = ::core::clone::Clone::clone(&notifier);
+                        move || {
+                            notifier.track();
+                            if !::core::mem::take(&mut first) {
+                                let paths = ::core::mem::take(&mut *pending.borrow_mut());
+                                #sycamore::reactive::untrack(|| paths.into_iter().for_each(&f));
+                            }
+                        }
+                    });
+                    let report: ::std::rc::Rc<dyn Fn(#path) + 'track> = ::std::rc::Rc::new(move |path| {
+                        let mut paths = pending.borrow_mut();
+                        if !paths.contains(&path) {
+                            paths.push(path);
+                        }
+                        ::core::mem::drop(paths);
+                        notifier.notify();
+                    });
+                    #body
+                }
+            }
+        };
+        let rc_on_change = on_change(rc_body, quote! { #(#rc_bounds,)* });
+        let ref_on_change = on_change(ref_body, quote! { #(#ref_bounds,)* });
+
+        quote! {
+            /// Path to a changed field of the state, reported by `on_change`
+            #[derive(#debug ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+            pub enum #path_ident #original_generics
+            where
+                #nested
+            {
+                #(#variants,)*
+                #marker
+            }
+
+            impl #rc_generics #state::StatePath for #rc_ident #rc_generics
+            where
+                #path: ::core::clone::Clone + ::core::cmp::Eq,
+                #nested
+            {
+                type Path = #path;
+            }
+
+            impl #ref_generics #state::StatePath for #ref_ident #ref_generics
+            where
+                #path: ::core::clone::Clone + ::core::cmp::Eq,
+                #nested
+            {
+                type Path = #path;
+            }
+
+            impl #rc_generics #rc_ident #rc_generics {
+                #rc_on_change
+            }
+
+            impl #ref_generics #ref_ident #ref_generics {
+                #ref_on_change
+            }
+        }
+    }
+
+    /// Whether `ty` mentions a type parameter of the state
+    ///
+    /// Only those nested states need a `StatePath` bound, bounds on concrete
+    /// types would hide which path type the impl resolves to
+    pub(crate) fn is_generic(&self, ty: &Type) -> bool {
+        fn mentions(tokens: TokenStream, params: &[&Ident]) -> bool {
+            tokens.into_iter().any(|token| match token {
+                TokenTree::Ident(ident) => params.contains(&&ident),
+                TokenTree::Group(group) => mentions(group.stream(), params),
+                _ => false,
+            })
+        }
+        let params = self
+            .generics
+            .type_params()
+            .map(|a| &a.ident)
+            .collect::<Vec<_>>();
+        mentions(ty.to_token_stream(), &params)
+    }
+
+    /// Variants of the path type along with the generic nested states they name
+    fn path_variants(&self) -> (Vec<TokenStream>, Vec<Type>) {
+        let paths = self.features.crate_paths();
+        let path_ident = format_path_ident(&self.ident);
+        let generics = &self.generics;
+        let owner_path = quote! { #path_ident #generics };
+        let mut nested = Vec::new();
+        let variants = self
+            .fields
+            .iter()
+            .map(|a| {
+                let variant = a.path_variant();
+                let FieldPath {
+                    payload,
+                    nested: field_nested,
+                } = a.path_payload(&paths, &self.ident, &owner_path);
+                nested.extend(field_nested.into_iter().filter(|ty| self.is_generic(ty)));
+                match payload {
+                    Some(payload) => quote! { #variant(#payload) },
+                    None => quote! { #variant },
+                }
+            })
+            .collect();
+        (variants, nested)
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_paths(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let path_ident = format_path_ident(&self.ident);
+        let (variants, nested) = self.path_variants();
+        let body = |repr: Repr| {
+            let fields = self.fields.iter().map(|a| {
+                let ident = a.ident();
+                a.watch_changes(
+                    &paths,
+                    &self.ident,
+                    &path_ident,
+                    quote! { &self.#ident },
+                    repr,
+                )
+            });
+            quote! { #(#fields)* }
+        };
+        self.derive_path_impls(variants, nested, body(Repr::Rc), body(Repr::Ref))
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_paths(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let path_ident = format_path_ident(&self.ident);
+        let (variants, nested) = self.path_variants();
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let body = |repr: Repr| {
+            let variants = self
+                .fields
+                .iter()
+                .map(|a| a.watch_changes(&paths, &self.ident, &path_ident, quote! { data }, repr));
+            quote! {
+                match self {
+                    #(Self::#idents(data) => { #variants }),*
+                }
+            }
+        };
+        self.derive_path_impls(variants, nested, body(Repr::Rc), body(Repr::Ref))
+    }
+}
@@ -1,202 +1,389 @@
-use crate::models::{FieldWrapper, GetSetType};
-use crate::utils::format_rc_ident;
-use crate::utils::is_collection;
+use crate::models::{CratePaths, FieldWrapper, GetSetType};
+use crate::utils::{
+    collection_shape, is_collection, is_configured_collection, is_custom, is_map, is_set,
+    CollectionShape, ConfiguredCollection, MapCollection, SetCollection,
+};
+use crate::utils::{
+    rc_state_ctor, rc_state_type, unwrap_state_type, wrap_state_ctor, wrap_state_type,
+};
+use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::*;
 
 impl FieldWrapper {
-    pub fn to_rc_decl(&self) -> Box<dyn ToTokens> {
+    pub fn to_rc_decl(&self, paths: &CratePaths) -> Box<dyn ToTokens> {
         let is_stateful = self.is_stateful();
         let is_collection = self.is_collection();
         let is_derived = self.is_derived();
 
         match &self {
             FieldWrapper::Enum(data) => {
-                let mut field = Self::inner_to_rc_decl(data, is_stateful, is_collection);
+                let mut field = Self::inner_to_rc_decl(paths, data, is_stateful, is_collection);
                 field.attrs.clear();
                 Box::new(field)
             }
             FieldWrapper::Struct(data) => {
-                let mut variant = Self::inner_to_rc_decl(data, is_stateful, is_collection);
+                let mut variant = Self::inner_to_rc_decl(paths, data, is_stateful, is_collection);
                 variant.attrs.clear();
                 Box::new(variant)
             }
         }
     }
-    fn inner_to_rc_decl<F: GetSetType>(field: &F, is_stateful: bool, is_collection: bool) -> F {
-        if is_stateful && is_collection {
-            Self::rc_stateful_collection_decl(field)
-        } else if is_stateful {
-            Self::rc_stateful_decl(field)
+    fn inner_to_rc_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        is_stateful: bool,
+        is_collection: bool,
+    ) -> F {
+        if let Some(with) = is_custom(field) {
+            Self::rc_custom_decl(paths, field, with)
+        } else if let Some(set) = is_set(field) {
+            Self::rc_set_decl(paths, field, set)
+        } else if let Some(map) = is_map(field) {
+            Self::rc_map_decl(paths, field, map, is_stateful)
+        } else if let Some(configured) = is_configured_collection(field) {
+            Self::rc_configured_decl(paths, field, configured.signal, is_stateful)
         } else if is_collection {
-            Self::rc_collection_decl(field)
+            Self::rc_collection_decl(paths, field, is_stateful)
+        } else if is_stateful {
+            Self::rc_stateful_decl(paths, field)
         } else {
-            Self::rc_bare_decl(field)
+            Self::rc_bare_decl(paths, field)
         }
     }
-    fn rc_stateful_collection_decl<F: GetSetType>(field: &F) -> F {
-        let mut inner_type = is_collection(field).expect("Type must be a collection");
+    fn rc_collection_decl<F: GetSetType>(paths: &CratePaths, field: &F, is_stateful: bool) -> F {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let CollectionShape {
+            optional,
+            mut element,
+            arrays,
+            ..
+        } = collection_shape(field).expect("Type must be a collection");
         let mut new_field = field.clone();
 
-        if let Type::Path(data) = &mut inner_type {
-            let mut last = data.path.segments.last_mut().expect("failed to parse type");
-            last.ident = format_rc_ident(&last.ident);
+        if is_stateful {
+            element = rc_state_type(field, &element);
+        }
+        let mut ty = quote! { #element };
+        for len in arrays.iter().rev() {
+            ty = match len {
+                Some(len) => quote! {
+                    #sycamore::prelude::RcSignal<[#sycamore::prelude::RcSignal<#ty>; #len]>
+                },
+                None => quote! { #state::RcCollectionSignal<#ty> },
+            };
+        }
+        if optional {
+            ty = quote! { #sycamore::prelude::RcSignal<Option<#ty>> };
         }
-        *new_field.mut_ty() =
-            Type::Verbatim(quote! { ::sycamore_state_manager::RcCollectionSignal<#inner_type>});
+        *new_field.mut_ty() = Type::Verbatim(ty);
 
         new_field
     }
-    fn rc_stateful_decl<F: GetSetType>(field: &F) -> F {
+    fn rc_stateful_decl<F: GetSetType>(paths: &CratePaths, field: &F) -> F {
+        let sycamore = &paths.sycamore;
+        let mut new_field = field.clone();
+        let (wrappers, inner) = unwrap_state_type(field.ref_ty());
+        let inner = rc_state_type(field, &inner);
+        let ty = wrap_state_type(&wrappers, quote! { #inner });
+        *new_field.mut_ty() = Type::Verbatim(quote! {#sycamore::prelude::RcSignal<#ty>});
+        new_field
+    }
+    fn rc_map_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        map: MapCollection,
+        is_stateful: bool,
+    ) -> F {
+        let state = &paths.state;
+        let MapCollection {
+            signal,
+            key,
+            mut value,
+        } = map;
         let mut new_field = field.clone();
-        let mut old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &mut old_ty {
-            let mut last = data.path.segments.last_mut().expect("failed to parse type");
-            last.ident = format_rc_ident(&last.ident);
+
+        if is_stateful {
+            value = rc_state_type(field, &value);
         }
-        *new_field.mut_ty() = Type::Verbatim(quote! {::sycamore::prelude::RcSignal<#old_ty>});
+        *new_field.mut_ty() = Type::Verbatim(quote! { #state::#signal<'static, #key, #value>});
+
         new_field
     }
-    fn rc_collection_decl<F: GetSetType>(field: &F) -> F {
-        let inner_type = is_collection(field).expect("Type must be a collection");
+    fn rc_configured_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        signal: Ident,
+        is_stateful: bool,
+    ) -> F {
+        let state = &paths.state;
+        let mut inner_type = is_collection(field).expect("Type must be a collection");
         let mut new_field = field.clone();
 
-        *new_field.mut_ty() =
-            Type::Verbatim(quote! { ::sycamore_state_manager::RcCollectionSignal<#inner_type>});
+        if is_stateful {
+            inner_type = rc_state_type(field, &inner_type);
+        }
+        *new_field.mut_ty() = Type::Verbatim(quote! { #state::#signal<#inner_type>});
 
         new_field
     }
-    fn rc_bare_decl<F: GetSetType>(field: &F) -> F {
+    fn rc_custom_decl<F: GetSetType>(paths: &CratePaths, field: &F, with: Path) -> F {
+        let state = &paths.state;
+        let old_ty = field.ref_ty();
+        let mut new_field = field.clone();
+        *new_field.mut_ty() = Type::Verbatim(quote! { <#with as #state::StateField<#old_ty>>::Rc });
+        new_field
+    }
+    fn rc_set_decl<F: GetSetType>(paths: &CratePaths, field: &F, set: SetCollection) -> F {
+        let state = &paths.state;
+        let SetCollection { signal, value } = set;
+        let mut new_field = field.clone();
+        *new_field.mut_ty() = Type::Verbatim(quote! { #state::#signal<#value>});
+        new_field
+    }
+    fn rc_bare_decl<F: GetSetType>(paths: &CratePaths, field: &F) -> F {
+        let sycamore = &paths.sycamore;
         let old_ty = &field.ref_ty();
         let mut new_field = field.clone();
-        *new_field.mut_ty() = Type::Verbatim(quote! { ::sycamore::prelude::RcSignal<#old_ty>});
+        *new_field.mut_ty() = Type::Verbatim(quote! { #sycamore::prelude::RcSignal<#old_ty>});
         new_field
     }
 }
 
 impl FieldWrapper {
-    pub fn to_rc_ctor(&self) -> Box<dyn ToTokens> {
+    /// `watched` keeps sorted collections of states in order as the states change,
+    /// the watcher needs `'static` items so it is left out for generic owners
+    pub fn to_rc_ctor(&self, paths: &CratePaths, watched: bool) -> Box<dyn ToTokens> {
         let is_stateful = self.is_stateful();
         let is_collection = self.is_collection();
+        let watched = watched && is_stateful;
         match &self {
             FieldWrapper::Enum(data) => {
-                let res = Self::inner_to_enum_rc_ctor(data, is_stateful, is_collection);
+                let res =
+                    Self::inner_to_enum_rc_ctor(paths, data, is_stateful, is_collection, watched);
                 Box::new(res)
             }
             FieldWrapper::Struct(data) => {
-                let res = Self::inner_to_struct_rc_ctor(data, is_stateful, is_collection);
+                let res =
+                    Self::inner_to_struct_rc_ctor(paths, data, is_stateful, is_collection, watched);
                 Box::new(res)
             }
         }
     }
 
     fn inner_to_struct_rc_ctor<F: GetSetType>(
+        paths: &CratePaths,
         field: &F,
         is_stateful: bool,
         is_collection: bool,
+        watched: bool,
     ) -> Expr {
-        if is_stateful && is_collection {
-            Self::rc_struct_stateful_collection_ctor(field)
-        } else if is_stateful {
-            Self::rc_struct_stateful_ctor(field)
+        if let Some(with) = is_custom(field) {
+            let ident = field.ident();
+            Self::rc_custom_ctor(paths, field, with, quote! { data.#ident })
+        } else if let Some(set) = is_set(field) {
+            let ident = field.ident();
+            Self::rc_set_ctor(paths, set, quote! { data.#ident })
+        } else if let Some(map) = is_map(field) {
+            let ident = field.ident();
+            Self::rc_map_ctor(paths, field, map, is_stateful, quote! { data.#ident })
+        } else if let Some(configured) = is_configured_collection(field) {
+            let ident = field.ident();
+            Self::rc_configured_ctor(
+                paths,
+                field,
+                configured,
+                is_stateful,
+                watched,
+                quote! { data.#ident },
+            )
         } else if is_collection {
-            Self::rc_struct_collection_ctor(field)
+            let ident = field.ident();
+            Self::rc_collection_ctor(paths, field, is_stateful, quote! { data.#ident })
+        } else if is_stateful {
+            let ident = field.ident();
+            Self::rc_stateful_ctor(paths, field, quote! { data.#ident })
         } else {
-            Self::rc_struct_bare_ctor(field)
+            Self::rc_struct_bare_ctor(paths, field)
         }
     }
 
     fn inner_to_enum_rc_ctor<F: GetSetType>(
+        paths: &CratePaths,
         field: &F,
         is_stateful: bool,
         is_collection: bool,
+        watched: bool,
     ) -> Expr {
-        if is_stateful && is_collection {
-            Self::rc_enum_stateful_collection_ctor(field)
-        } else if is_stateful {
-            Self::rc_enum_stateful_ctor(field)
+        if let Some(with) = is_custom(field) {
+            Self::rc_custom_ctor(paths, field, with, quote! { data })
+        } else if let Some(set) = is_set(field) {
+            Self::rc_set_ctor(paths, set, quote! { data })
+        } else if let Some(map) = is_map(field) {
+            Self::rc_map_ctor(paths, field, map, is_stateful, quote! { data })
+        } else if let Some(configured) = is_configured_collection(field) {
+            Self::rc_configured_ctor(
+                paths,
+                field,
+                configured,
+                is_stateful,
+                watched,
+                quote! { data },
+            )
         } else if is_collection {
-            Self::rc_enum_collection_ctor(field)
+            Self::rc_collection_ctor(paths, field, is_stateful, quote! { data })
+        } else if is_stateful {
+            Self::rc_stateful_ctor(paths, field, quote! { data })
         } else {
-            Self::rc_enum_bare_ctor(field)
+            Self::rc_enum_bare_ctor(paths, field)
         }
     }
 
-    fn rc_struct_stateful_collection_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        let old_ty = is_collection(field).unwrap();
-        let inner_type = if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_rc_ident(&last.ident);
-            quote! {
-                #rc_ident::new(a)
-            }
-        } else {
-            quote!()
-        };
-
-        Expr::Verbatim(quote! {
-            ::sycamore_state_manager::RcCollectionSignal::new(data.#ident.into_iter().map(|a| #inner_type))
-        })
-    }
-    fn rc_struct_stateful_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        let old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_rc_ident(&last.ident);
+    pub(crate) fn rc_stateful_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        data: TokenStream,
+    ) -> Expr {
+        let sycamore = &paths.sycamore;
+        let (wrappers, inner) = unwrap_state_type(field.ref_ty());
+        if let Type::Path(inner) = &inner {
+            let rc_ident = rc_state_ctor(field, inner);
+            let value = wrap_state_ctor(&wrappers, quote! { #rc_ident::new(a) });
             Expr::Verbatim(quote! {
-                ::sycamore::prelude::create_rc_signal(#rc_ident::new(data.#ident))
+                #sycamore::prelude::create_rc_signal({ let a = #data; #value })
             })
         } else {
             Expr::Verbatim(quote!())
         }
     }
-    fn rc_struct_collection_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        Expr::Verbatim(quote! {
-            ::sycamore_state_manager::RcCollectionSignal::new(data.#ident)
-        })
+    pub(crate) fn rc_collection_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        is_stateful: bool,
+        data: TokenStream,
+    ) -> Expr {
+        let sycamore = &paths.sycamore;
+        let shape = collection_shape(field).expect("Type must be a collection");
+        let ctor = Self::rc_collection_levels(paths, field, &shape, 0, is_stateful);
+        if shape.optional {
+            Expr::Verbatim(quote! { #sycamore::prelude::create_rc_signal(#data.map(|a| #ctor)) })
+        } else {
+            Expr::Verbatim(quote! { { let a = #data; #ctor } })
+        }
     }
-    fn rc_struct_bare_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        Expr::Verbatim(quote! { ::sycamore::prelude::create_rc_signal(data.#ident)})
+    /// Rc value of the levels of a collection starting at `from`, built from the plain value `a`
+    ///
+    /// Each level builds its signal from the items `a` of the level above
+    pub(crate) fn rc_collection_levels<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        shape: &CollectionShape,
+        from: usize,
+        is_stateful: bool,
+    ) -> TokenStream {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        // `None` while the items are the plain values themselves
+        let mut item = match &shape.element {
+            Type::Path(inner) if is_stateful => {
+                let rc_ident = rc_state_ctor(field, inner);
+                Some(quote! { #rc_ident::new(a) })
+            }
+            _ => None,
+        };
+        for len in shape.arrays[from..].iter().rev() {
+            let ctor = match (len, item) {
+                (Some(_), Some(item)) => quote! {
+                    #sycamore::reactive::create_rc_signal(a.map(|a| #sycamore::reactive::create_rc_signal(#item)))
+                },
+                (Some(_), None) => quote! {
+                    #sycamore::reactive::create_rc_signal(a.map(#sycamore::reactive::create_rc_signal))
+                },
+                (None, Some(item)) => {
+                    quote! { #state::RcCollectionSignal::new(a.into_iter().map(|a| #item)) }
+                }
+                (None, None) => quote! { #state::RcCollectionSignal::new(a) },
+            };
+            item = Some(ctor);
+        }
+        item.unwrap_or_else(|| quote! { a })
     }
 
-    fn rc_enum_stateful_collection_ctor<F: GetSetType>(field: &F) -> Expr {
-        let old_ty = is_collection(field).unwrap();
-        let inner_type = if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_rc_ident(&last.ident);
-            quote! {
-                #rc_ident::new(a)
+    pub(crate) fn rc_configured_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        configured: ConfiguredCollection,
+        is_stateful: bool,
+        watched: bool,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let old_ty = is_collection(field).expect("Type must be a collection");
+        let values = match &old_ty {
+            Type::Path(inner) if is_stateful => {
+                let rc_ident = rc_state_ctor(field, inner);
+                quote! { #data.into_iter().map(|a| #rc_ident::new(a)) }
             }
+            _ => data,
+        };
+        let ConfiguredCollection { signal, arg } = configured;
+        let watched = if watched && signal == "RcSortedCollectionSignal" {
+            quote! { .watched() }
         } else {
-            quote!()
+            quote! {}
         };
-
         Expr::Verbatim(quote! {
-            ::sycamore_state_manager::RcCollectionSignal::new(data.into_iter().map(|a| #inner_type))
+            #state::#signal::new(#arg, #values)#watched
         })
     }
-    fn rc_enum_stateful_ctor<F: GetSetType>(field: &F) -> Expr {
-        let old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_rc_ident(&last.ident);
-            Expr::Verbatim(quote! {
-                ::sycamore::prelude::create_rc_signal(#rc_ident::new(data))
-            })
-        } else {
-            Expr::Verbatim(quote!())
-        }
+    fn rc_custom_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        with: Path,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let old_ty = field.ref_ty();
+        Expr::Verbatim(quote! {
+            <#with as #state::StateField<#old_ty>>::new_rc(#data)
+        })
     }
-    fn rc_enum_collection_ctor<F: GetSetType>(_field: &F) -> Expr {
+    fn rc_set_ctor(paths: &CratePaths, set: SetCollection, data: TokenStream) -> Expr {
+        let state = &paths.state;
+        let signal = set.signal;
         Expr::Verbatim(quote! {
-            ::sycamore_state_manager::RcCollectionSignal::new(data)
+            #state::#signal::new(#data)
         })
     }
-    fn rc_enum_bare_ctor<F: GetSetType>(_field: &F) -> Expr {
-        Expr::Verbatim(quote! { ::sycamore::prelude::create_rc_signal(data)})
+    fn rc_map_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        map: MapCollection,
+        is_stateful: bool,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let signal = map.signal;
+        let entries = match &map.value {
+            Type::Path(value) if is_stateful => {
+                let rc_ident = rc_state_ctor(field, value);
+                quote! { #data.into_iter().map(|(k, v)| (k, #rc_ident::new(v))) }
+            }
+            _ => data,
+        };
+        Expr::Verbatim(quote! {
+            #state::#signal::new(#entries)
+        })
+    }
+    fn rc_struct_bare_ctor<F: GetSetType>(paths: &CratePaths, field: &F) -> Expr {
+        let sycamore = &paths.sycamore;
+        let ident = field.ident();
+        Expr::Verbatim(quote! { #sycamore::prelude::create_rc_signal(data.#ident)})
+    }
+
+    fn rc_enum_bare_ctor<F: GetSetType>(paths: &CratePaths, _field: &F) -> Expr {
+        let sycamore = &paths.sycamore;
+        Expr::Verbatim(quote! { #sycamore::prelude::create_rc_signal(data)})
     }
 }
@@ -1,205 +1,382 @@
-use crate::models::{insert_lifetime_into_generics, FieldWrapper, GetSetType};
-use crate::utils::{format_ref_ident, is_collection, s_lifetime};
+use crate::models::{CratePaths, FieldWrapper, GetSetType};
+use crate::utils::{
+    collection_shape, is_collection, is_configured_collection, is_custom, is_map, is_set,
+    ref_state_ctor, ref_state_type, unwrap_state_type, wrap_state_ctor, wrap_state_type,
+    CollectionShape, ConfiguredCollection, MapCollection, SetCollection,
+};
+use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::*;
 
 impl FieldWrapper {
-    pub fn to_ref_decl(&self) -> Box<dyn ToTokens> {
+    pub fn to_ref_decl(&self, paths: &CratePaths) -> Box<dyn ToTokens> {
         let is_stateful = self.is_stateful();
         let is_collection = self.is_collection();
 
         match &self {
             FieldWrapper::Enum(data) => {
-                let mut field = Self::inner_to_ref_decl(data, is_stateful, is_collection);
+                let mut field = Self::inner_to_ref_decl(paths, data, is_stateful, is_collection);
                 field.attrs.clear();
                 Box::new(field)
             }
             FieldWrapper::Struct(data) => {
-                let mut variant = Self::inner_to_ref_decl(data, is_stateful, is_collection);
+                let mut variant = Self::inner_to_ref_decl(paths, data, is_stateful, is_collection);
                 variant.attrs.clear();
                 Box::new(variant)
             }
         }
     }
-    fn inner_to_ref_decl<F: GetSetType>(field: &F, is_stateful: bool, is_collection: bool) -> F {
-        if is_stateful && is_collection {
-            Self::to_ref_stateful_collection_decl(field)
-        } else if is_stateful {
-            Self::to_ref_stateful_decl(field)
+    fn inner_to_ref_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        is_stateful: bool,
+        is_collection: bool,
+    ) -> F {
+        if let Some(with) = is_custom(field) {
+            Self::to_ref_custom_decl(paths, field, with)
+        } else if let Some(set) = is_set(field) {
+            Self::to_ref_set_decl(paths, field, set)
+        } else if let Some(map) = is_map(field) {
+            Self::to_ref_map_decl(paths, field, map, is_stateful)
+        } else if let Some(configured) = is_configured_collection(field) {
+            Self::to_ref_configured_decl(paths, field, configured.signal, is_stateful)
         } else if is_collection {
-            Self::to_ref_collection_decl(field)
+            Self::to_ref_collection_decl(paths, field, is_stateful)
+        } else if is_stateful {
+            Self::to_ref_stateful_decl(paths, field)
         } else {
-            Self::to_ref_bare_decl(field)
+            Self::to_ref_bare_decl(paths, field)
         }
     }
-    fn to_ref_stateful_collection_decl<F: GetSetType>(field: &F) -> F {
-        let mut inner_type = is_collection(field).expect("Type must be a collection");
+    fn to_ref_collection_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        is_stateful: bool,
+    ) -> F {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let CollectionShape {
+            optional,
+            mut element,
+            arrays,
+            ..
+        } = collection_shape(field).expect("Type must be a collection");
         let mut new_field = field.clone();
 
-        if let Type::Path(data) = &mut inner_type {
-            let mut last = data.path.segments.last_mut().expect("failed to parse type");
-            insert_lifetime_into_generics(last, s_lifetime());
-            last.ident = format_ref_ident(&last.ident);
+        if is_stateful {
+            element = ref_state_type(field, &element);
+        }
+        let mut ty = quote! { #element };
+        for len in arrays.iter().rev() {
+            ty = match len {
+                Some(len) => quote! {
+                    &'stateful #sycamore::prelude::Signal<[&'stateful #sycamore::prelude::Signal<#ty>; #len]>
+                },
+                None => quote! { #state::RefCollectionSignal<'stateful, #ty> },
+            };
+        }
+        if optional {
+            ty = quote! { &'stateful #sycamore::prelude::Signal<Option<#ty>> };
         }
-        *new_field.mut_ty() = Type::Verbatim(
-            quote! { ::sycamore_state_manager::RefCollectionSignal<'stateful, #inner_type>},
-        );
+        *new_field.mut_ty() = Type::Verbatim(ty);
 
         new_field
     }
-    fn to_ref_stateful_decl<F: GetSetType>(field: &F) -> F {
+    fn to_ref_stateful_decl<F: GetSetType>(paths: &CratePaths, field: &F) -> F {
+        let sycamore = &paths.sycamore;
         let mut new_field = field.clone();
-        let mut old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &mut old_ty {
-            let mut last = data.path.segments.last_mut().expect("failed to parse type");
-            last.ident = format_ref_ident(&last.ident);
-            insert_lifetime_into_generics(last, s_lifetime());
+        let (wrappers, inner) = unwrap_state_type(field.ref_ty());
+        let inner = ref_state_type(field, &inner);
+        let ty = wrap_state_type(&wrappers, quote! { #inner });
+        *new_field.mut_ty() = Type::Verbatim(quote! {&'stateful #sycamore::prelude::Signal<#ty>});
+        new_field
+    }
+    fn to_ref_map_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        map: MapCollection,
+        is_stateful: bool,
+    ) -> F {
+        let state = &paths.state;
+        let MapCollection {
+            signal,
+            key,
+            mut value,
+        } = map;
+        let mut new_field = field.clone();
+
+        if is_stateful {
+            value = ref_state_type(field, &value);
         }
         *new_field.mut_ty() =
-            Type::Verbatim(quote! {&'stateful ::sycamore::prelude::Signal<#old_ty>});
+            Type::Verbatim(quote! { &'stateful #state::#signal<'stateful, #key, #value>});
+
         new_field
     }
-    fn to_ref_collection_decl<F: GetSetType>(field: &F) -> F {
-        let inner_type = is_collection(field).expect("Type must be a collection");
+    fn to_ref_configured_decl<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        signal: Ident,
+        is_stateful: bool,
+    ) -> F {
+        let state = &paths.state;
+        let mut inner_type = is_collection(field).expect("Type must be a collection");
         let mut new_field = field.clone();
 
-        *new_field.mut_ty() = Type::Verbatim(
-            quote! { ::sycamore_state_manager::RefCollectionSignal<'stateful, #inner_type>},
-        );
+        if is_stateful {
+            inner_type = ref_state_type(field, &inner_type);
+        }
+        *new_field.mut_ty() = Type::Verbatim(quote! { &'stateful #state::#signal<#inner_type>});
 
         new_field
     }
-    fn to_ref_bare_decl<F: GetSetType>(field: &F) -> F {
+    fn to_ref_custom_decl<F: GetSetType>(paths: &CratePaths, field: &F, with: Path) -> F {
+        let state = &paths.state;
+        let old_ty = field.ref_ty();
+        let mut new_field = field.clone();
+        *new_field.mut_ty() =
+            Type::Verbatim(quote! { <#with as #state::StateField<#old_ty>>::Ref<'stateful> });
+        new_field
+    }
+    fn to_ref_set_decl<F: GetSetType>(paths: &CratePaths, field: &F, set: SetCollection) -> F {
+        let state = &paths.state;
+        let SetCollection { signal, value } = set;
+        let mut new_field = field.clone();
+        *new_field.mut_ty() = Type::Verbatim(quote! { &'stateful #state::#signal<#value>});
+        new_field
+    }
+    fn to_ref_bare_decl<F: GetSetType>(paths: &CratePaths, field: &F) -> F {
+        let sycamore = &paths.sycamore;
         let old_ty = &field.ref_ty();
         let mut new_field = field.clone();
         *new_field.mut_ty() =
-            Type::Verbatim(quote! { &'stateful ::sycamore::prelude::Signal<#old_ty>});
+            Type::Verbatim(quote! { &'stateful #sycamore::prelude::Signal<#old_ty>});
         new_field
     }
 }
 
 impl FieldWrapper {
-    pub fn to_ref_ctor(&self) -> Box<dyn ToTokens> {
+    pub fn to_ref_ctor(&self, paths: &CratePaths) -> Box<dyn ToTokens> {
         let is_stateful = self.is_stateful();
         let is_collection = self.is_collection();
         match &self {
             FieldWrapper::Enum(data) => {
-                let res = Self::inner_to_enum_ref_ctor(data, is_stateful, is_collection);
+                let res = Self::inner_to_enum_ref_ctor(paths, data, is_stateful, is_collection);
                 Box::new(res)
             }
             FieldWrapper::Struct(data) => {
-                let res = Self::inner_to_struct_ref_ctor(data, is_stateful, is_collection);
+                let res = Self::inner_to_struct_ref_ctor(paths, data, is_stateful, is_collection);
                 Box::new(res)
             }
         }
     }
     fn inner_to_struct_ref_ctor<F: GetSetType>(
+        paths: &CratePaths,
         field: &F,
         is_stateful: bool,
         is_collection: bool,
     ) -> Expr {
-        if is_stateful && is_collection {
-            Self::to_ref_struct_stateful_collection_ctor(field)
-        } else if is_stateful {
-            Self::to_ref_struct_stateful_ctor(field)
+        if let Some(with) = is_custom(field) {
+            let ident = field.ident();
+            Self::to_ref_custom_ctor(paths, field, with, quote! { data.#ident })
+        } else if let Some(set) = is_set(field) {
+            let ident = field.ident();
+            Self::to_ref_set_ctor(paths, set, quote! { data.#ident })
+        } else if let Some(map) = is_map(field) {
+            let ident = field.ident();
+            Self::to_ref_map_ctor(paths, field, map, is_stateful, quote! { data.#ident })
+        } else if let Some(configured) = is_configured_collection(field) {
+            let ident = field.ident();
+            Self::to_ref_configured_ctor(
+                paths,
+                field,
+                configured,
+                is_stateful,
+                quote! { data.#ident },
+            )
         } else if is_collection {
-            Self::to_ref_struct_collection_ctor(field)
+            let ident = field.ident();
+            Self::to_ref_collection_ctor(paths, field, is_stateful, quote! { data.#ident })
+        } else if is_stateful {
+            let ident = field.ident();
+            Self::to_ref_stateful_ctor(paths, field, quote! { data.#ident })
         } else {
-            Self::to_ref_struct_bare_ctor(field)
+            Self::to_ref_struct_bare_ctor(paths, field)
         }
     }
-    fn to_ref_struct_stateful_collection_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        let old_ty = is_collection(field).expect("failed to parse collection");
-        let inner_type = if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_ref_ident(&last.ident);
-            quote! {
-                #rc_ident::new(cx, a)
-            }
-        } else {
-            quote!()
-        };
-
-        Expr::Verbatim(quote! {
-            unsafe{ ::sycamore_state_manager::RefCollectionSignal::new(cx, data.#ident.into_iter().map(|a| #inner_type).collect::<Vec<_>>())}
-        })
-    }
-    fn to_ref_struct_stateful_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        let old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_ref_ident(&last.ident);
+    fn to_ref_stateful_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        data: TokenStream,
+    ) -> Expr {
+        let sycamore = &paths.sycamore;
+        let (wrappers, inner) = unwrap_state_type(field.ref_ty());
+        if let Type::Path(inner) = &inner {
+            let ref_ident = ref_state_ctor(field, inner);
+            let value = wrap_state_ctor(&wrappers, quote! { #ref_ident::new(cx, a) });
             Expr::Verbatim(quote! {
-                unsafe{::sycamore::reactive::create_signal_unsafe(cx, #rc_ident::new(cx, data.#ident))}
+                unsafe{#sycamore::reactive::create_signal_unsafe(cx, { let a = #data; #value })}
             })
         } else {
             Expr::Verbatim(quote!())
         }
     }
-    fn to_ref_struct_collection_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        Expr::Verbatim(quote! {
-            unsafe{ ::sycamore_state_manager::RefCollectionSignal::new(cx, data.#ident)}
-        })
-    }
-    fn to_ref_struct_bare_ctor<F: GetSetType>(field: &F) -> Expr {
-        let ident = field.ident();
-        Expr::Verbatim(
-            quote! { unsafe{::sycamore::reactive::create_signal_unsafe(cx, data.#ident)}},
-        )
-    }
-
-    fn inner_to_enum_ref_ctor<F: GetSetType>(
+    pub(crate) fn to_ref_collection_ctor<F: GetSetType>(
+        paths: &CratePaths,
         field: &F,
         is_stateful: bool,
-        is_collection: bool,
+        data: TokenStream,
     ) -> Expr {
-        if is_stateful && is_collection {
-            Self::to_ref_enum_stateful_collection_ctor(field)
-        } else if is_stateful {
-            Self::to_ref_enum_stateful_ctor(field)
-        } else if is_collection {
-            Self::to_ref_enum_collection_ctor(field)
+        let sycamore = &paths.sycamore;
+        let shape = collection_shape(field).expect("failed to parse collection");
+        let ctor = Self::ref_collection_levels(paths, field, &shape, 0, is_stateful);
+        if shape.optional {
+            Expr::Verbatim(quote! {
+                { let a = #data.map(|a| #ctor); unsafe{ #sycamore::reactive::create_signal_unsafe(cx, a) } }
+            })
         } else {
-            Self::to_ref_enum_bare_ctor(field)
+            Expr::Verbatim(quote! { { let a = #data; #ctor } })
+        }
+    }
+    /// Ref value of the levels of a collection starting at `from`, built from the plain value `a`
+    ///
+    /// Each level builds its signal from the items `a` of the level above
+    pub(crate) fn ref_collection_levels<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        shape: &CollectionShape,
+        from: usize,
+        is_stateful: bool,
+    ) -> TokenStream {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        // `None` while the items are the plain values themselves
+        let mut item = match &shape.element {
+            Type::Path(inner) if is_stateful => {
+                let ref_ident = ref_state_ctor(field, inner);
+                Some(quote! { #ref_ident::new(cx, a) })
+            }
+            _ => None,
+        };
+        for len in shape.arrays[from..].iter().rev() {
+            let signals = quote! {
+                unsafe { #sycamore::reactive::create_signal_unsafe(cx, a.map(|a| #sycamore::reactive::create_signal_unsafe(cx, a))) }
+            };
+            let ctor = match (len, item) {
+                (Some(_), Some(item)) => quote! { { let a = a.map(|a| #item); #signals } },
+                (Some(_), None) => signals,
+                (None, Some(item)) => {
+                    quote! { #state::RefCollectionSignal::new(cx, a.into_iter().map(|a| #item)) }
+                }
+                (None, None) => quote! { #state::RefCollectionSignal::new(cx, a) },
+            };
+            item = Some(ctor);
         }
+        item.unwrap_or_else(|| quote! { a })
     }
-    fn to_ref_enum_stateful_collection_ctor<F: GetSetType>(field: &F) -> Expr {
+    pub(crate) fn to_ref_configured_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        configured: ConfiguredCollection,
+        is_stateful: bool,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
         let old_ty = is_collection(field).expect("failed to parse collection");
-        let inner_type = if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_ref_ident(&last.ident);
-            quote! {
-                #rc_ident::new(cx, a)
+        let values = match &old_ty {
+            Type::Path(inner) if is_stateful => {
+                let ref_ident = ref_state_ctor(field, inner);
+                quote! { #data.into_iter().map(|a| #ref_ident::new(cx, a)) }
             }
+            _ => data,
+        };
+        let ConfiguredCollection { signal, arg } = configured;
+        // Sorted collections of states get moved as the fields of the states change
+        let watch = if is_stateful && signal == "RcSortedCollectionSignal" {
+            quote! { signal.watch(cx); }
         } else {
-            quote!()
+            quote! {}
         };
-
         Expr::Verbatim(quote! {
-            unsafe{ ::sycamore_state_manager::RefCollectionSignal::new(cx, data.into_iter().map(|a| #inner_type).collect::<Vec<_>>())}
+            {
+                let signal = #state::#signal::new(#arg, #values);
+                #watch
+                unsafe{ #sycamore::reactive::create_ref_unsafe(cx, signal)}
+            }
         })
     }
-    fn to_ref_enum_stateful_ctor<F: GetSetType>(field: &F) -> Expr {
-        let old_ty = field.ref_ty().clone();
-        if let Type::Path(data) = &old_ty {
-            let last = data.path.segments.last().expect("failed to parse type");
-            let rc_ident = format_ref_ident(&last.ident);
-            Expr::Verbatim(quote! {
-                unsafe{::sycamore::reactive::create_signal_unsafe(cx, #rc_ident::new(cx, data))}
-            })
-        } else {
-            Expr::Verbatim(quote!())
-        }
+    fn to_ref_custom_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        with: Path,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let old_ty = field.ref_ty();
+        Expr::Verbatim(quote! {
+            <#with as #state::StateField<#old_ty>>::new_ref(cx, #data)
+        })
+    }
+    fn to_ref_set_ctor(paths: &CratePaths, set: SetCollection, data: TokenStream) -> Expr {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let signal = set.signal;
+        Expr::Verbatim(quote! {
+            unsafe{ #sycamore::reactive::create_ref_unsafe(cx, #state::#signal::new(#data))}
+        })
     }
-    fn to_ref_enum_collection_ctor<F: GetSetType>(_field: &F) -> Expr {
+    fn to_ref_map_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        map: MapCollection,
+        is_stateful: bool,
+        data: TokenStream,
+    ) -> Expr {
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        let signal = map.signal;
+        let entries = match &map.value {
+            Type::Path(value) if is_stateful => {
+                let ref_ident = ref_state_ctor(field, value);
+                quote! { #data.into_iter().map(|(k, v)| (k, #ref_ident::new(cx, v))) }
+            }
+            _ => data,
+        };
         Expr::Verbatim(quote! {
-            unsafe{ ::sycamore_state_manager::RefCollectionSignal::new(cx, data)}
+            unsafe{ #sycamore::reactive::create_ref_unsafe(cx, #state::#signal::new(#entries))}
         })
     }
-    fn to_ref_enum_bare_ctor<F: GetSetType>(_field: &F) -> Expr {
-        Expr::Verbatim(quote! { unsafe{::sycamore::reactive::create_signal_unsafe(cx, data)}})
+    fn to_ref_struct_bare_ctor<F: GetSetType>(paths: &CratePaths, field: &F) -> Expr {
+        let sycamore = &paths.sycamore;
+        let ident = field.ident();
+        Expr::Verbatim(quote! { unsafe{#sycamore::reactive::create_signal_unsafe(cx, data.#ident)}})
+    }
+
+    fn inner_to_enum_ref_ctor<F: GetSetType>(
+        paths: &CratePaths,
+        field: &F,
+        is_stateful: bool,
+        is_collection: bool,
+    ) -> Expr {
+        if let Some(with) = is_custom(field) {
+            Self::to_ref_custom_ctor(paths, field, with, quote! { data })
+        } else if let Some(set) = is_set(field) {
+            Self::to_ref_set_ctor(paths, set, quote! { data })
+        } else if let Some(map) = is_map(field) {
+            Self::to_ref_map_ctor(paths, field, map, is_stateful, quote! { data })
+        } else if let Some(configured) = is_configured_collection(field) {
+            Self::to_ref_configured_ctor(paths, field, configured, is_stateful, quote! { data })
+        } else if is_collection {
+            Self::to_ref_collection_ctor(paths, field, is_stateful, quote! { data })
+        } else if is_stateful {
+            Self::to_ref_stateful_ctor(paths, field, quote! { data })
+        } else {
+            Self::to_ref_enum_bare_ctor(paths, field)
+        }
+    }
+    fn to_ref_enum_bare_ctor<F: GetSetType>(paths: &CratePaths, _field: &F) -> Expr {
+        let sycamore = &paths.sycamore;
+        Expr::Verbatim(quote! { unsafe{#sycamore::reactive::create_signal_unsafe(cx, data)}})
     }
 }
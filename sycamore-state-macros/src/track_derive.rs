@@ -0,0 +1,237 @@
+use crate::convert_derive::Repr;
+use crate::models::{CratePaths, Deriver, FieldWrapper, GenericContainer, GetSetType};
+use crate::utils::{
+    collection_shape, format_rc_ident, format_ref_ident, is_configured_collection, is_custom,
+    is_map, is_set, unwrap_state_type, StateWrapper,
+};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::token::{Enum, Struct};
+
+impl FieldWrapper {
+    /// Statements subscribing to every signal of the field at `access`
+    fn track_value(&self, paths: &CratePaths, access: TokenStream, repr: Repr) -> TokenStream {
+        let state = &paths.state;
+        // Tracks a nested state held by an item `a`, plain items are tracked by `items`
+        let item = self.is_stateful().then(|| quote! { a.track(); });
+        let items = |collection: TokenStream, item: Option<TokenStream>| match item {
+            Some(item) => quote! {
+                for a in #collection.get().iter() { let a = a.get(); #item }
+            },
+            None => quote! {
+                for a in #collection.get().iter() { a.track(); }
+            },
+        };
+
+        if let Some(with) = is_custom(self) {
+            let ty = self.ref_ty();
+            let track = match repr {
+                Repr::Rc => quote! { track_rc },
+                Repr::Ref => quote! { track_ref },
+            };
+            quote! { <#with as #state::StateField<#ty>>::#track(&#access); }
+        } else if is_set(self).is_some() {
+            quote! { #access.track(); }
+        } else if is_map(self).is_some() {
+            match item {
+                Some(item) => quote! {
+                    for (_, a) in ::core::ops::Deref::deref(&*#access).get().iter() { let a = a.get(); #item }
+                },
+                None => quote! {
+                    for (_, a) in ::core::ops::Deref::deref(&*#access).get().iter() { a.track(); }
+                },
+            }
+        } else if is_configured_collection(self).is_some() {
+            items(access, item)
+        } else if let Some(shape) = collection_shape(self) {
+            // Each level tracks the items `a` of the level above
+            let mut level = item;
+            for _ in 1..shape.depth {
+                level = Some(items(quote! { a }, level));
+            }
+            if shape.optional {
+                let level = items(quote! { a }, level);
+                quote! {
+                    if let ::core::option::Option::Some(a) = ::core::option::Option::as_ref(&*#access.get()) { #level }
+                }
+            } else {
+                items(access, level)
+            }
+        } else if let Some(item) = item {
+            let (wrappers, _) = unwrap_state_type(self.ref_ty());
+            let value = wrappers
+                .iter()
+                .rev()
+                .fold(item, |value, wrapper| match wrapper {
+                    StateWrapper::Option => quote! {
+                        if let ::core::option::Option::Some(a) = a.as_ref() { #value }
+                    },
+                    StateWrapper::Box | StateWrapper::Rc => value,
+                });
+            quote! { { let a = #access.get(); let a = &*a; #value } }
+        } else {
+            quote! { #access.track(); }
+        }
+    }
+}
+
+impl<T> Deriver<T> {
+    /// `setters` are the batch aware field setters, shared by the Rc and Ref types
+    fn derive_tracking_impls(
+        &self,
+        rc_body: TokenStream,
+        ref_body: TokenStream,
+        setters: TokenStream,
+    ) -> TokenStream {
+        let rc_ident = format_rc_ident(&self.ident);
+        let ref_ident = format_ref_ident(&self.ident);
+        let rc_generics = self.make_rc_generic();
+        let ref_generics = self.make_ref_generic();
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let sycamore = &paths.sycamore;
+        // Ref types are always `Copy`, Rc types only `Clone` when asked to derive it
+        let on_any_change = |holds: &str, bound: TokenStream, copy: TokenStream| {
+            quote! {
+                /// Call `f` in `cx` every time a signal of the state changes,
+                /// `f` itself runs untracked and at most once per `batch`
+                ///
+                #[doc = #holds]
+                pub fn on_any_change<'track>(
+                    &self,
+                    cx: #sycamore::reactive::Scope<'track>,
+                    mut f: impl FnMut() + 'track,
+                ) where
+                    Self: #bound,
+                    Self: 'track,
+                {
+                    let notifier = #state::BatchNotifier::new();
+                    let mut first = true;
+                    #sycamore::reactive::create_effect(cx, {
+                        let notifier = ::core::clone::Clone::clone(&notifier);
+                        move || {
+                            notifier.track();
+                            if !::core::mem::take(&mut first) {
+                                #sycamore::reactive::untrack(&mut f);
+                            }
+                        }
+                    });
+                    let state = #copy;
+                    let mut first = true;
+                    #sycamore::reactive::create_effect(cx, move || {
+                        state.track();
+                        if !::core::mem::take(&mut first) {
+                            notifier.notify();
+                        }
+                    });
+                }
+            }
+        };
+        let rc_on_any_change = self.features.rc_clone().then(|| {
+            on_any_change(
+                " The effect holds a clone of the state, which shares its signals",
+                quote! { ::core::clone::Clone },
+                quote! { ::core::clone::Clone::clone(self) },
+            )
+        });
+        let ref_on_any_change = on_any_change(
+            " The effect holds a copy of the state",
+            quote! { ::core::marker::Copy },
+            quote! { *self },
+        );
+        let batch = quote! {
+            /// Run `f` with the state inside a `batch`
+            ///
+            /// Writes made with the `set_*` setters of the state, through its collection, map,
+            /// set, ring and sorted signals or with `BatchSignal` are deferred, the subscribers
+            /// of every written signal then run once when it ends, as do `on_change` and
+            /// `on_any_change` callbacks. `Signal::set` on a field signal is not batch aware
+            /// and notifies right away
+            pub fn batch<R>(&self, f: impl FnOnce(&Self) -> R) -> R {
+                #state::batch(|| f(self))
+            }
+        };
+        quote! {
+            impl #rc_generics #rc_ident #rc_generics {
+                /// Subscribe the current reactive scope to every signal of the state,
+                /// including nested states and collection items
+                pub fn track(&self) {
+                    #rc_body
+                }
+
+                #rc_on_any_change
+
+                #batch
+
+                #setters
+            }
+
+            impl #ref_generics #ref_ident #ref_generics {
+                /// Subscribe the current reactive scope to every signal of the state,
+                /// including nested states and collection items
+                pub fn track(&self) {
+                    #ref_body
+                }
+
+                #ref_on_any_change
+
+                #batch
+
+                #setters
+            }
+        }
+    }
+}
+
+impl Deriver<Struct> {
+    pub fn derive_tracking(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let state = &paths.state;
+        let body = |repr: Repr| {
+            let fields = self.fields.iter().map(|a| {
+                let ident = a.ident();
+                a.track_value(&paths, quote! { self.#ident }, repr)
+            });
+            quote! { #(#fields)* }
+        };
+        // Plain value fields, the others are written through their own signals and states
+        let setters = self.fields.iter().filter(|a| a.is_bare()).map(|a| {
+            let ident = a.ident();
+            let ty = a.ref_ty();
+            let name = ident.unraw();
+            let setter = format_ident!("set_{}", name);
+            let doc = format!(
+                " Set `{}`, notifying its subscribers at the end of the running `batch`",
+                name
+            );
+            quote! {
+                #[doc = #doc]
+                pub fn #setter(&self, value: #ty) {
+                    #state::BatchSignal::set_batched(&*self.#ident, value);
+                }
+            }
+        });
+        let setters = quote! { #(#setters)* };
+        self.derive_tracking_impls(body(Repr::Rc), body(Repr::Ref), setters)
+    }
+}
+
+impl Deriver<Enum> {
+    pub fn derive_tracking(&self) -> TokenStream {
+        let paths = self.features.crate_paths();
+        let idents = self.fields.iter().map(|a| a.ident()).collect::<Vec<_>>();
+        let body = |repr: Repr| {
+            let variants = self
+                .fields
+                .iter()
+                .map(|a| a.track_value(&paths, quote! { (*data) }, repr));
+            quote! {
+                match self {
+                    #(Self::#idents(data) => { #variants }),*
+                }
+            }
+        };
+        self.derive_tracking_impls(body(Repr::Rc), body(Repr::Ref), TokenStream::new())
+    }
+}
@@ -1,11 +1,13 @@
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{format_ident, quote};
 use syn::{
-    punctuated::Punctuated, token::Comma, Attribute, GenericParam, Generics, PathArguments, Type,
-    Variant,
+    ext::IdentExt, parse_quote, punctuated::Punctuated, token::Comma, Attribute, Expr, ExprLit,
+    GenericParam, Generics, Lit, LitInt, Path, PathArguments, Token, Type, TypePath, Variant,
 };
 
-use crate::models::{FeatureModel, GetSetType};
+use crate::models::{
+    insert_lifetime_into_generics, CollectionOptions, FeatureModel, GetSetType, StateOptions,
+};
 
 pub fn extract_features_from_attrs(
     attrs: Vec<Attribute>,
@@ -22,6 +24,38 @@ pub fn extract_features_from_attrs(
             continue;
         }
         let tkns = attr.tokens.clone();
+        let metas = attr
+            .parse_args_with(Punctuated::<syn::Meta, Comma>::parse_terminated)
+            .expect("failed to parse #[state] options");
+        for meta in metas {
+            if let syn::Meta::NameValue(meta) = meta {
+                let key = meta.path.get_ident().map(ToString::to_string);
+                match (&meta.value, key.as_deref()) {
+                    (Expr::Path(remote), Some("remote")) => {
+                        features.remote = Some(remote.path.clone())
+                    }
+                    (Expr::Lit(lit), Some("crate")) => features.krate = Some(parse_lit_path(lit)),
+                    (Expr::Lit(lit), Some("sycamore")) => {
+                        features.sycamore = Some(parse_lit_path(lit))
+                    }
+                    _ => panic!("unsupported state option"),
+                }
+            } else if let syn::Meta::List(list) = meta {
+                let key = list.path.get_ident().map(ToString::to_string);
+                let derives = list
+                    .parse_args_with(Punctuated::<Path, Comma>::parse_terminated)
+                    .expect("failed to parse derive list");
+                match key.as_deref() {
+                    Some("derive") => {
+                        features.rc_derives.extend(derives.iter().cloned());
+                        features.ref_derives.extend(derives);
+                    }
+                    Some("rc_derive") => features.rc_derives.extend(derives),
+                    Some("ref_derive") => features.ref_derives.extend(derives),
+                    _ => panic!("unsupported state option"),
+                }
+            }
+        }
 
         for tkn in tkns.into_iter() {
             match tkn {
@@ -38,6 +72,14 @@ pub fn extract_features_from_attrs(
     }
 }
 
+/// Parses a path given as a string, as in `#[state(crate = "my_facade::state")]`
+fn parse_lit_path(lit: &ExprLit) -> Path {
+    match &lit.lit {
+        Lit::Str(path) => path.parse().expect("expected a path"),
+        _ => panic!("expected a string literal"),
+    }
+}
+
 pub fn extract_attribute_from_field(attrs: &Vec<Attribute>, ident: Ident) -> Option<Ident> {
     for attr in attrs.iter() {
         match &attr.meta {
@@ -49,18 +91,6 @@ pub fn extract_attribute_from_field(attrs: &Vec<Attribute>, ident: Ident) -> Opt
                 }
             }
             syn::Meta::List(data) => {
-                if data
-                    .tokens
-                    .clone()
-                    .into_iter()
-                    .find(|val| match val {
-                        proc_macro2::TokenTree::Ident(ident) => ident.to_string() == "inner_state",
-                        _ => false,
-                    })
-                    .is_none()
-                {
-                    continue;
-                }
                 for seg in data.path.segments.iter() {
                     if seg.ident.to_string() == ident.to_string() {
                         return Some(seg.ident.clone());
@@ -108,6 +138,33 @@ pub fn format_rc_ident(ident: &Ident) -> Ident {
     format_ident!("Rc{}", ident)
 }
 
+/// Name of the generated path type of a state
+pub(crate) fn format_path_ident(ident: &Ident) -> Ident {
+    format_ident!("{}Field", ident)
+}
+
+/// Name of the generated patch type of a state
+pub(crate) fn format_patch_ident(ident: &Ident) -> Ident {
+    format_ident!("{}Patch", ident)
+}
+
+/// Variant of a struct field in the generated path type, `field_3` becomes `Field3`
+pub(crate) fn format_path_variant(ident: &Ident) -> Ident {
+    let name = ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+    format_ident!("{}", name)
+}
+
 #[allow(dead_code)]
 pub fn prepend_type(label: &str, mut ty: Type) -> Type {
     let tmp = match &mut ty {
@@ -145,58 +202,72 @@ pub fn extract_lifetimes(generics: &Generics) -> Punctuated<syn::LifetimeParam,
         .collect::<Punctuated<_, Comma>>()
 }
 
+/// `bound` on every type parameter of the original type
+pub(crate) fn type_param_bounds(generics: &Generics, bound: TokenStream) -> TokenStream {
+    let params = generics.params.iter().filter_map(|a| match a {
+        GenericParam::Type(param) => Some(&param.ident),
+        _ => None,
+    });
+    quote! { #(#params: #bound),* }
+}
+
 pub fn make_derive_features(features: &FeatureModel) -> TokenStream {
-    let mut macros = Punctuated::<Ident, Comma>::new();
+    let mut macros = Punctuated::<Path, Comma>::new();
     if features.clone {
-        macros.push(format_ident!("Clone"));
-    }
-    if features.eq {
-        macros.push(format_ident!("PartialEq"));
-        macros.push(format_ident!("Eq"));
-    }
-    if features.debug {
-        macros.push(format_ident!("Debug"))
+        macros.push(format_ident!("Clone").into());
     }
-    if features.ord {
-        macros.push(format_ident!("PartialOrd"));
-        macros.push(format_ident!("Ord"));
-    }
-
-    let token_stream = if macros.len() != 0 {
-        quote! {
-            #[derive(#macros)]
-        }
-    } else {
-        quote! {}
-    };
-    token_stream
+    push_common_derives(features, &mut macros);
+    // `Default` is implemented by hand from the `Default` of the original type
+    let extra = features
+        .rc_derives
+        .iter()
+        .filter(|a| !a.is_ident("Default"));
+    push_derives(&mut macros, extra);
+    derive_attribute(macros)
 }
 
 pub fn make_ref_derive_features(features: &FeatureModel) -> TokenStream {
-    let mut macros = Punctuated::<Ident, Comma>::new();
-    macros.push(format_ident!("Copy"));
-    macros.push(format_ident!("Clone"));
+    let mut macros = Punctuated::<Path, Comma>::new();
+    macros.push(format_ident!("Copy").into());
+    macros.push(format_ident!("Clone").into());
+    push_common_derives(features, &mut macros);
+    // Ref types are created in a scope, `Default` becomes a `default_in` constructor
+    let extra = features
+        .ref_derives
+        .iter()
+        .filter(|a| !a.is_ident("Default"));
+    push_derives(&mut macros, extra);
+    derive_attribute(macros)
+}
 
+fn push_common_derives(features: &FeatureModel, macros: &mut Punctuated<Path, Comma>) {
     if features.eq {
-        macros.push(format_ident!("PartialEq"));
-        macros.push(format_ident!("Eq"));
-    }
-    if features.debug {
-        macros.push(format_ident!("Debug"))
+        macros.push(format_ident!("PartialEq").into());
+        macros.push(format_ident!("Eq").into());
     }
     if features.ord {
-        macros.push(format_ident!("PartialOrd"));
-        macros.push(format_ident!("Ord"));
+        macros.push(format_ident!("PartialOrd").into());
+        macros.push(format_ident!("Ord").into());
     }
+}
 
-    let token_stream = if macros.len() != 0 {
+/// Adds the derives of a `#[state(derive(..))]` list, skipping the ones already present
+fn push_derives<'a>(macros: &mut Punctuated<Path, Comma>, derives: impl Iterator<Item = &'a Path>) {
+    for derive in derives {
+        if !macros.iter().any(|a| a == derive) {
+            macros.push(derive.clone());
+        }
+    }
+}
+
+fn derive_attribute(macros: Punctuated<Path, Comma>) -> TokenStream {
+    if macros.is_empty() {
+        quote! {}
+    } else {
         quote! {
             #[derive(#macros)]
         }
-    } else {
-        quote! {}
-    };
-    token_stream
+    }
 }
 
 pub(crate) fn is_collection<F: GetSetType>(field: &F) -> Option<Type> {
@@ -228,6 +299,390 @@ pub(crate) fn is_collection<F: GetSetType>(field: &F) -> Option<Type> {
     }
 }
 
+pub(crate) fn extract_state_options(attrs: &[Attribute]) -> StateOptions {
+    let mut options = StateOptions::default();
+    for attr in attrs.iter() {
+        if !attr.path().is_ident(&stateful_ident()) {
+            continue;
+        }
+        if let syn::Meta::List(_) = attr.meta {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rc") {
+                    options.rc = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("ref") {
+                    options.r#ref = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    options.with = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("debug") {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("redact") {
+                            options.redact = true;
+                            Ok(())
+                        } else {
+                            Err(meta.error("unsupported debug option"))
+                        }
+                    })
+                } else if meta.path.is_ident("default") {
+                    options.default = Some(if meta.input.peek(Token![=]) {
+                        meta.value()?.parse()?
+                    } else {
+                        parse_quote! { ::core::default::Default::default() }
+                    });
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported state option"))
+                }
+            })
+            .expect("failed to parse #[state] options");
+        }
+    }
+    options
+}
+
+/// Mapping of a `#[state(with = ..)]` field, implementing `StateField` for the field type
+pub(crate) fn is_custom<F: GetSetType>(field: &F) -> Option<Path> {
+    extract_state_options(&field.attrs()).with
+}
+
+/// Replaces the path of a nested state type, keeping its generic arguments
+/// unless the new path has its own
+fn override_state_path(ty: &mut TypePath, path: Path) {
+    let arguments = ty.path.segments.last().map(|a| a.arguments.clone());
+    ty.path = path;
+    let last = ty.path.segments.last_mut().expect("failed to parse type");
+    if let (PathArguments::None, Some(arguments)) = (&last.arguments, arguments) {
+        last.arguments = arguments;
+    }
+}
+
+/// Rc type of a nested state, given by `#[state(rc = ..)]` or named
+/// after the state type with a `Rc` prefix
+pub(crate) fn rc_state_type<F: GetSetType>(field: &F, inner: &Type) -> Type {
+    let mut inner = inner.clone();
+    if let Type::Path(data) = &mut inner {
+        match extract_state_options(&field.attrs()).rc {
+            Some(path) => override_state_path(data, path),
+            None => {
+                let last = data.path.segments.last_mut().expect("failed to parse type");
+                last.ident = format_rc_ident(&last.ident);
+            }
+        }
+    }
+    inner
+}
+
+/// Ref type of a nested state, given by `#[state(ref = ..)]` or named
+/// after the state type with a `Ref` prefix
+pub(crate) fn ref_state_type<F: GetSetType>(field: &F, inner: &Type) -> Type {
+    let mut inner = inner.clone();
+    if let Type::Path(data) = &mut inner {
+        match extract_state_options(&field.attrs()).r#ref {
+            Some(path) => override_state_path(data, path),
+            None => {
+                let last = data.path.segments.last_mut().expect("failed to parse type");
+                last.ident = format_ref_ident(&last.ident);
+            }
+        }
+        let last = data.path.segments.last_mut().expect("failed to parse type");
+        insert_lifetime_into_generics(last, s_lifetime());
+    }
+    inner
+}
+
+/// Whether `ty` names the state being derived
+pub(crate) fn is_owner(ty: &Type, owner: &Ident) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == *owner),
+        _ => false,
+    }
+}
+
+/// Path to call `new` on the Rc type of a nested state
+pub(crate) fn rc_state_ctor<F: GetSetType>(field: &F, inner: &TypePath) -> TokenStream {
+    state_ctor_path(rc_state_type(field, &Type::Path(inner.clone())))
+}
+
+/// Path to call `new` on the Ref type of a nested state
+pub(crate) fn ref_state_ctor<F: GetSetType>(field: &F, inner: &TypePath) -> TokenStream {
+    state_ctor_path(ref_state_type(field, &Type::Path(inner.clone())))
+}
+
+fn state_ctor_path(ty: Type) -> TokenStream {
+    match ty {
+        Type::Path(mut data) => {
+            let last = data.path.segments.last_mut().expect("failed to parse type");
+            last.arguments = PathArguments::None;
+            quote! { #data }
+        }
+        ty => quote! { #ty },
+    }
+}
+
+/// Wrapper around a `#[state]` field type that the derive sees through
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StateWrapper {
+    Option,
+    Box,
+    Rc,
+}
+
+/// Peels `Option`, `Box` and `Rc` off a `#[state]` field type, outermost wrapper first
+pub(crate) fn unwrap_state_type(ty: &Type) -> (Vec<StateWrapper>, Type) {
+    let mut wrappers = Vec::new();
+    let mut ty = ty.clone();
+    while let Type::Path(data) = &ty {
+        let wrapper = match data.path.segments.last() {
+            Some(last) if last.ident == "Option" => StateWrapper::Option,
+            Some(last) if last.ident == "Box" => StateWrapper::Box,
+            Some(last) if last.ident == "Rc" => StateWrapper::Rc,
+            _ => break,
+        };
+        match collection_element(&ty) {
+            Some(inner) => ty = inner,
+            None => break,
+        }
+        wrappers.push(wrapper);
+    }
+    (wrappers, ty)
+}
+
+/// Wraps the derived state type back, the signal already provides the indirection
+/// of `Box` and `Rc` so only `Option` layers are kept
+pub(crate) fn wrap_state_type(wrappers: &[StateWrapper], inner: TokenStream) -> TokenStream {
+    wrappers
+        .iter()
+        .rev()
+        .fold(inner, |ty, wrapper| match wrapper {
+            StateWrapper::Option => quote! { Option<#ty> },
+            StateWrapper::Box | StateWrapper::Rc => ty,
+        })
+}
+
+/// Converts the wrapped value `a` with `ctor`, which builds the derived state from an unwrapped `a`
+pub(crate) fn wrap_state_ctor(wrappers: &[StateWrapper], ctor: TokenStream) -> TokenStream {
+    wrappers
+        .iter()
+        .rev()
+        .fold(ctor, |ctor, wrapper| match wrapper {
+            StateWrapper::Option => quote! { a.map(|a| #ctor) },
+            StateWrapper::Box => quote! { { let a = *a; #ctor } },
+            StateWrapper::Rc => quote! { { let a = ::std::rc::Rc::unwrap_or_clone(a); #ctor } },
+        })
+}
+
+/// Layout of a plain `#[collection]` field
+pub(crate) struct CollectionShape {
+    /// The collection is wrapped in an [`Option`]
+    pub optional: bool,
+    /// Number of nested collections, 1 for a flat collection
+    pub depth: usize,
+    /// Type of the innermost items
+    pub element: Type,
+    /// Length of each level which is an array, outermost first
+    ///
+    /// Array levels get a fixed size signal of item signals instead of a collection
+    /// signal, so they can't be resized and always convert back to their array
+    pub arrays: Vec<Option<Expr>>,
+}
+
+/// Nesting of a `#[collection]` field, either given by `#[collection(depth = ..)]`
+/// or inferred from directly nested `Vec` and `VecDeque`, nested arrays need an explicit depth
+pub(crate) fn collection_shape<F: GetSetType>(field: &F) -> Option<CollectionShape> {
+    extract_attribute_from_field(&field.attrs(), collection_ident())?;
+    let depth = extract_collection_options(&field.attrs()).depth;
+    let (optional, ty) = match option_inner(field.ref_ty()) {
+        Some(inner) => (true, inner),
+        None => (false, field.ref_ty().clone()),
+    };
+
+    let mut element = collection_element(&ty)?;
+    let mut arrays = vec![array_len(&ty)];
+    while depth.map_or_else(
+        || is_nested_collection(&element),
+        |depth| arrays.len() < depth,
+    ) {
+        arrays.push(array_len(&element));
+        element = collection_element(&element).expect("collection depth exceeds the field type");
+    }
+    Some(CollectionShape {
+        optional,
+        depth: arrays.len(),
+        element,
+        arrays,
+    })
+}
+
+fn array_len(ty: &Type) -> Option<Expr> {
+    match ty {
+        Type::Array(data) => Some(data.len.clone()),
+        _ => None,
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Path(data) if data.path.segments.last()?.ident == "Option" => collection_element(ty),
+        _ => None,
+    }
+}
+
+fn collection_element(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Array(data) => Some(*data.elem.clone()),
+        Type::Path(data) => match &data.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(inner) => match inner.args.first()? {
+                syn::GenericArgument::Type(inner) => Some(inner.clone()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_nested_collection(ty: &Type) -> bool {
+    match ty {
+        Type::Path(data) => data
+            .path
+            .segments
+            .last()
+            .is_some_and(|last| last.ident == "Vec" || last.ident == "VecDeque"),
+        _ => false,
+    }
+}
+
+pub(crate) fn extract_collection_options(attrs: &[Attribute]) -> CollectionOptions {
+    let mut options = CollectionOptions::default();
+    for attr in attrs.iter() {
+        if !attr.path().is_ident(&collection_ident()) {
+            continue;
+        }
+        if let syn::Meta::List(_) = attr.meta {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("capacity") {
+                    options.capacity = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("sorted_by") {
+                    options.sorted_by = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("depth") {
+                    let depth: LitInt = meta.value()?.parse()?;
+                    match depth.base10_parse()? {
+                        0 => Err(meta.error("collection depth must be at least 1")),
+                        depth => {
+                            options.depth = Some(depth);
+                            Ok(())
+                        }
+                    }
+                } else {
+                    Err(meta.error("unsupported collection option"))
+                }
+            })
+            .expect("failed to parse #[collection] options");
+        }
+    }
+    options
+}
+
+/// Signal of a `#[collection(..)]` field with options, along with the option
+/// passed as first argument of the signal constructor
+pub(crate) struct ConfiguredCollection {
+    pub signal: Ident,
+    pub arg: Expr,
+}
+
+/// `#[collection(capacity = ..)]` fields are mapped to a ring signal and
+/// `#[collection(sorted_by = ..)]` fields to a sorted collection signal
+pub(crate) fn is_configured_collection<F: GetSetType>(field: &F) -> Option<ConfiguredCollection> {
+    let options = extract_collection_options(&field.attrs());
+    match (options.capacity, options.sorted_by) {
+        (Some(_), Some(_)) => panic!("capacity and sorted_by collection options are exclusive"),
+        (Some(capacity), None) => Some(ConfiguredCollection {
+            signal: format_ident!("RcRingSignal"),
+            arg: capacity,
+        }),
+        (None, Some(sorted_by)) => Some(ConfiguredCollection {
+            signal: format_ident!("RcSortedCollectionSignal"),
+            arg: sorted_by,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Key and value types of a `#[collection]` field holding a map,
+/// along with the map signal it is mapped to
+pub(crate) struct MapCollection {
+    pub signal: Ident,
+    pub key: Type,
+    pub value: Type,
+}
+
+pub(crate) fn is_map<F: GetSetType>(field: &F) -> Option<MapCollection> {
+    extract_attribute_from_field(&field.attrs(), collection_ident())?;
+    let data = match field.ref_ty() {
+        Type::Path(data) => data,
+        _ => return None,
+    };
+    let last = data.path.segments.last()?;
+    let signal = match last.ident.to_string().as_str() {
+        "HashMap" => format_ident!("RcHashMapSignal"),
+        "BTreeMap" => format_ident!("RcBTreeMapSignal"),
+        "IndexMap" => format_ident!("RcIndexMapSignal"),
+        _ => return None,
+    };
+    let args = match &last.arguments {
+        PathArguments::AngleBracketed(args) => args,
+        _ => return None,
+    };
+    let mut types = args.args.iter().filter_map(|a| match a {
+        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    Some(MapCollection {
+        signal,
+        key: types.next()?,
+        value: types.next()?,
+    })
+}
+
+/// Element type of a `#[set]` field along with the set signal it is mapped to
+pub(crate) struct SetCollection {
+    pub signal: Ident,
+    pub value: Type,
+}
+
+pub(crate) fn is_set<F: GetSetType>(field: &F) -> Option<SetCollection> {
+    extract_attribute_from_field(&field.attrs(), set_ident())?;
+    let last = match field.ref_ty() {
+        Type::Path(data) => data.path.segments.last(),
+        _ => None,
+    };
+    let last = last.expect("#[set] fields must be a HashSet or a BTreeSet");
+    let signal = match last.ident.to_string().as_str() {
+        "HashSet" => format_ident!("RcHashSetSignal"),
+        "BTreeSet" => format_ident!("RcBTreeSetSignal"),
+        _ => panic!("#[set] fields must be a HashSet or a BTreeSet"),
+    };
+    let value = match &last.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|a| match a {
+            syn::GenericArgument::Type(ty) => Some(ty.clone()),
+            _ => None,
+        }),
+        _ => None,
+    };
+    Some(SetCollection {
+        signal,
+        value: value.expect("failed to parse set type"),
+    })
+}
+
 pub fn stateful_ident() -> Ident {
     format_ident!("state")
 }
@@ -236,6 +691,10 @@ pub fn collection_ident() -> Ident {
     format_ident!("collection")
 }
 
+pub fn set_ident() -> Ident {
+    format_ident!("set")
+}
+
 pub fn derived_ident() -> Ident {
     format_ident!("derived")
 }
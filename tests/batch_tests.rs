@@ -0,0 +1,178 @@
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+
+use sycamore::reactive::{create_effect, create_ref, create_scope};
+use sycamore_state_macros::State;
+use sycamore_state_manager::{batch, BatchSignal, CollectionPath, MapPath};
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub struct MyInnerState {
+    pub field_1: i64,
+    #[collection]
+    pub collection: Vec<String>,
+}
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub struct MyState {
+    pub field_1: String,
+    pub field_2: u32,
+    #[state]
+    pub field_3: MyInnerState,
+    #[collection]
+    pub scores: BTreeMap<String, u32>,
+}
+
+fn my_state() -> MyState {
+    MyState {
+        field_1: "state".into(),
+        field_2: 0,
+        field_3: MyInnerState {
+            field_1: 1,
+            collection: vec!["a".into()],
+        },
+        scores: BTreeMap::from([("ada".into(), 1)]),
+    }
+}
+
+#[test]
+pub fn state_batch_test() {
+    let _ = create_scope(|cx| {
+        let state = RcMyState::new(my_state());
+        let runs = create_ref(cx, Cell::new(0));
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        state.on_any_change(cx, || runs.set(runs.get() + 1));
+        state.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        let result = state.batch(|s| {
+            s.set_field_1("edited".into());
+            s.set_field_2(1);
+            s.set_field_2(2);
+            s.field_3.get().set_field_1(5);
+            s.field_3.get().collection.push("b".into());
+            s.scores.insert("bob".into(), 2);
+            assert_eq!(*state.field_2.get(), 2);
+            assert_eq!(runs.get(), 0);
+            "done"
+        });
+        assert_eq!(result, "done");
+        assert_eq!(runs.get(), 1);
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                MyStateField::Field1,
+                MyStateField::Field2,
+                MyStateField::Scores(MapPath::Entries),
+                MyStateField::Field3(Some(MyInnerStateField::Field1)),
+                MyStateField::Field3(Some(MyInnerStateField::Collection(CollectionPath::Items))),
+            ]
+        );
+
+        let mut expected = my_state();
+        expected.field_1 = "edited".into();
+        expected.field_2 = 2;
+        expected.field_3.field_1 = 5;
+        expected.field_3.collection.push("b".into());
+        expected.scores.insert("bob".into(), 2);
+        assert_eq!(state.snapshot(), expected);
+
+        state.batch(|_| {});
+        assert_eq!(runs.get(), 1);
+    });
+}
+
+#[test]
+pub fn free_batch_test() {
+    let _ = create_scope(|cx| {
+        let state = RcMyState::new(my_state());
+        let runs = create_ref(cx, Cell::new(0));
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        state.on_any_change(cx, || runs.set(runs.get() + 1));
+        state.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        batch(|| {
+            state.field_2.set_batched(1);
+            state.field_2.set_batched(2);
+            state.scores.insert("bob".into(), 2);
+            batch(|| state.field_3.get().collection.push("b".into()));
+            assert_eq!(runs.get(), 0);
+        });
+        assert_eq!(runs.get(), 1);
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                MyStateField::Field2,
+                MyStateField::Scores(MapPath::Entries),
+                MyStateField::Field3(Some(MyInnerStateField::Collection(CollectionPath::Items))),
+            ]
+        );
+
+        state.field_2.set(3);
+        assert_eq!(runs.get(), 2);
+    });
+}
+
+#[test]
+pub fn effect_batch_test() {
+    let _ = create_scope(|cx| {
+        let state = RcMyState::new(my_state());
+        let first = state.field_3.get().collection.get()[0].clone();
+        let seen = create_ref(cx, RefCell::new(Vec::new()));
+        create_effect(cx, {
+            let state = state.clone();
+            move || {
+                let items = state.field_3.get().collection.get().len();
+                let entry = (state.field_1.get().to_string(), *state.field_2.get(), items);
+                seen.borrow_mut().push(entry);
+            }
+        });
+
+        state.batch(|s| {
+            s.set_field_1("edited".into());
+            s.field_2.modify_batched(|a| *a += 1);
+            s.field_2.modify_batched(|a| *a += 1);
+            s.field_3.get().collection.push("b".into());
+            first.move_down();
+            assert_eq!(seen.borrow().len(), 1);
+        });
+        // The effect reads every written signal and runs once, with the final values
+        assert_eq!(
+            *seen.borrow(),
+            vec![("state".to_string(), 0, 1), ("edited".to_string(), 2, 2)]
+        );
+        assert_eq!(first.index(), Some(1));
+        assert_eq!(*first.get(), "a");
+    });
+}
+
+#[test]
+pub fn ref_batch_test() {
+    let _ = create_scope(|cx| {
+        let state = RefMyState::new(cx, my_state());
+        let runs = create_ref(cx, Cell::new(0));
+        create_effect(cx, move || {
+            state.field_1.track();
+            state.field_2.track();
+            state.field_3.get().field_1.track();
+            state.field_3.get().collection.track();
+            runs.set(runs.get() + 1);
+        });
+
+        state.batch(|s| {
+            s.set_field_1("edited".into());
+            s.set_field_2(1);
+            s.field_3.get().set_field_1(2);
+            s.field_3.get().collection.push_value(cx, "b".into());
+            s.field_3.get().collection.get()[0].remove();
+            s.scores.insert("bob".into(), 2);
+            assert_eq!(*s.field_2.get(), 1);
+            assert_eq!(runs.get(), 1);
+        });
+        assert_eq!(runs.get(), 2);
+        assert_eq!(state.snapshot().field_3.collection, vec!["b".to_string()]);
+
+        state.field_2.set(3);
+        assert_eq!(runs.get(), 3);
+    });
+}
@@ -0,0 +1,89 @@
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+use sycamore_state_manager::BuildError;
+
+#[derive(State, Clone, Default)]
+#[state(clone, derive(Default))]
+pub struct Settings {
+    pub volume: u8,
+    pub theme: String,
+}
+
+#[derive(State, Clone)]
+pub struct Profile {
+    pub name: String,
+    #[state(default = 18)]
+    pub age: u32,
+    #[state(default)]
+    #[collection]
+    pub aliases: Vec<String>,
+    #[state]
+    #[state(default)]
+    pub settings: Settings,
+}
+
+#[derive(State, Clone)]
+#[state(ref_derive(Default))]
+pub enum Mode {
+    Idle(bool),
+    Busy(u32),
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Busy(3)
+    }
+}
+
+#[test]
+pub fn rc_default_and_builder_test() {
+    let settings = RcSettings::default();
+    assert_eq!(*settings.volume.get(), 0);
+
+    let profile = RcProfile::builder()
+        .name("ada".into())
+        .aliases(vec!["countess".into()])
+        .build()
+        .expect("name is set");
+    assert_eq!(*profile.name.get(), "ada");
+    assert_eq!(*profile.age.get(), 18);
+    assert_eq!(profile.aliases.get().len(), 1);
+    assert_eq!(*profile.settings.get().theme.get(), "");
+
+    let older = RcProfile::builder()
+        .name("bob".into())
+        .age(40)
+        .build()
+        .expect("name is set");
+    assert_eq!(*older.age.get(), 40);
+    assert!(older.aliases.get().is_empty());
+}
+
+#[test]
+pub fn builder_missing_field_test() {
+    let error = RcProfile::builder().age(1).build().err();
+    assert_eq!(error, Some(BuildError::MissingField("name")));
+    assert_eq!(error.unwrap().to_string(), "missing field `name`");
+
+    let _ = create_scope(|cx| {
+        let error = RefProfile::builder().build(cx).err();
+        assert_eq!(error, Some(BuildError::MissingField("name")));
+    });
+}
+
+#[test]
+pub fn ref_default_and_builder_test() {
+    let _ = create_scope(|cx| {
+        let settings = RefSettings::default_in(cx);
+        assert_eq!(*settings.theme.get(), "");
+
+        let profile = RefProfile::builder()
+            .name("ada".into())
+            .build(cx)
+            .expect("name is set");
+        assert_eq!(*profile.age.get(), 18);
+        assert_eq!(*profile.settings.get().volume.get(), 0);
+
+        assert!(matches!(RefMode::default_in(cx), RefMode::Busy(_)));
+    });
+}
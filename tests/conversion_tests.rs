@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Task {
+    pub title: String,
+    pub done: bool,
+}
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Board {
+    pub name: String,
+    #[state]
+    pub owner: Option<Task>,
+    #[state]
+    #[collection]
+    pub tasks: Vec<Task>,
+    #[collection]
+    pub labels: HashMap<String, u32>,
+}
+
+#[derive(State, Clone, Debug, PartialEq)]
+pub enum Filter {
+    All(bool),
+    Tag(String),
+}
+
+pub struct Handle(pub Rc<u32>);
+
+#[derive(State)]
+pub struct Session {
+    pub user: String,
+    pub handle: Handle,
+}
+
+fn board() -> Board {
+    Board {
+        name: "work".into(),
+        owner: Some(Task {
+            title: "lead".into(),
+            done: false,
+        }),
+        tasks: vec![Task {
+            title: "write".into(),
+            done: true,
+        }],
+        labels: HashMap::from([("bug".into(), 2)]),
+    }
+}
+
+#[test]
+pub fn rc_conversion_test() {
+    let state = RcBoard::from(board());
+    assert_eq!(state.snapshot(), board());
+
+    state.tasks.get()[0].get().done.set(false);
+    let plain = Board::from(&state);
+    assert!(!plain.tasks[0].done);
+
+    let filter = RcFilter::new(Filter::Tag("bug".into()));
+    assert_eq!(filter.snapshot(), Filter::Tag("bug".into()));
+}
+
+#[test]
+pub fn ref_conversion_test() {
+    let _ = create_scope(|cx| {
+        let state = RcBoard::new(board());
+        let copy = state.to_ref(cx);
+        copy.name.set("home".into());
+        assert_eq!(*state.name.get(), "work");
+        assert_eq!(copy.snapshot().name, "home");
+
+        let back = copy.to_rc();
+        assert_eq!(Board::from(&back), copy.snapshot());
+        assert_eq!(RcBoard::from(&copy).snapshot(), Board::from(&copy));
+    });
+}
+
+#[test]
+pub fn share_test() {
+    let task = RcTask::new(Task {
+        title: "write".into(),
+        done: false,
+    });
+    let shared = task.share();
+    shared.done.set(true);
+    assert!(*task.done.get());
+
+    let session = RcSession::new(Session {
+        user: "ada".into(),
+        handle: Handle(Rc::new(1)),
+    });
+    let shared = session.share();
+    assert_eq!(*shared.handle.get().0, 1);
+}
@@ -0,0 +1,109 @@
+use sycamore_state_macros::State;
+
+/// Facade re-exporting the state manager and sycamore under its own paths
+mod facade {
+    pub mod state {
+        pub use sycamore_state_manager::*;
+    }
+    pub use sycamore as reactive;
+}
+
+mod app {
+    use super::*;
+
+    #[derive(State, Clone)]
+    #[state(
+        clone,
+        crate = "crate::facade::state",
+        sycamore = "crate::facade::reactive"
+    )]
+    pub struct Task {
+        pub title: String,
+        #[collection]
+        pub tags: Vec<String>,
+        #[set]
+        pub owners: std::collections::HashSet<u32>,
+    }
+
+    #[derive(State, Clone)]
+    #[state(crate = "crate::facade::state", sycamore = "crate::facade::reactive")]
+    pub enum Status {
+        Open(bool),
+        #[collection]
+        Blocked(Vec<String>),
+    }
+}
+
+/// Only the renamed paths are imported, nothing else of either crate is in scope
+mod renamed {
+    use sycamore as syc;
+    use sycamore_state_manager as ssm;
+
+    #[derive(ssm::State, Clone)]
+    #[state(clone, crate = "ssm", sycamore = "syc")]
+    pub struct Step {
+        pub done: bool,
+    }
+
+    #[derive(ssm::State, Clone)]
+    #[state(clone, crate = "ssm", sycamore = "syc")]
+    pub struct Plan {
+        pub name: String,
+        #[state]
+        #[collection]
+        pub steps: Vec<Step>,
+        #[collection]
+        pub notes: std::collections::HashMap<u32, String>,
+    }
+
+    pub fn plan() -> Plan {
+        Plan {
+            name: "release".into(),
+            steps: vec![Step { done: false }],
+            notes: [(1, "tag".into())].into(),
+        }
+    }
+}
+
+fn task() -> app::Task {
+    app::Task {
+        title: "write".into(),
+        tags: vec!["docs".into()],
+        owners: [1].into(),
+    }
+}
+
+#[test]
+pub fn rc_crate_path_test() {
+    let task = app::RcTask::new(task());
+    task.tags.push("review".into());
+    assert_eq!(task.tags.get().len(), 2);
+    assert!(matches!(
+        app::RcStatus::new(app::Status::Open(true)),
+        app::RcStatus::Open(_)
+    ));
+}
+
+#[test]
+pub fn ref_crate_path_test() {
+    let _ = facade::reactive::reactive::create_scope(|cx| {
+        let task = app::RefTask::new(cx, task());
+        assert_eq!(*task.title.get(), "write");
+        match app::RefStatus::new(cx, app::Status::Blocked(vec!["ci".into()])) {
+            app::RefStatus::Blocked(reasons) => assert_eq!(reasons.get().len(), 1),
+            _ => panic!("expected blocked"),
+        }
+    });
+}
+
+#[test]
+fn renamed_crate_path_test() {
+    let plan = renamed::RcPlan::new(renamed::plan());
+    plan.steps.get()[0].get().done.set(true);
+    assert!(plan.snapshot().steps[0].done);
+    assert_eq!(plan.notes.len(), 1);
+    let _ = sycamore::reactive::create_scope(|cx| {
+        let plan = renamed::RefPlan::new(cx, renamed::plan());
+        assert_eq!(*plan.name.get(), "release");
+    });
+}
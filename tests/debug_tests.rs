@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone, debug)]
+pub struct Credentials {
+    pub user: String,
+    #[state(debug(redact))]
+    pub password: String,
+}
+
+#[derive(State, Clone)]
+#[state(clone, debug)]
+pub struct Account {
+    pub id: u32,
+    #[state]
+    pub credentials: Credentials,
+    #[collection]
+    pub roles: Vec<String>,
+    #[collection]
+    pub backup: Option<Vec<u8>>,
+    pub limits: BTreeMap<String, u32>,
+}
+
+#[derive(State, Clone)]
+#[state(debug)]
+pub enum Session {
+    Anonymous(bool),
+    #[state]
+    Signed(Credentials),
+}
+
+fn account() -> Account {
+    Account {
+        id: 7,
+        credentials: Credentials {
+            user: "ada".into(),
+            password: "hunter2".into(),
+        },
+        roles: vec!["admin".into()],
+        backup: None,
+        limits: [("daily".into(), 3)].into(),
+    }
+}
+
+const EXPECTED: &str = "Account { id: 7, credentials: Credentials { user: \"ada\", \
+    password: <redacted> }, roles: [\"admin\"], backup: None, limits: {\"daily\": 3} }";
+
+#[test]
+pub fn rc_value_debug_test() {
+    let state = RcAccount::new(account());
+    assert_eq!(format!("{:?}", state), EXPECTED);
+    state.roles.push("owner".into());
+    assert!(format!("{:?}", state).contains("roles: [\"admin\", \"owner\"]"));
+
+    let session = RcSession::new(Session::Signed(account().credentials));
+    assert_eq!(
+        format!("{:?}", session),
+        "Signed(Credentials { user: \"ada\", password: <redacted> })"
+    );
+}
+
+#[test]
+pub fn ref_value_debug_test() {
+    let _ = create_scope(|cx| {
+        let state = RefAccount::new(cx, account());
+        assert_eq!(format!("{:?}", state), EXPECTED);
+        let session = RefSession::new(cx, Session::Anonymous(true));
+        assert_eq!(format!("{:?}", session), "Anonymous(true)");
+    });
+}
+
+#[test]
+pub fn patch_debug_test() {
+    let patch = CredentialsPatch {
+        user: None,
+        password: Some("hunter2".into()),
+    };
+    assert_eq!(
+        format!("{:?}", patch),
+        "CredentialsPatch { user: None, password: Some(<redacted>) }"
+    );
+}
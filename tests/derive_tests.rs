@@ -0,0 +1,90 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone, derive(PartialEq, Debug, Default))]
+pub struct Measure {
+    pub value: f64,
+    pub unit: String,
+}
+
+impl Default for Measure {
+    fn default() -> Self {
+        Self {
+            value: 1.0,
+            unit: "m".into(),
+        }
+    }
+}
+
+#[derive(State, Clone)]
+#[state(eq, derive(Hash), rc_derive(Default))]
+pub struct Tagged {
+    pub name: String,
+    #[collection]
+    pub tags: Vec<String>,
+}
+
+impl Default for Tagged {
+    fn default() -> Self {
+        Self {
+            name: "untitled".into(),
+            tags: vec!["new".into()],
+        }
+    }
+}
+
+#[derive(State, Clone)]
+#[state(derive(PartialEq, Default), ref_derive(Debug))]
+pub enum Reading {
+    Empty(bool),
+    Value(f64),
+}
+
+impl Default for Reading {
+    fn default() -> Self {
+        Reading::Empty(true)
+    }
+}
+
+fn hash_of(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+pub fn rc_derive_list_test() {
+    let measure = RcMeasure::default();
+    assert_eq!(*measure.value.get(), 1.0);
+    assert_eq!(*measure.unit.get(), "m");
+    assert_eq!(measure, RcMeasure::new(Measure::default()).clone());
+    measure.value.set(2.0);
+    assert_eq!(format!("{:?}", measure.value), "RcSignal(2.0)");
+
+    let a = RcTagged::default();
+    let b = RcTagged::new(Tagged::default());
+    assert!(a == b);
+    assert_eq!(hash_of(&a), hash_of(&b));
+    b.tags.push("again".into());
+    assert_ne!(hash_of(&a), hash_of(&b));
+
+    assert!(matches!(RcReading::default(), RcReading::Empty(_)));
+    assert!(RcReading::new(Reading::Value(0.5)) == RcReading::new(Reading::Value(0.5)));
+}
+
+#[test]
+pub fn ref_derive_list_test() {
+    let _ = create_scope(|cx| {
+        let a = RefTagged::new(cx, Tagged::default());
+        let b = RefTagged::new(cx, Tagged::default());
+        assert_eq!(hash_of(&a), hash_of(&b));
+
+        let reading = RefReading::new(cx, Reading::Value(0.5));
+        assert!(reading == RefReading::new(cx, Reading::Value(0.5)));
+        assert!(format!("{:?}", reading).starts_with("Value"));
+    });
+}
@@ -0,0 +1,63 @@
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Address {
+    pub city: String,
+}
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Contact {
+    pub name: String,
+    #[state]
+    pub address: Address,
+    #[state]
+    #[collection]
+    pub previous: Vec<Address>,
+}
+
+fn contact() -> Contact {
+    Contact {
+        name: "ada".into(),
+        address: Address {
+            city: "london".into(),
+        },
+        previous: vec![Address {
+            city: "paris".into(),
+        }],
+    }
+}
+
+#[test]
+pub fn rc_fork_test() {
+    let state = RcContact::new(contact());
+    let shared = state.clone();
+    let fork = state.fork();
+    assert_eq!(fork.snapshot(), contact());
+
+    fork.name.set("bob".into());
+    fork.address.get().city.set("rome".into());
+    fork.previous.get()[0].get().city.set("oslo".into());
+    fork.previous.push(RcAddress::new(Address {
+        city: "nice".into(),
+    }));
+    assert_eq!(state.snapshot(), contact());
+
+    shared.name.set("eve".into());
+    assert_eq!(*state.name.get(), "eve");
+    assert_eq!(*fork.name.get(), "bob");
+}
+
+#[test]
+pub fn ref_fork_test() {
+    let _ = create_scope(|cx| {
+        let state = RefContact::new(cx, contact());
+        let fork = state.fork(cx);
+        fork.address.get().city.set("rome".into());
+        fork.previous.get()[0].get().city.set("oslo".into());
+        assert_eq!(state.snapshot(), contact());
+        assert_eq!(*fork.address.get().city.get(), "rome");
+    });
+}
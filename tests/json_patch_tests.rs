@@ -0,0 +1,270 @@
+#![cfg(feature = "json-patch")]
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sycamore::reactive::{create_effect, create_ref, create_scope};
+use sycamore_state_macros::State;
+use sycamore_state_manager::{json_patch, JsonPatchError, JsonPatchOp, JsonPointer};
+
+#[derive(State, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[state(clone)]
+pub struct Item {
+    pub name: String,
+    pub done: bool,
+}
+
+#[derive(State, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[state(clone)]
+pub enum Status {
+    Idle(String),
+    Busy(u32),
+}
+
+#[derive(State, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[state(clone)]
+pub struct Todos {
+    pub title: String,
+    pub tags: Vec<String>,
+    #[state]
+    pub selected: Option<Item>,
+    #[state]
+    pub status: Status,
+    #[state]
+    #[collection]
+    pub items: Vec<Item>,
+    #[collection]
+    pub counts: BTreeMap<u32, String>,
+    #[state]
+    #[collection]
+    pub owners: BTreeMap<String, Item>,
+    #[collection]
+    pub grid: Vec<Vec<u8>>,
+    #[collection]
+    pub corners: [u8; 2],
+    #[set]
+    pub labels: BTreeSet<String>,
+    #[collection(capacity = 2)]
+    pub recent: VecDeque<String>,
+    #[collection(sorted_by = |a, b| a.cmp(b))]
+    pub timestamps: Vec<u64>,
+    #[collection]
+    pub history: Option<Vec<String>>,
+}
+
+fn item(name: &str) -> Item {
+    Item {
+        name: name.into(),
+        done: false,
+    }
+}
+
+fn todos() -> Todos {
+    Todos {
+        title: "todos".into(),
+        tags: vec!["a".into()],
+        selected: None,
+        status: Status::Idle("ready".into()),
+        items: vec![item("first"), item("second")],
+        counts: BTreeMap::from([(1, "one".into())]),
+        owners: BTreeMap::from([("ada".into(), item("owned"))]),
+        grid: vec![vec![1, 2], vec![3]],
+        corners: [1, 2],
+        labels: BTreeSet::from(["x".into()]),
+        recent: VecDeque::from(["r".into()]),
+        timestamps: vec![10, 20],
+        history: None,
+    }
+}
+
+fn patch(value: serde_json::Value) -> Vec<JsonPatchOp> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn get_pointer_test() {
+    let state = RcTodos::new(todos());
+    let expected = serde_json::to_value(RcTodos::new(todos()).snapshot()).unwrap();
+    assert_eq!(state.get_pointer(&[]), Ok(expected));
+    let path = json_patch::parse_pointer("/items/1/name").unwrap();
+    assert_eq!(state.get_pointer(&path), Ok(json!("second")));
+    let path = json_patch::parse_pointer("/counts/1").unwrap();
+    assert_eq!(state.get_pointer(&path), Ok(json!("one")));
+    let path = json_patch::parse_pointer("/status/Idle").unwrap();
+    assert_eq!(state.get_pointer(&path), Ok(json!("ready")));
+    let path = json_patch::parse_pointer("/grid/0/1").unwrap();
+    assert_eq!(state.get_pointer(&path), Ok(json!(2)));
+}
+
+#[test]
+fn apply_fields_test() {
+    let state = RcTodos::new(todos());
+    state
+        .apply_json_patch(&patch(json!([
+            { "op": "replace", "path": "/title", "value": "renamed" },
+            { "op": "add", "path": "/tags/-", "value": "b" },
+            { "op": "replace", "path": "/items/0/done", "value": true },
+            { "op": "add", "path": "/items/1", "value": { "name": "middle", "done": false } },
+            { "op": "remove", "path": "/items/2" },
+            { "op": "add", "path": "/selected", "value": { "name": "picked", "done": true } },
+            { "op": "replace", "path": "/status/Idle", "value": "waiting" },
+            { "op": "add", "path": "/counts/2", "value": "two" },
+            { "op": "remove", "path": "/counts/1" },
+            { "op": "replace", "path": "/owners/ada/name", "value": "renamed" },
+            { "op": "add", "path": "/grid/1/-", "value": 4 },
+            { "op": "replace", "path": "/corners/1", "value": 5 },
+            { "op": "add", "path": "/labels", "value": ["y"] },
+            { "op": "add", "path": "/recent/-", "value": "s" },
+            { "op": "add", "path": "/timestamps/-", "value": 5 },
+            { "op": "add", "path": "/history", "value": ["h"] },
+        ])))
+        .unwrap();
+    let mut expected = todos();
+    expected.title = "renamed".into();
+    expected.tags.push("b".into());
+    expected.items = vec![
+        Item {
+            name: "first".into(),
+            done: true,
+        },
+        item("middle"),
+    ];
+    expected.selected = Some(Item {
+        name: "picked".into(),
+        done: true,
+    });
+    expected.status = Status::Idle("waiting".into());
+    expected.counts = BTreeMap::from([(2, "two".into())]);
+    expected.owners = BTreeMap::from([("ada".into(), item("renamed"))]);
+    expected.grid = vec![vec![1, 2], vec![3, 4]];
+    expected.corners = [1, 5];
+    expected.labels = BTreeSet::from(["y".into()]);
+    expected.recent = VecDeque::from(["r".into(), "s".into()]);
+    expected.timestamps = vec![5, 10, 20];
+    expected.history = Some(vec!["h".into()]);
+    assert_eq!(state.snapshot(), expected);
+}
+
+#[test]
+fn move_copy_test() {
+    let state = RcTodos::new(todos());
+    state
+        .apply_json_patch(&patch(json!([
+            { "op": "copy", "from": "/items/1/name", "path": "/title" },
+            { "op": "move", "from": "/owners/ada", "path": "/owners/bob" },
+            { "op": "move", "from": "/items/0", "path": "/items/1" },
+            { "op": "test", "path": "/title", "value": "second" },
+        ])))
+        .unwrap();
+    let snapshot = state.snapshot();
+    assert_eq!(snapshot.title, "second");
+    assert_eq!(
+        snapshot.owners,
+        BTreeMap::from([("bob".into(), item("owned"))])
+    );
+    assert_eq!(snapshot.items, vec![item("second"), item("first")]);
+}
+
+#[test]
+fn typed_error_test() {
+    let state = RcTodos::new(todos());
+    let error = |value| state.apply_json_patch(&patch(value)).unwrap_err();
+    assert_eq!(
+        error(json!([{ "op": "replace", "path": "/missing", "value": 1 }])),
+        JsonPatchError::NotFound("/missing".into())
+    );
+    assert_eq!(
+        error(json!([{ "op": "remove", "path": "/items/5" }])),
+        JsonPatchError::NotFound("/items/5".into())
+    );
+    assert_eq!(
+        error(json!([{ "op": "remove", "path": "/corners/0" }])),
+        JsonPatchError::Unsupported {
+            op: "remove",
+            path: "/corners/0".into()
+        }
+    );
+    assert_eq!(
+        error(json!([{ "op": "move", "from": "/items/0/name", "path": "/title" }])),
+        JsonPatchError::Unsupported {
+            op: "move",
+            path: "/items/0/name".into()
+        }
+    );
+    assert!(matches!(
+        error(json!([{ "op": "replace", "path": "/title", "value": 1 }])),
+        JsonPatchError::InvalidValue { path, .. } if path == "/title"
+    ));
+    assert_eq!(
+        error(json!([
+            { "op": "replace", "path": "/title", "value": "changed" },
+            { "op": "test", "path": "/tags/0", "value": "b" },
+        ])),
+        JsonPatchError::TestFailed("/tags/0".into())
+    );
+    assert!(matches!(
+        json_patch::parse_pointer("items"),
+        Err(JsonPatchError::InvalidPointer(_))
+    ));
+    // a failed check leaves the state untouched
+    assert_eq!(state.snapshot(), todos());
+}
+
+#[test]
+fn diff_round_trip_test() {
+    let state = RcTodos::new(todos());
+    let mut new = todos();
+    new.title = "next".into();
+    new.items.remove(0);
+    new.items[0].done = true;
+    new.counts.insert(7, "seven".into());
+    new.owners.clear();
+    new.grid[0] = vec![9];
+    new.history = Some(vec!["h".into()]);
+    new.status = Status::Busy(3);
+    let ops = state.json_diff(&new).unwrap();
+    assert!(!ops.is_empty());
+    state.apply_json_patch(&ops).unwrap();
+    assert_eq!(state.snapshot(), new);
+    assert_eq!(state.json_diff(&new).unwrap(), Vec::new());
+}
+
+#[test]
+fn ref_apply_test() {
+    let _ = create_scope(|cx| {
+        let state = RefTodos::new(cx, todos());
+        let runs = create_ref(cx, Cell::new(0));
+        create_effect(cx, move || {
+            state.title.track();
+            runs.set(runs.get() + 1);
+        });
+        state
+            .apply_json_patch(
+                cx,
+                &patch(json!([
+                    { "op": "replace", "path": "/title", "value": "ref" },
+                    { "op": "add", "path": "/items/-", "value": { "name": "third", "done": false } },
+                    { "op": "add", "path": "/owners/bob", "value": { "name": "bob", "done": true } },
+                ])),
+            )
+            .unwrap();
+        assert_eq!(runs.get(), 2);
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.title, "ref");
+        assert_eq!(snapshot.items.last(), Some(&item("third")));
+        assert_eq!(
+            snapshot.owners.get("bob"),
+            Some(&Item {
+                name: "bob".into(),
+                done: true
+            })
+        );
+        let mut new = snapshot.clone();
+        new.tags.clear();
+        let ops = state.json_diff(&new).unwrap();
+        state.apply_json_patch(cx, &ops).unwrap();
+        assert_eq!(state.snapshot(), new);
+    });
+}
@@ -0,0 +1,69 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct InnerState {
+    pub field1: String,
+}
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct MapState {
+    #[collection]
+    pub sorted: BTreeMap<u32, String>,
+    #[collection]
+    pub hashed: HashMap<String, i32>,
+    #[state]
+    #[collection]
+    pub inner: BTreeMap<u32, InnerState>,
+}
+
+fn map_state() -> MapState {
+    MapState {
+        sorted: BTreeMap::from([(3, "c".into()), (1, "a".into()), (2, "b".into())]),
+        hashed: HashMap::from([("key".into(), 1)]),
+        inner: BTreeMap::from([(
+            0,
+            InnerState {
+                field1: "inner".into(),
+            },
+        )]),
+    }
+}
+
+#[test]
+fn rc_map_test() {
+    let state = RcMapState::new(map_state());
+    state.sorted.insert(0, "z".into());
+    let values = state
+        .sorted
+        .get_untracked()
+        .values()
+        .map(|a| a.get().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(values, vec!["z", "a", "b", "c"]);
+
+    assert!(state.hashed.update(&"key".into(), |a| a + 1));
+    assert_eq!(*state.hashed.get_item(&"key".into()).unwrap().get(), 2);
+
+    let inner = state.inner.get_item(&0).expect("inner state");
+    assert_eq!(inner.get().field1.get().as_str(), "inner");
+}
+
+#[test]
+fn ref_map_test() {
+    _ = create_scope(|cx| {
+        let state = RefMapState::new(cx, map_state());
+        let first = state.sorted.first().map(|(_, v)| v.get().to_string());
+        assert_eq!(first.as_deref(), Some("a"));
+
+        let inner = state.inner.get_item(&0).expect("inner state");
+        inner.get().field1.set("changed".into());
+        assert_eq!(inner.get().field1.get().as_str(), "changed");
+        inner.remove();
+        assert!(state.inner.is_empty());
+    });
+}
@@ -0,0 +1,130 @@
+use sycamore::reactive::{create_effect, create_rc_signal, create_scope, create_signal};
+use sycamore_state_macros::State;
+use sycamore_state_manager::StatePatch;
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Cell {
+    pub value: u8,
+}
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Board {
+    #[state]
+    #[collection]
+    pub grid: Vec<Vec<Cell>>,
+    #[collection]
+    pub layers: Vec<Vec<Vec<u8>>>,
+    #[collection(depth = 1)]
+    pub rows: Vec<Vec<u8>>,
+    #[collection]
+    pub history: Option<Vec<String>>,
+    #[collection]
+    pub pairs: Vec<[u8; 2]>,
+    #[collection(depth = 2)]
+    pub tiles: Vec<[u8; 2]>,
+    #[collection]
+    pub corners: [u8; 3],
+}
+
+fn board() -> Board {
+    Board {
+        grid: vec![
+            vec![Cell { value: 1 }, Cell { value: 2 }],
+            vec![Cell { value: 3 }],
+        ],
+        layers: vec![vec![vec![1, 2], vec![3]]],
+        rows: vec![vec![1, 2]],
+        history: None,
+        pairs: vec![[1, 2]],
+        tiles: vec![[3, 4]],
+        corners: [1, 2, 3],
+    }
+}
+
+#[test]
+fn rc_nested_collection_test() {
+    let state = RcBoard::new(board());
+    let row = state.grid.get()[1].get();
+    row.push(RcCell::new(Cell { value: 4 }));
+    assert_eq!(*row.get()[1].get().value.get(), 4);
+    assert_eq!(state.grid.get()[0].get().get().len(), 2);
+
+    let layer = state.layers.get()[0].get();
+    layer.get()[1].get().push(5);
+    assert_eq!(layer.get()[1].get().get().len(), 2);
+
+    let plain: &Vec<u8> = &state.rows.get()[0].get();
+    assert_eq!(plain, &vec![1, 2]);
+    assert_eq!(*state.pairs.get()[0].get(), [1, 2]);
+    state.tiles.get()[0].get().get()[1].set(5);
+    assert_eq!(state.snapshot().tiles, vec![[3, 5]]);
+
+    assert!(state.history.get().is_none());
+    state
+        .history
+        .set(Some(sycamore_state_manager::RcCollectionSignal::new(vec![
+            "first".into(),
+        ])));
+    assert_eq!(
+        state.history.get().as_ref().as_ref().map(|a| a.get().len()),
+        Some(1)
+    );
+}
+
+#[test]
+fn ref_nested_collection_test() {
+    _ = create_scope(|cx| {
+        let state = RefBoard::new(
+            cx,
+            Board {
+                history: Some(vec!["a".into()]),
+                ..board()
+            },
+        );
+        let cell = state.grid.get()[0].get().get()[1];
+        let values = create_signal(cx, Vec::new());
+        create_effect(cx, move || {
+            let value = *cell.get().value.get();
+            values.modify().push(value);
+        });
+        cell.get().value.set(7);
+        assert_eq!(*values.get(), vec![2, 7]);
+
+        let history = state.history.get();
+        let history = history.as_ref().as_ref().expect("history");
+        history.push_value(cx, "b".into());
+        assert_eq!(history.get().len(), 2);
+    });
+}
+
+#[test]
+fn array_level_test() {
+    let state = RcBoard::new(board());
+    state
+        .tiles
+        .push(create_rc_signal([create_rc_signal(5), create_rc_signal(6)]));
+    state.tiles.remove(0);
+    state.corners.get()[2].set(9);
+    let snapshot = state.snapshot();
+    assert_eq!(snapshot.tiles, vec![[5, 6]]);
+    assert_eq!(snapshot.corners, [1, 2, 9]);
+    assert_eq!(state.fork().snapshot(), snapshot);
+
+    let mut next = snapshot.clone();
+    next.corners[0] = 0;
+    next.tiles.push([7, 8]);
+    let patch = RcBoard::diff(&snapshot, &next).expect("boards differ");
+    state.apply_patch(patch);
+    assert_eq!(state.snapshot(), next);
+
+    _ = create_scope(|cx| {
+        let copy = state.to_ref(cx);
+        copy.tiles.get()[0].get().get()[0].set(1);
+        copy.corners.get()[1].set(4);
+        let snapshot = copy.snapshot();
+        assert_eq!(snapshot.tiles, vec![[1, 6], [7, 8]]);
+        assert_eq!(snapshot.corners, [0, 4, 9]);
+    });
+}
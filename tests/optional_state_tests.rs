@@ -0,0 +1,72 @@
+use std::rc::Rc;
+
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Author {
+    pub name: String,
+}
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Comment {
+    pub text: String,
+    #[state]
+    pub author: Option<Author>,
+    #[state]
+    pub reply: Option<Box<Comment>>,
+    #[state]
+    pub pinned: Rc<Author>,
+}
+
+fn thread() -> Comment {
+    Comment {
+        text: "first".into(),
+        author: None,
+        reply: Some(Box::new(Comment {
+            text: "second".into(),
+            author: Some(Author { name: "b".into() }),
+            reply: None,
+            pinned: Rc::new(Author { name: "p".into() }),
+        })),
+        pinned: Rc::new(Author { name: "p".into() }),
+    }
+}
+
+#[test]
+fn rc_optional_state_test() {
+    let state = RcComment::new(thread());
+    assert!(state.author.get().is_none());
+    assert_eq!(state.pinned.get().name.get().as_str(), "p");
+
+    let reply = state.reply.get();
+    let reply = reply.as_ref().as_ref().expect("reply");
+    assert_eq!(reply.text.get().as_str(), "second");
+    assert!(reply.reply.get().is_none());
+
+    let author = reply.author.get();
+    let author = author.as_ref().as_ref().expect("author");
+    author.name.set("c".into());
+    assert_eq!(author.name.get().as_str(), "c");
+
+    state
+        .author
+        .set(Some(RcAuthor::new(Author { name: "a".into() })));
+    assert!(state.author.get().is_some());
+}
+
+#[test]
+fn ref_optional_state_test() {
+    _ = create_scope(|cx| {
+        let state = RefComment::new(cx, thread());
+        let reply = state.reply.get();
+        let reply = reply.as_ref().as_ref().expect("reply");
+        reply.text.set("edited".into());
+        assert_eq!(reply.text.get().as_str(), "edited");
+        assert!(state.author.get().is_none());
+        state.reply.set(None);
+        assert!(state.reply.get().is_none());
+    });
+}
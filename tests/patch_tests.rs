@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use sycamore_state_macros::State;
+use sycamore_state_manager::{CollectionOp, MapOp, NestedPatch};
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub struct MyInnerState {
+    pub field_1: i64,
+    #[collection]
+    pub collection: Vec<String>,
+}
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub struct MyState {
+    pub field_1: String,
+    #[state]
+    pub field_3: MyInnerState,
+    #[state]
+    #[collection]
+    pub state_collection: Vec<MyInnerState>,
+    #[collection]
+    pub scores: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub enum Selection {
+    Nothing(bool),
+    #[state]
+    Inner(MyInnerState),
+}
+
+#[derive(Debug, State, Clone, PartialEq)]
+#[state(clone, debug)]
+pub struct Wrapper<T> {
+    pub value: T,
+    #[collection]
+    pub items: Vec<T>,
+}
+
+fn inner() -> MyInnerState {
+    MyInnerState {
+        field_1: 1,
+        collection: vec!["a".into(), "b".into(), "c".into()],
+    }
+}
+
+fn my_state() -> MyState {
+    MyState {
+        field_1: "state".into(),
+        field_3: inner(),
+        state_collection: vec![inner()],
+        scores: BTreeMap::from([("ada".into(), 1)]),
+    }
+}
+
+#[test]
+pub fn diff_test() {
+    let old = my_state();
+    assert_eq!(MyStatePatch::diff(&old, &old), MyStatePatch::default());
+
+    let mut new = my_state();
+    new.field_3.collection.pop();
+    new.state_collection.push(MyInnerState {
+        field_1: 2,
+        collection: vec![],
+    });
+    new.scores.insert("bob".into(), 2);
+    new.scores.remove("ada");
+    assert_eq!(
+        MyStatePatch::diff(&old, &new),
+        MyStatePatch {
+            field_1: None,
+            field_3: Some(NestedPatch::Patch(MyInnerStatePatch {
+                field_1: None,
+                collection: Some(vec![CollectionOp::Remove(2)]),
+            })),
+            state_collection: Some(vec![CollectionOp::Push(MyInnerState {
+                field_1: 2,
+                collection: vec![],
+            })]),
+            scores: Some(vec![
+                MapOp::Insert("bob".into(), 2),
+                MapOp::Remove("ada".into())
+            ]),
+        }
+    );
+}
+
+#[test]
+pub fn apply_patch_test() {
+    let state = RcMyState::new(my_state());
+    let field_3 = state.field_3.get();
+    let item = state.state_collection.get()[0].get();
+
+    let mut new = my_state();
+    new.field_1 = "edited".into();
+    new.field_3.field_1 = 2;
+    new.field_3.collection[1] = "z".into();
+    new.state_collection[0].collection.push("d".into());
+    new.scores.insert("ada".into(), 3);
+    state.apply_patch(MyStatePatch::diff(&my_state(), &new));
+    assert_eq!(state.snapshot(), new);
+
+    // Nested states are patched in place
+    assert!(Rc::ptr_eq(&field_3, &state.field_3.get()));
+    assert!(Rc::ptr_eq(&item, &state.state_collection.get()[0].get()));
+
+    state.apply_patch(MyStatePatch {
+        field_3: Some(NestedPatch::Set(inner())),
+        state_collection: Some(vec![CollectionOp::Set(0, inner())]),
+        ..Default::default()
+    });
+    assert_eq!(state.field_3.get().snapshot(), inner());
+    assert_eq!(state.state_collection.get()[0].get().snapshot(), inner());
+}
+
+#[test]
+pub fn enum_patch_test() {
+    let old = Selection::Inner(inner());
+    let mut new = inner();
+    new.field_1 = 5;
+    let patch = SelectionPatch::diff(&old, &Selection::Inner(new.clone())).unwrap();
+    assert_eq!(
+        patch,
+        SelectionPatch::Inner(Some(NestedPatch::Patch(MyInnerStatePatch {
+            field_1: Some(5),
+            collection: None,
+        })))
+    );
+
+    let state = RcSelection::new(old.clone());
+    state.apply_patch(patch);
+    assert_eq!(state.snapshot(), Selection::Inner(new));
+
+    assert_eq!(SelectionPatch::diff(&old, &Selection::Nothing(true)), None);
+}
+
+#[test]
+pub fn generic_patch_test() {
+    let old = Wrapper {
+        value: 1u8,
+        items: vec![1, 2],
+    };
+    let new = Wrapper {
+        value: 2u8,
+        items: vec![3, 2, 4],
+    };
+    let patch = WrapperPatch::diff(&old, &new);
+    assert_eq!(patch.value, Some(2));
+    assert_eq!(
+        patch.items,
+        Some(vec![CollectionOp::Set(0, 3), CollectionOp::Push(4)])
+    );
+
+    let state = RcWrapper::new(old);
+    state.apply_patch(patch);
+    assert_eq!(state.snapshot(), new);
+}
+
+#[test]
+fn out_of_range_patch_test() {
+    let state = RcWrapper::new(Wrapper {
+        value: 1u8,
+        items: vec![1],
+    });
+    state.apply_patch(WrapperPatch {
+        items: Some(vec![CollectionOp::Remove(3), CollectionOp::Set(3, 2)]),
+        ..Default::default()
+    });
+    assert_eq!(state.snapshot().items, vec![1]);
+}
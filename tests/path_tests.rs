@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+use sycamore::reactive::{create_ref, create_scope};
+use sycamore_state_macros::State;
+use sycamore_state_manager::CollectionPath::{Index, Item, Items};
+use sycamore_state_manager::MapPath;
+
+#[derive(Debug, State, Clone)]
+#[state(clone, debug)]
+pub struct MyInnerState {
+    pub field_1: i64,
+    #[collection]
+    pub collection: Vec<String>,
+}
+
+#[derive(Debug, State, Clone)]
+#[state(clone, debug)]
+pub struct MyState {
+    pub field_1: String,
+    #[state]
+    pub field_3: MyInnerState,
+    #[state]
+    #[collection]
+    pub state_collection: Vec<MyInnerState>,
+    #[collection]
+    pub scores: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, State, Clone)]
+#[state(clone, debug)]
+pub enum Selection {
+    Nothing(bool),
+    #[state]
+    Inner(MyInnerState),
+}
+
+#[derive(Debug, State, Clone)]
+#[state(clone, debug)]
+pub struct Wrapper<T> {
+    pub value: T,
+    #[collection]
+    pub items: Vec<T>,
+}
+
+fn inner() -> MyInnerState {
+    MyInnerState {
+        field_1: 1,
+        collection: vec!["a".into(), "b".into(), "c".into()],
+    }
+}
+
+fn my_state() -> MyState {
+    MyState {
+        field_1: "state".into(),
+        field_3: inner(),
+        state_collection: vec![inner()],
+        scores: BTreeMap::from([("ada".into(), 1)]),
+    }
+}
+
+#[test]
+pub fn rc_on_change_test() {
+    let _ = create_scope(|cx| {
+        let state = RcMyState::new(my_state());
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        state.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        state.field_1.set("edited".into());
+        state.field_3.get().collection.get()[2].set("z".into());
+        state.field_3.get().collection.push("d".into());
+        state.state_collection.get()[0].get().field_1.set(2);
+        state.scores.insert("ada".into(), 2);
+        state.scores.insert("bob".into(), 1);
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                MyStateField::Field1,
+                MyStateField::Field3(Some(MyInnerStateField::Collection(Index(2)))),
+                MyStateField::Field3(Some(MyInnerStateField::Collection(Items))),
+                MyStateField::StateCollection(Item(0, MyInnerStateField::Field1)),
+                MyStateField::Scores(MapPath::Key("ada".into())),
+                MyStateField::Scores(MapPath::Entries),
+            ]
+        );
+    });
+}
+
+#[test]
+pub fn replaced_state_test() {
+    let _ = create_scope(|cx| {
+        let state = RcMyState::new(my_state());
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        state.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        let old = state.field_3.get();
+        state.field_3.set(RcMyInnerState::new(inner()));
+        old.field_1.set(5);
+        state.field_3.get().field_1.set(6);
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                MyStateField::Field3(None),
+                MyStateField::Field3(Some(MyInnerStateField::Field1)),
+            ]
+        );
+    });
+}
+
+#[test]
+pub fn ref_on_change_test() {
+    let _ = create_scope(|cx| {
+        let selection = RefSelection::new(cx, Selection::Inner(inner()));
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        selection.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        if let RefSelection::Inner(data) = &selection {
+            data.get().field_1.set(3);
+        }
+        assert_eq!(
+            *changes.borrow(),
+            vec![SelectionField::Inner(Some(MyInnerStateField::Field1))]
+        );
+    });
+}
+
+#[test]
+pub fn generic_on_change_test() {
+    let _ = create_scope(|cx| {
+        let state = RcWrapper::new(Wrapper {
+            value: 1,
+            items: vec![1, 2],
+        });
+        let changes = create_ref(cx, RefCell::new(Vec::new()));
+        state.on_change(cx, |path| changes.borrow_mut().push(path));
+
+        state.items.get()[1].set(3);
+        assert_eq!(*changes.borrow(), vec![WrapperField::Items(Index(1))]);
+    });
+}
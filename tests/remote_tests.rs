@@ -0,0 +1,79 @@
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+mod foreign {
+    #[derive(Clone)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[derive(Clone)]
+    pub enum Shape {
+        Dot(Point),
+        Circle(u32),
+    }
+}
+
+#[derive(State, Clone)]
+#[state(clone, remote = foreign::Point)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(State, Clone)]
+#[state(remote = foreign::Shape)]
+pub enum Shape {
+    #[state(rc = RcPoint, ref = RefPoint)]
+    Dot(foreign::Point),
+    Circle(u32),
+}
+
+pub type Position = foreign::Point;
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Marker {
+    #[state(rc = RcPoint, ref = RefPoint)]
+    pub position: Position,
+    #[state(rc = RcPoint, ref = RefPoint)]
+    #[collection]
+    pub path: Vec<foreign::Point>,
+}
+
+fn marker() -> Marker {
+    Marker {
+        position: foreign::Point { x: 1, y: 2 },
+        path: vec![foreign::Point { x: 0, y: 0 }],
+    }
+}
+
+#[test]
+fn rc_remote_test() {
+    let point = RcPoint::new(foreign::Point { x: 3, y: 4 });
+    assert_eq!(*point.x.get() + *point.y.get(), 7);
+
+    match RcShape::new(foreign::Shape::Dot(foreign::Point { x: 1, y: 1 })) {
+        RcShape::Dot(point) => assert_eq!(*point.get().x.get(), 1),
+        RcShape::Circle(_) => unreachable!(),
+    }
+
+    let state = RcMarker::new(marker());
+    assert_eq!(*state.position.get().y.get(), 2);
+    assert_eq!(*state.path.get()[0].get().x.get(), 0);
+}
+
+#[test]
+fn ref_remote_test() {
+    _ = create_scope(|cx| {
+        let state = RefMarker::new(cx, marker());
+        state.position.get().x.set(10);
+        assert_eq!(*state.position.get().x.get(), 10);
+
+        let RefShape::Circle(radius) = RefShape::new(cx, foreign::Shape::Circle(5)) else {
+            panic!("expected a circle");
+        };
+        assert_eq!(*radius.get(), 5);
+    });
+}
@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+use sycamore::reactive::{create_effect, create_ref, create_scope};
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Notification {
+    pub message: String,
+}
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct LogState {
+    #[collection(capacity = 3)]
+    pub lines: Vec<String>,
+    #[state]
+    #[collection(capacity = 1)]
+    pub notifications: VecDeque<Notification>,
+}
+
+fn log_state() -> LogState {
+    LogState {
+        lines: vec!["a".into(), "b".into()],
+        notifications: VecDeque::from([Notification {
+            message: "first".into(),
+        }]),
+    }
+}
+
+#[test]
+fn ref_ring_eviction_test() {
+    let _ = create_scope(|cx| {
+        let state = RefLogState::new(cx, log_state());
+        let evicted = create_ref(cx, RefCell::new(Vec::new()));
+        create_effect(cx, move || {
+            let lines = state.lines.evicted().get();
+            evicted.borrow_mut().extend(lines.iter().map(|a| a.to_string()));
+        });
+
+        state.lines.push_back("c".into());
+        assert!(evicted.borrow().is_empty());
+        state.lines.push_back("d".into());
+        state.lines.push_front("z".into());
+        assert_eq!(*evicted.borrow(), vec!["a", "d"]);
+
+        let lines = state.lines.get();
+        let lines = lines.iter().map(|a| a.get().to_string());
+        assert_eq!(lines.collect::<Vec<_>>(), vec!["z", "b", "c"]);
+    });
+}
+
+#[test]
+fn state_ring_eviction_test() {
+    let state = RcLogState::new(log_state());
+    let first = state.notifications.get()[0].get();
+    state
+        .notifications
+        .push_back(RcNotification::new(Notification {
+            message: "second".into(),
+        }));
+
+    let evicted = state.notifications.evicted().get();
+    assert_eq!(evicted[0].message.get().as_str(), "first");
+    first.message.set("still live".into());
+    assert_eq!(evicted[0].message.get().as_str(), "still live");
+
+    let snapshot = state.snapshot();
+    assert_eq!(snapshot.notifications.len(), 1);
+    assert_eq!(snapshot.notifications[0].message, "second");
+    assert_eq!(snapshot.lines, vec!["a", "b"]);
+}
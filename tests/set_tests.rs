@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashSet};
+
+use sycamore::reactive::{create_effect, create_ref, create_scope};
+use sycamore_state_macros::State;
+
+#[derive(State, Clone, Debug, PartialEq)]
+#[state(clone)]
+pub struct Filters {
+    #[set]
+    pub tags: BTreeSet<String>,
+    #[set]
+    pub selected: HashSet<u32>,
+}
+
+fn filters() -> Filters {
+    Filters {
+        tags: BTreeSet::from(["b".into(), "a".into()]),
+        selected: HashSet::from([1, 2]),
+    }
+}
+
+#[test]
+fn member_subscription_test() {
+    let _ = create_scope(|cx| {
+        let state = RefFilters::new(cx, filters());
+        let member_runs = create_ref(cx, Cell::new(0));
+        let set_runs = create_ref(cx, Cell::new(0));
+        create_effect(cx, move || {
+            state.selected.contains(&2);
+            member_runs.set(member_runs.get() + 1);
+        });
+        create_effect(cx, move || {
+            state.selected.track();
+            set_runs.set(set_runs.get() + 1);
+        });
+
+        state.selected.insert(3);
+        state.selected.remove(&1);
+        state.selected.insert(3);
+        assert_eq!(member_runs.get(), 1);
+        assert_eq!(set_runs.get(), 3);
+
+        assert!(!state.selected.toggle(2));
+        assert!(!state.selected.contains(&2));
+        assert_eq!(member_runs.get(), 2);
+        assert_eq!(set_runs.get(), 4);
+    });
+}
+
+#[test]
+fn set_fields_convert_test() {
+    let _ = create_scope(|cx| {
+        let state = RcFilters::new(filters());
+        assert!(state.tags.toggle("c".into()));
+        assert!(!state.tags.toggle("a".into()));
+        state.selected.extend([2, 3]);
+
+        let mut expected = filters();
+        expected.tags = BTreeSet::from(["b".into(), "c".into()]);
+        expected.selected.insert(3);
+        assert_eq!(state.snapshot(), expected);
+
+        let copy = state.to_ref(cx);
+        assert!(state.tags.symmetric_difference(copy.tags).is_empty());
+        copy.tags.insert("d".into());
+        assert_eq!(copy.tags.difference(&state.tags), BTreeSet::from(["d".into()]));
+        assert!(state.tags.is_subset(copy.tags));
+    });
+}
@@ -0,0 +1,81 @@
+use sycamore::reactive::create_scope;
+use sycamore_state_macros::State;
+
+#[derive(State, Clone, PartialEq)]
+#[state(clone)]
+pub struct Player {
+    pub name: String,
+    pub score: u32,
+}
+
+#[derive(State, Clone, PartialEq)]
+#[state(clone)]
+pub struct Leaderboard {
+    #[collection(sorted_by = |a, b| a.cmp(b))]
+    pub timestamps: Vec<u64>,
+    #[state]
+    #[collection(sorted_by = |a, b| b.score.get().cmp(&a.score.get()))]
+    pub players: Vec<Player>,
+}
+
+fn leaderboard() -> Leaderboard {
+    Leaderboard {
+        timestamps: vec![30, 10, 20],
+        players: vec![
+            Player {
+                name: "a".into(),
+                score: 10,
+            },
+            Player {
+                name: "b".into(),
+                score: 20,
+            },
+        ],
+    }
+}
+
+#[test]
+fn rc_sorted_test() {
+    let state = RcLeaderboard::new(leaderboard());
+    state.timestamps.insert(15);
+    let timestamps = state
+        .timestamps
+        .get()
+        .iter()
+        .map(|a| *a.get())
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps, vec![10, 15, 20, 30]);
+    assert_eq!(state.timestamps.range(12..=20).len(), 2);
+
+    let first = state.players.first().expect("first player");
+    assert_eq!(first.get().name.get().as_str(), "b");
+    state.players.get()[1].get().score.set(30);
+    let first = state.players.first().expect("first player");
+    assert_eq!(first.get().name.get().as_str(), "a");
+
+    state.apply_patch(LeaderboardPatch {
+        timestamps: Some(vec![5, 1]),
+        ..Default::default()
+    });
+    let timestamps = state
+        .timestamps
+        .get()
+        .iter()
+        .map(|a| *a.get())
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps, vec![1, 5]);
+}
+
+#[test]
+fn ref_sorted_test() {
+    _ = create_scope(|cx| {
+        let state = RefLeaderboard::new(cx, leaderboard());
+        let item = state.timestamps.get()[0].clone();
+        item.set(40);
+        assert_eq!(item.index(), Some(2));
+        assert_eq!(state.players.len(), 2);
+        assert_eq!(state.players.get()[0].get().score.get().as_ref(), &20);
+        state.players.get()[0].get().score.set(5);
+        assert_eq!(state.players.get()[0].get().name.get().as_str(), "a");
+    });
+}
@@ -0,0 +1,109 @@
+use std::cell::Cell;
+
+use sycamore::reactive::{create_effect, create_ref, create_scope, Scope};
+use sycamore_state_macros::State;
+use sycamore_state_manager::{RcCollectionSignal, RefCollectionSignal, StateField};
+
+/// Stores a comma separated list as a collection with a signal per tag
+pub struct Tags;
+
+impl StateField<String> for Tags {
+    type Rc = RcCollectionSignal<String>;
+    type Ref<'a> = RefCollectionSignal<'a, String>;
+
+    fn new_rc(value: String) -> Self::Rc {
+        RcCollectionSignal::new(value.split(',').map(Into::into))
+    }
+
+    fn new_ref<'a>(cx: Scope<'a>, value: String) -> Self::Ref<'a> {
+        RefCollectionSignal::new(cx, value.split(',').map(Into::into))
+    }
+
+    fn snapshot_rc(field: &Self::Rc) -> String {
+        let tags = field.get_untracked();
+        let tags = tags.iter().map(|a| a.get_untracked().as_ref().clone());
+        tags.collect::<Vec<_>>().join(",")
+    }
+
+    fn snapshot_ref(field: &Self::Ref<'_>) -> String {
+        let tags = field.get_untracked();
+        let tags = tags.iter().map(|a| a.get_untracked().as_ref().clone());
+        tags.collect::<Vec<_>>().join(",")
+    }
+
+    fn set_rc(field: &Self::Rc, value: String) {
+        field.set(Self::new_rc(value).get_untracked().as_ref().clone());
+    }
+
+    fn track_rc(field: &Self::Rc) {
+        field.get().iter().for_each(|a| a.track());
+    }
+
+    fn track_ref(field: &Self::Ref<'_>) {
+        field.get().iter().for_each(|a| a.track());
+    }
+}
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Issue {
+    pub title: String,
+    #[state(with = Tags)]
+    pub tags: String,
+}
+
+#[derive(State, Clone)]
+pub enum Label {
+    #[state(with = Tags)]
+    Many(String),
+    None(bool),
+}
+
+fn issue() -> Issue {
+    Issue {
+        title: "Crash".into(),
+        tags: "bug,ui".into(),
+    }
+}
+
+#[test]
+fn rc_state_field_test() {
+    let state = RcIssue::new(issue());
+    state.tags.get()[1].set("backend".into());
+    state.tags.push("urgent".into());
+    assert_eq!(state.snapshot().tags, "bug,backend,urgent");
+    assert_eq!(state.snapshot().title, "Crash");
+
+    let patch = IssuePatch::diff(&state.snapshot(), &issue());
+    assert_eq!(patch.tags.as_deref(), Some("bug,ui"));
+    state.apply_patch(patch);
+    assert_eq!(state.snapshot().tags, "bug,ui");
+
+    let RcLabel::Many(tags) = RcLabel::new(Label::Many("a,b".into())) else {
+        panic!("expected many");
+    };
+    assert_eq!(tags.get().len(), 2);
+}
+
+#[test]
+fn ref_state_field_test() {
+    let _ = create_scope(|cx| {
+        let state = RefIssue::new(cx, issue());
+        state.tags.get()[0].set("feature".into());
+        assert_eq!(state.snapshot().tags, "feature,ui");
+
+        let runs = create_ref(cx, Cell::new(0));
+        create_effect(cx, move || {
+            state.track();
+            runs.set(runs.get() + 1);
+        });
+        state.tags.get()[1].set("docs".into());
+        state.tags.push_value(cx, "easy".into());
+        assert_eq!(runs.get(), 3);
+
+        let RefLabel::Many(tags) = RefLabel::new(cx, Label::Many("a".into())) else {
+            panic!("expected many");
+        };
+        assert_eq!(Tags::snapshot_ref(&tags), "a");
+    });
+}
@@ -0,0 +1,99 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+use sycamore::reactive::{create_effect, create_ref, create_scope};
+use sycamore_state_macros::State;
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Field {
+    pub value: String,
+}
+
+#[derive(State, Clone)]
+#[state(clone)]
+pub struct Form {
+    pub title: String,
+    #[state]
+    pub primary: Field,
+    #[state]
+    pub secondary: Option<Field>,
+    #[state]
+    #[collection]
+    pub extra: Vec<Field>,
+    #[collection]
+    pub counts: BTreeMap<u32, u32>,
+}
+
+fn form() -> Form {
+    Form {
+        title: "signup".into(),
+        primary: Field {
+            value: "ada".into(),
+        },
+        secondary: Some(Field { value: "".into() }),
+        extra: vec![Field { value: "x".into() }],
+        counts: BTreeMap::from([(1, 1)]),
+    }
+}
+
+#[test]
+pub fn rc_track_test() {
+    let _ = create_scope(|cx| {
+        let state = RcForm::new(form());
+        let runs = create_ref(cx, Cell::new(0));
+        create_effect(cx, {
+            let state = state.clone();
+            move || {
+                state.track();
+                runs.set(runs.get() + 1);
+            }
+        });
+        assert_eq!(runs.get(), 1);
+
+        state.title.set("login".into());
+        assert_eq!(runs.get(), 2);
+        state.primary.get().value.set("bob".into());
+        assert_eq!(runs.get(), 3);
+        let secondary = state.secondary.get();
+        secondary.as_ref().as_ref().unwrap().value.set("b".into());
+        assert_eq!(runs.get(), 4);
+        state.extra.get()[0].get().value.set("y".into());
+        assert_eq!(runs.get(), 5);
+        state.counts.insert(1, 2);
+        assert_eq!(runs.get(), 6);
+    });
+}
+
+#[test]
+pub fn ref_on_any_change_test() {
+    let _ = create_scope(|cx| {
+        let state = RefForm::new(cx, form());
+        let changes = create_ref(cx, Cell::new(0));
+        state.on_any_change(cx, || changes.set(changes.get() + 1));
+        assert_eq!(changes.get(), 0);
+
+        state.primary.get().value.set("bob".into());
+        assert_eq!(changes.get(), 1);
+        state.extra.get()[0].get().value.set("y".into());
+        assert_eq!(changes.get(), 2);
+        state.extra.remove(0);
+        assert_eq!(changes.get(), 3);
+    });
+}
+
+#[derive(State, Clone)]
+pub struct Counter<T> {
+    pub value: T,
+}
+
+#[test]
+pub fn generic_on_any_change_test() {
+    let _ = create_scope(|cx| {
+        let state = RefCounter::new(cx, Counter { value: 1 });
+        let changes = create_ref(cx, Cell::new(0));
+        state.on_any_change(cx, || changes.set(changes.get() + 1));
+        state.value.set(2);
+        assert_eq!(changes.get(), 1);
+    });
+}